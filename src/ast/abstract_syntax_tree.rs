@@ -1,12 +1,15 @@
+use crate::ast::import_statement::ImportStatement;
 use crate::ast::let_statement::{LetStatement, LetsStatement};
 use crate::ast::module_statement::ModuleStatement;
 use crate::ast::parser::Parse;
 use crate::error::CResult;
-use crate::lex::token::Token;
+use crate::lex::token::{Kw, Token};
 use crate::lex::tokens::Tokens;
 
 /// The abstract syntax tree of a whole program, containing all statements and expressions.
+#[derive(Debug)]
 pub struct AbstractSyntaxTree {
+    pub imports: Vec<ImportStatement>,
     pub mods: Vec<ModuleStatement>,
     pub lets: Vec<LetStatement>,
 }
@@ -14,10 +17,30 @@ pub struct AbstractSyntaxTree {
 impl AbstractSyntaxTree {
     pub fn new() -> Self {
         Self {
+            imports: vec![],
             mods: vec![],
             lets: vec![],
         }
     }
+
+    /// Adds the modules and lets of another tree, with their names moved into the namespace.
+    pub fn append(&mut self, other: AbstractSyntaxTree, namespace: &str) {
+        let qualify = |name: &mut String| {
+            if !namespace.is_empty() {
+                *name = format!("{}\\{}", namespace, name);
+            }
+        };
+
+        for mut module in other.mods {
+            qualify(&mut module.name);
+            self.mods.push(module);
+        }
+
+        for mut lett in other.lets {
+            qualify(&mut lett.name);
+            self.lets.push(lett);
+        }
+    }
 }
 
 impl Parse for AbstractSyntaxTree {
@@ -29,15 +52,32 @@ impl Parse for AbstractSyntaxTree {
         let mut ast = AbstractSyntaxTree::new();
 
         while tokens.still_more() {
-            tokens.expect("'mod' or 'lets'");
-            if let Some(module) = ModuleStatement::maybe_parse(tokens)? {
-                ast.mods.push(module)
-            } else if let Some(mut lets) = LetsStatement::maybe_parse(tokens)? {
-                ast.lets.append(&mut lets.lets)
+            let level = tokens.level();
+            let start = tokens.position();
+
+            tokens.expect("'import', 'mod' or 'lets'");
+            let result = if ImportStatement::matches(tokens) {
+                ImportStatement::parse(tokens).map(|import| ast.imports.push(import))
+            } else if ModuleStatement::matches(tokens) {
+                ModuleStatement::parse(tokens).map(|module| ast.mods.push(module))
+            } else if LetsStatement::matches(tokens) {
+                LetsStatement::parse(tokens).map(|mut lets| ast.lets.append(&mut lets.lets))
             } else if matches!(tokens.token(), Token::Eof) {
                 break;
             } else {
-                return tokens.unexpected_token_error();
+                tokens.unexpected_token_error()
+            };
+
+            if let Err(error) = result {
+                tokens.recover(error, start, level);
+
+                // Continue at the next 'import', 'mod' or 'lets'.
+                while !matches!(
+                    tokens.token(),
+                    Token::Kw(Kw::Import | Kw::Private | Kw::Mod | Kw::Lets) | Token::Eof
+                ) {
+                    tokens.step();
+                }
             }
         }
 
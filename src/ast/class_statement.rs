@@ -1,17 +1,18 @@
 use crate::ast::parser::{parse_parameter, Parse};
 use crate::ast::type_statement::TypeStatement;
 use crate::ast::Statement;
-use std::ops::Range;
 
 use crate::error::CResult;
 use crate::lex::token::{Kw, Token};
 
 use crate::lex::tokens::Tokens;
+use crate::source::Span;
 
 /// The class keyword and its dependencies.
+#[derive(Debug)]
 pub struct ClassStatement {
     pub dependencies: Vec<(String, TypeStatement)>,
-    pub token_range: Range<usize>,
+    pub span: Span,
 }
 
 impl Parse for ClassStatement {
@@ -32,14 +33,14 @@ impl Parse for ClassStatement {
 
         let statement = ClassStatement {
             dependencies,
-            token_range: token_start..tokens.position(),
+            span: tokens.span_from(token_start),
         };
         Ok(statement)
     }
 }
 
 impl Statement for ClassStatement {
-    fn token_range(&self) -> &Range<usize> {
-        &self.token_range
+    fn span(&self) -> Span {
+        self.span
     }
 }
@@ -3,15 +3,16 @@ use crate::ast::parser::{parse_global, Parse};
 use crate::ast::Statement;
 use crate::error::CResult;
 use crate::lex::token::{Kw, Token};
-use std::ops::Range;
 
 use crate::lex::tokens::Tokens;
+use crate::source::Span;
 
 /// A single def.
+#[derive(Debug)]
 pub struct DefStatement {
     pub name: String,
     pub expr: ExpressionStatement,
-    token_range: Range<usize>,
+    span: Span,
 }
 
 /// The defs keyword and its defs.
@@ -30,8 +31,16 @@ impl Parse for DefsStatement {
 
         let mut defs = vec![];
 
+        // Recover from errors by skipping to the next item on the level of the first one.
+        let item_level = tokens.level();
+
         while tokens.deeper_than(base_level) {
-            defs.push(parse_def(tokens)?)
+            let start = tokens.position();
+
+            match parse_def(tokens) {
+                Ok(def) => defs.push(def),
+                Err(error) => tokens.recover(error, start, item_level),
+            }
         }
 
         let statement = DefsStatement { defs };
@@ -47,13 +56,13 @@ fn parse_def(tokens: &mut Tokens) -> CResult<DefStatement> {
     let statement = DefStatement {
         name,
         expr,
-        token_range: token_start..tokens.position(),
+        span: tokens.span_from(token_start),
     };
     Ok(statement)
 }
 
 impl Statement for DefStatement {
-    fn token_range(&self) -> &Range<usize> {
-        &self.token_range
+    fn span(&self) -> Span {
+        self.span
     }
 }
@@ -1,9 +1,9 @@
-use std::collections::HashMap;
 use crate::ast::expression::ExpressionStatement;
-use crate::ast::parser::{Parse, parse_global};
+use crate::ast::parser::{parse_global, Parse};
 use crate::error::CResult;
 use crate::lex::token::Token;
 use crate::lex::tokens::Tokens;
+use std::collections::HashMap;
 
 /// e.g.: Module\Function(param1: ..., param2: ...)
 /// e.g.: Module\Constant
@@ -1,4 +1,4 @@
-use crate::ast::parser::{Parse};
+use crate::ast::parser::Parse;
 use crate::ast::raw_value::RawValue;
 use crate::error::CResult;
 use crate::lex::token::{Kw, Lit, Op, Token};
@@ -6,16 +6,16 @@ use crate::lex::token::{Kw, Lit, Op, Token};
 use crate::ast::expr::match_call::MatchCall;
 
 use crate::ast::expr::if_else_call::IfElseCall;
+use crate::ast::expr::let_call::LetCall;
 use crate::ast::Statement;
 use crate::lex::tokens::Tokens;
+use crate::source::Span;
 use std::collections::HashMap;
-use std::ops::Range;
-use crate::ast::expr::let_call::LetCall;
 
 #[derive(Clone, Debug)]
 pub struct ExpressionStatement {
     pub expression: Expression,
-    token_range: Range<usize>,
+    span: Span,
 }
 
 /// An expression within the abstract syntax tree.
@@ -70,6 +70,9 @@ pub enum BinaryOp {
     Eq,
     Lt,
     Gt,
+    Ne,
+    Lte,
+    Gte,
     And,
     Or,
 }
@@ -80,6 +83,44 @@ pub enum UnaryOp {
     Not,
 }
 
+impl BinaryOp {
+    /// The binary operation of the operator, if it has one.
+    fn of(op: &Op) -> Option<Self> {
+        match op {
+            Op::Add => Some(BinaryOp::Add),
+            Op::Sub => Some(BinaryOp::Sub),
+            Op::Mul => Some(BinaryOp::Mul),
+            Op::Div => Some(BinaryOp::Div),
+            Op::Eq => Some(BinaryOp::Eq),
+            Op::Lt => Some(BinaryOp::Lt),
+            Op::Gt => Some(BinaryOp::Gt),
+            Op::Ne => Some(BinaryOp::Ne),
+            Op::Lte => Some(BinaryOp::Lte),
+            Op::Gte => Some(BinaryOp::Gte),
+            Op::And => Some(BinaryOp::And),
+            Op::Or => Some(BinaryOp::Or),
+            _ => None,
+        }
+    }
+
+    /// How tightly the operation binds its operands. Operations with a higher precedence are
+    /// applied first.
+    pub fn precedence(&self) -> usize {
+        match self {
+            BinaryOp::Or => 0,
+            BinaryOp::And => 1,
+            BinaryOp::Eq
+            | BinaryOp::Lt
+            | BinaryOp::Gt
+            | BinaryOp::Ne
+            | BinaryOp::Lte
+            | BinaryOp::Gte => 2,
+            BinaryOp::Add | BinaryOp::Sub => 3,
+            BinaryOp::Mul | BinaryOp::Div => 4,
+        }
+    }
+}
+
 impl Parse for ExpressionStatement {
     fn matches(_tokens: &Tokens) -> bool {
         true
@@ -87,146 +128,250 @@ impl Parse for ExpressionStatement {
 
     fn parse(tokens: &mut Tokens) -> CResult<Self> {
         let base_level = tokens.level();
-        let token_start = tokens.position();
 
-        // Parse first token
-        tokens.expect("an expression");
-        let mut expr = match tokens.token().clone() {
-            Token::Kw(Kw::Zelf) => {
-                tokens.step();
+        parse_operations(tokens, base_level, 0)
+    }
+}
 
-                Expression::Zelf
-            }
-            Token::Global(_) => {
-                let call = LetCall::parse(tokens)?;
+/// Parses a chain of binary operations with at least the given precedence. Operations with the
+/// same precedence are applied from left to right.
+fn parse_operations(
+    tokens: &mut Tokens,
+    base_level: usize,
+    min_precedence: usize,
+) -> CResult<ExpressionStatement> {
+    let token_start = tokens.position();
+    let mut expr = parse_operand(tokens, base_level)?;
 
-                Expression::Let(call)
-            }
-            Token::Local(name) => {
-                tokens.step();
+    while tokens.deeper_than_or_eq(base_level) {
+        let Token::Op(op) = tokens.token() else {
+            break;
+        };
+        let Some(op) = BinaryOp::of(op) else {
+            break;
+        };
 
-                Expression::Local(name)
-            }
-            Token::Lit(lit) => {
-                tokens.step();
+        let precedence = op.precedence();
+        if precedence < min_precedence {
+            break;
+        }
 
-                Expression::Literal(match lit {
-                    Lit::String(value) => RawValue::String(value),
-                    Lit::Number(value) => RawValue::Int(value as i64),
-                    Lit::Boolean(value) => RawValue::Bool(value),
-                })
-            }
-            Token::Op(Op::Dot) => {
-                // We don't step so we can reevaluate the same dot in the next step.
-                Expression::Zelf
-            }
-            Token::Op(Op::Sub) => {
-                tokens.step();
+        tokens.step();
 
-                let expr = ExpressionStatement::parse(tokens)?;
+        // The right-hand side can't be on a line less deep than the expression.
+        if !tokens.deeper_than_or_eq(base_level) {
+            tokens.expect("an expression");
+            return tokens.unexpected_token_error();
+        }
+        let rhs = parse_operations(tokens, base_level, precedence + 1)?;
 
-                Expression::Unary(UnaryCall {
-                    op: UnaryOp::Neg,
-                    subject: Box::new(expr),
-                })
-            }
-            Token::Kw(Kw::Match) => Expression::Match(MatchCall::parse(tokens)?),
-            Token::Kw(Kw::If) => Expression::IfElse(IfElseCall::parse(tokens)?),
-            Token::Op(Op::Question) => {
-                tokens.step();
-                let statement = ExpressionStatement {
-                    expression: Expression::Void,
-                    token_range: token_start..tokens.position(),
-                };
-                return Ok(statement);
-            }
-            _ => return tokens.unexpected_token_error(),
+        expr = ExpressionStatement {
+            expression: Expression::Binary(BinaryCall {
+                op,
+                lhs: Box::new(expr),
+                rhs: Box::new(rhs),
+            }),
+            span: tokens.span_from(token_start),
         };
+    }
 
-        // Parse further operations
-        while tokens.deeper_than_or_eq(base_level) {
-            expr = match tokens.token().clone() {
-                Token::Op(op) => match op {
-                    Op::Add
-                    | Op::Sub
-                    | Op::Mul
-                    | Op::Div
-                    | Op::Eq
-                    | Op::Lt
-                    | Op::Gt
-                    | Op::And
-                    | Op::Or => {
-                        tokens.step();
-
-                        let lhs = ExpressionStatement {
-                            expression: expr,
-                            token_range: token_start..tokens.position(),
-                        };
-                        let rhs = ExpressionStatement::parse(tokens)?;
-
-                        Expression::Binary(BinaryCall {
-                            op: match op {
-                                Op::Add => BinaryOp::Add,
-                                Op::Sub => BinaryOp::Sub,
-                                Op::Mul => BinaryOp::Mul,
-                                Op::Div => BinaryOp::Div,
-                                Op::Eq => BinaryOp::Eq,
-                                Op::Lt => BinaryOp::Lt,
-                                Op::Gt => BinaryOp::Gt,
-                                Op::And => BinaryOp::And,
-                                Op::Or => BinaryOp::Or,
-                                _ => unreachable!(),
-                            },
-                            lhs: Box::new(lhs),
-                            rhs: Box::new(rhs),
-                        })
-                    }
-                    Op::Dot => {
-                        tokens.step();
-
-                        tokens.expect("a trait name or a friendly field name");
-                        match (expr, tokens.token().clone()) {
-                            (Expression::Local(local_name), Token::Local(field_name)) => {
-                                tokens.step();
-
-                                Expression::FriendlyField(FriendlyField {
-                                    local_name,
-                                    field_name,
-                                })
-                            }
-                            (expr, Token::Global(_)) => {
-                                let call = LetCall::parse(tokens)?;
-
-                                let subject = ExpressionStatement {
-                                    expression: expr,
-                                    token_range: token_start..tokens.position(),
-                                };
-
-                                Expression::Def(DefCall {
-                                    name: call.name,
-                                    subject: Box::new(subject),
-                                    inputs: call.inputs,
-                                })
-                            }
-                            _ => return tokens.unexpected_token_error(),
-                        }
-                    }
-                    _ => break,
-                },
-                _ => break,
-            }
+    Ok(expr)
+}
+
+/// Parses an operand of the binary operations: a value with the traits called on it. An operand
+/// which is deeper than the operations, within parentheses or on a deeper line, is a whole
+/// expression of its own.
+fn parse_operand(tokens: &mut Tokens, base_level: usize) -> CResult<ExpressionStatement> {
+    let token_start = tokens.position();
+
+    // Parse first token
+    tokens.expect("an expression");
+    let mut expr = match tokens.token().clone() {
+        _ if tokens.deeper_than(base_level) => ExpressionStatement::parse(tokens)?.expression,
+        Token::Kw(Kw::Zelf) => {
+            tokens.step();
+
+            Expression::Zelf
+        }
+        Token::Global(_) => {
+            let call = LetCall::parse(tokens)?;
+
+            Expression::Let(call)
         }
+        Token::Local(name) => {
+            tokens.step();
 
-        let statement = ExpressionStatement {
+            Expression::Local(name)
+        }
+        Token::Lit(lit) => {
+            tokens.step();
+
+            Expression::Literal(match lit {
+                Lit::String(value) => RawValue::String(value),
+                Lit::Number(value) => RawValue::Int(value),
+                Lit::Boolean(value) => RawValue::Bool(value),
+            })
+        }
+        Token::Op(Op::Dot) => {
+            // We don't step so we can reevaluate the same dot in the next step.
+            Expression::Zelf
+        }
+        Token::Op(op @ (Op::Sub | Op::Not)) => {
+            let op = match op {
+                Op::Sub => UnaryOp::Neg,
+                _ => UnaryOp::Not,
+            };
+            tokens.step();
+
+            let expr = parse_operand(tokens, base_level)?;
+
+            Expression::Unary(UnaryCall {
+                op,
+                subject: Box::new(expr),
+            })
+        }
+        Token::Kw(Kw::Match) => Expression::Match(MatchCall::parse(tokens)?),
+        Token::Kw(Kw::If) => Expression::IfElse(IfElseCall::parse(tokens)?),
+        Token::Op(Op::Question) => {
+            tokens.step();
+            let statement = ExpressionStatement {
+                expression: Expression::Void,
+                span: tokens.span_from(token_start),
+            };
+            return Ok(statement);
+        }
+        _ => return tokens.unexpected_token_error(),
+    };
+
+    // Parse calls of traits
+    while tokens.deeper_than_or_eq(base_level) && tokens.token() == &Token::Op(Op::Dot) {
+        let subject = ExpressionStatement {
             expression: expr,
-            token_range: token_start..tokens.position(),
+            span: tokens.span_from(token_start),
         };
-        Ok(statement)
+
+        tokens.step();
+
+        tokens.expect("a trait name or a friendly field name");
+        expr = match (subject.expression, tokens.token().clone()) {
+            (Expression::Local(local_name), Token::Local(field_name)) => {
+                tokens.step();
+
+                Expression::FriendlyField(FriendlyField {
+                    local_name,
+                    field_name,
+                })
+            }
+            (expression, Token::Global(_)) => {
+                let call = LetCall::parse(tokens)?;
+
+                Expression::Def(DefCall {
+                    name: call.name,
+                    subject: Box::new(ExpressionStatement {
+                        expression,
+                        span: subject.span,
+                    }),
+                    inputs: call.inputs,
+                })
+            }
+            _ => return tokens.unexpected_token_error(),
+        }
     }
+
+    let statement = ExpressionStatement {
+        expression: expr,
+        span: tokens.span_from(token_start),
+    };
+    Ok(statement)
 }
 
 impl Statement for ExpressionStatement {
-    fn token_range(&self) -> &Range<usize> {
-        &self.token_range
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ast::expression::{Expression, ExpressionStatement};
+    use crate::ast::parser::Parse;
+    use crate::ast::raw_value::RawValue;
+    use crate::lex::tokenizer::tokenize;
+    use crate::source::SourceMap;
+
+    /// The expression with each operation within parentheses.
+    fn grouped(expr: &ExpressionStatement) -> String {
+        match &expr.expression {
+            Expression::Binary(call) => format!(
+                "({} {:?} {})",
+                grouped(&call.lhs),
+                call.op,
+                grouped(&call.rhs)
+            ),
+            Expression::Unary(call) => format!("{:?} {}", call.op, grouped(&call.subject)),
+            Expression::Def(call) => format!("{}.{}", grouped(&call.subject), call.name),
+            Expression::Local(name) => name.clone(),
+            Expression::Literal(RawValue::Int(value)) => value.to_string(),
+            expression => format!("{:?}", expression),
+        }
+    }
+
+    fn parse(code: &str) -> String {
+        let mut tokens = tokenize(&SourceMap::single("test.compost", code), 0).unwrap();
+        let expr = ExpressionStatement::parse(&mut tokens).unwrap();
+        assert_eq!(
+            tokens.remaining().len(),
+            1,
+            "Not all of '{}' was parsed",
+            code
+        );
+
+        grouped(&expr)
+    }
+
+    #[test]
+    fn test_left_associativity() {
+        assert_eq!(parse("10 - 2 - 3"), "((10 Sub 2) Sub 3)");
+        assert_eq!(parse("a / b * c / d"), "(((a Div b) Mul c) Div d)");
+        assert_eq!(parse("a < b = c > d"), "(((a Lt b) Eq c) Gt d)");
+        assert_eq!(parse("a | b | c"), "((a Or b) Or c)");
+    }
+
+    #[test]
+    fn test_precedence() {
+        assert_eq!(parse("1 + 2 * 3 = 7"), "((1 Add (2 Mul 3)) Eq 7)");
+        assert_eq!(
+            parse("a | b & c = d + e * f"),
+            "(a Or (b And (c Eq (d Add (e Mul f)))))"
+        );
+        assert_eq!(
+            parse("a * b + c * d - e / f"),
+            "(((a Mul b) Add (c Mul d)) Sub (e Div f))"
+        );
+        assert_eq!(
+            parse("a = b & c < d | e > f & g"),
+            "(((a Eq b) And (c Lt d)) Or ((e Gt f) And g))"
+        );
+        assert_eq!(parse("-a * b + c.D"), "((Neg a Mul b) Add c.D)");
+    }
+
+    #[test]
+    fn test_negation_and_comparisons() {
+        assert_eq!(parse("a != b + c"), "(a Ne (b Add c))");
+        assert_eq!(parse("a <= b & c >= d"), "((a Lte b) And (c Gte d))");
+        assert_eq!(parse("!a & not b.C"), "(Not a And Not b.C)");
+        assert_eq!(parse("a | !(b = c)"), "(a Or Not (b Eq c))");
+    }
+
+    #[test]
+    fn test_groups() {
+        assert_eq!(parse("10 - (2 - 3)"), "(10 Sub (2 Sub 3))");
+        assert_eq!(parse("a * (b + c) * d"), "((a Mul (b Add c)) Mul d)");
+        assert_eq!(parse("-(a + b) * c"), "(Neg (a Add b) Mul c)");
+        assert_eq!(parse("a + (b + c).D"), "(a Add (b Add c).D)");
+
+        // An operand on a deeper line is a whole expression of its own.
+        assert_eq!(parse("a *\n    b + c\n+ d"), "((a Mul (b Add c)) Add d)");
+        assert_eq!(parse("a\n- b\n- c * d"), "((a Sub b) Sub (c Mul d))");
     }
 }
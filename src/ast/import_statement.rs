@@ -0,0 +1,49 @@
+use crate::ast::parser::{parse_global, Parse};
+use crate::ast::Statement;
+use crate::error::CResult;
+use crate::lex::token::{Kw, Token};
+use crate::lex::tokens::Tokens;
+use crate::source::Span;
+
+/// An import of the modules and lets of another file, as in 'import Geometry\Point'.
+#[derive(Debug)]
+pub struct ImportStatement {
+    /// The path of the file relative to the importing one, separated by backslashes.
+    pub path: String,
+    span: Span,
+}
+
+impl ImportStatement {
+    /// The parts of the path, of which the last one is the name of the file.
+    pub fn segments(&self) -> Vec<&str> {
+        self.path
+            .split('\\')
+            .filter(|segment| !segment.is_empty())
+            .collect()
+    }
+}
+
+impl Statement for ImportStatement {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+
+impl Parse for ImportStatement {
+    fn matches(tokens: &Tokens) -> bool {
+        matches!(tokens.token(), Token::Kw(Kw::Import))
+    }
+
+    fn parse(tokens: &mut Tokens) -> CResult<Self> {
+        let token_start = tokens.position();
+        tokens.step();
+
+        tokens.expect("a path to a file, as in 'Geometry\\Point'");
+        let path = parse_global(tokens)?;
+
+        Ok(ImportStatement {
+            path,
+            span: tokens.span_from(token_start),
+        })
+    }
+}
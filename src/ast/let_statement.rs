@@ -1,20 +1,23 @@
 use crate::ast::expression::ExpressionStatement;
-use crate::ast::parser::{parse_global, parse_in_out_types, Parse};
+use crate::ast::parser::{parse_global, parse_in_out_types, parse_private, Parse};
 use crate::ast::type_statement::TypeStatement;
 use crate::ast::Statement;
 use crate::error::CResult;
 use crate::lex::token::{Kw, Token};
-use std::ops::Range;
 
 use crate::lex::tokens::Tokens;
+use crate::source::Span;
 
 /// A single let which is made up of a name, optional parameters, an output type and the expression.
+#[derive(Debug)]
 pub struct LetStatement {
     pub name: String,
+    /// Whether the let is only visible where it is declared.
+    pub private: bool,
     pub parameters: Vec<(String, TypeStatement)>,
     pub output: TypeStatement,
     pub expr: ExpressionStatement,
-    token_range: Range<usize>,
+    span: Span,
 }
 
 /// The lets keywords and its lets.
@@ -39,8 +42,16 @@ impl Parse for LetsStatement {
 
         let mut statement = LetsStatement::new();
 
+        // Recover from errors by skipping to the next item on the level of the first one.
+        let item_level = tokens.level();
+
         while tokens.deeper_than(base_level) {
-            statement.lets.push(parse_let(tokens)?)
+            let start = tokens.position();
+
+            match parse_let(tokens) {
+                Ok(lett) => statement.lets.push(lett),
+                Err(error) => tokens.recover(error, start, item_level),
+            }
         }
 
         Ok(statement)
@@ -51,6 +62,7 @@ fn parse_let(tokens: &mut Tokens) -> CResult<LetStatement> {
     let base_level = tokens.level();
     let token_start = tokens.position();
 
+    let private = parse_private(tokens);
     tokens.expect("the name of a let (Starting with an upper-case letter)");
     let name = parse_global(tokens)?;
     let (parameters, output) = parse_in_out_types(tokens, base_level)?;
@@ -58,16 +70,17 @@ fn parse_let(tokens: &mut Tokens) -> CResult<LetStatement> {
 
     let statement = LetStatement {
         name,
+        private,
         parameters,
         output,
         expr,
-        token_range: token_start..tokens.position(),
+        span: tokens.span_from(token_start),
     };
     Ok(statement)
 }
 
 impl Statement for LetStatement {
-    fn token_range(&self) -> &Range<usize> {
-        &self.token_range
+    fn span(&self) -> Span {
+        self.span
     }
 }
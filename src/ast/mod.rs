@@ -1,12 +1,12 @@
-use crate::error::ErrorContext::TokenRange;
-use crate::error::{CResult, CompilationError, ErrorMessage, ErrorContext};
-use std::ops::Range;
+use crate::error::{CResult, CompilationError, ErrorMessage};
+use crate::source::Span;
 
 pub(crate) mod abstract_syntax_tree;
 pub(crate) mod class_statement;
 pub(crate) mod def_statement;
 pub(crate) mod expr;
 pub(crate) mod expression;
+pub(crate) mod import_statement;
 pub(crate) mod let_statement;
 pub(crate) mod module_statement;
 pub(crate) mod parser;
@@ -17,16 +17,14 @@ pub(crate) mod type_statement;
 pub(crate) mod using_statement;
 
 pub(crate) trait Statement {
-    fn token_range(&self) -> &Range<usize>;
-
-    fn error_context(&self) -> ErrorContext {
-        TokenRange(self.token_range().clone())
-    }
+    /// Where the statement is in the source files.
+    fn span(&self) -> Span;
 
     fn error<T>(&self, message: ErrorMessage) -> CResult<T> {
         Err(CompilationError {
             message,
-            context: Some(self.error_context()),
+            context: Some(self.span()),
+            annotations: vec![],
         })
     }
 }
@@ -1,57 +1,67 @@
 use crate::ast::class_statement::ClassStatement;
 use crate::ast::def_statement::{DefStatement, DefsStatement};
 use crate::ast::let_statement::{LetStatement, LetsStatement};
-use crate::ast::parser::{parse_global, Parse};
+use crate::ast::parser::{parse_global, parse_private, Parse};
 use crate::ast::struct_statement::StructStatement;
 use crate::ast::trait_statement::{TraitStatement, TraitsStatement};
 use crate::ast::using_statement::{SingleUsingStatement, UsingStatement};
 use crate::ast::Statement;
 use crate::error::{CResult, ErrorMessage};
 use crate::lex::token::{Kw, Token};
-use std::ops::Range;
 
 use crate::lex::tokens::Tokens;
+use crate::source::Span;
 
 /// A whole module.
+#[derive(Debug)]
 pub struct ModuleStatement {
     pub name: String,
+    /// Whether the module is only visible where it is declared.
+    pub private: bool,
     pub class: Option<ClassStatement>,
     pub strukt: Option<StructStatement>,
     pub traits: Vec<TraitStatement>,
     pub defs: Vec<DefStatement>,
     pub lets: Vec<LetStatement>,
     pub using: Vec<SingleUsingStatement>,
-    token_range: Range<usize>,
+    span: Span,
 }
 
 impl ModuleStatement {
-    fn new(name: String) -> Self {
+    fn new(name: String, private: bool) -> Self {
         Self {
             name,
+            private,
             class: None,
             strukt: None,
             traits: vec![],
             defs: vec![],
             lets: vec![],
             using: vec![],
-            token_range: Range::default(),
+            span: Span::default(),
         }
     }
 }
 
 impl Parse for ModuleStatement {
     fn matches(tokens: &Tokens) -> bool {
-        matches!(tokens.token(), Token::Kw(Kw::Mod))
+        matches!(tokens.token(), Token::Kw(Kw::Mod | Kw::Private))
     }
 
     fn parse(tokens: &mut Tokens) -> CResult<Self> {
         let base_level = tokens.level();
         let token_start = tokens.position();
+
+        let private = parse_private(tokens);
+        tokens.expect("'mod'");
+        if !matches!(tokens.token(), Token::Kw(Kw::Mod)) {
+            return tokens.unexpected_token_error();
+        }
         tokens.step();
 
         let name = parse_global(tokens)?;
 
-        let mut statement = ModuleStatement::new(name);
+        let mut statement = ModuleStatement::new(name, private);
 
         while tokens.deeper_than(base_level) {
             tokens.expect("class, struct, traits, defs, lets or using");
@@ -85,14 +95,14 @@ impl Parse for ModuleStatement {
             }
         }
 
-        statement.token_range = token_start..tokens.position();
+        statement.span = tokens.span_from(token_start);
 
         Ok(statement)
     }
 }
 
 impl Statement for ModuleStatement {
-    fn token_range(&self) -> &Range<usize> {
-        &self.token_range
+    fn span(&self) -> Span {
+        self.span
     }
 }
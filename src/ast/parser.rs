@@ -1,7 +1,7 @@
 use crate::ast::type_statement::TypeStatement;
 
 use crate::error::CResult;
-use crate::lex::token::{Op, Token};
+use crate::lex::token::{Kw, Op, Token};
 use crate::lex::tokens::Tokens;
 
 /// Something that can be created by parsing tokens.
@@ -35,6 +35,16 @@ pub fn parse_global(tokens: &mut Tokens) -> CResult<String> {
     }
 }
 
+/// Parses the optional 'private' modifier, and returns whether it was there.
+pub fn parse_private(tokens: &mut Tokens) -> bool {
+    let private = matches!(tokens.token(), Token::Kw(Kw::Private));
+    if private {
+        tokens.step();
+    }
+
+    private
+}
+
 /// Parses a local name.
 pub fn parse_local(tokens: &mut Tokens) -> CResult<String> {
     if let Token::Local(name) = tokens.token().clone() {
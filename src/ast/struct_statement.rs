@@ -5,13 +5,14 @@ use crate::lex::token::{Kw, Token};
 
 use crate::ast::Statement;
 use crate::lex::tokens::Tokens;
+use crate::source::Span;
 use std::borrow::Borrow;
-use std::ops::Range;
 
 /// The struct keyword and its fields.
+#[derive(Debug)]
 pub struct StructStatement {
     pub fields: Vec<(String, RawType)>,
-    token_range: Range<usize>,
+    span: Span,
 }
 
 impl Parse for StructStatement {
@@ -32,7 +33,7 @@ impl Parse for StructStatement {
 
         let statement = StructStatement {
             fields,
-            token_range: token_start..tokens.position(),
+            span: tokens.span_from(token_start),
         };
         Ok(statement)
     }
@@ -56,7 +57,7 @@ fn parse_field(tokens: &mut Tokens) -> CResult<(String, RawType)> {
 }
 
 impl Statement for StructStatement {
-    fn token_range(&self) -> &Range<usize> {
-        &self.token_range
+    fn span(&self) -> Span {
+        self.span
     }
 }
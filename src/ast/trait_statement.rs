@@ -1,15 +1,21 @@
-use crate::ast::parser::{parse_global, parse_in_out_types, Parse};
+use crate::ast::parser::{parse_global, parse_in_out_types, parse_private, Parse};
 use crate::ast::type_statement::TypeStatement;
+use crate::ast::Statement;
 use crate::error::CResult;
 use crate::lex::token::{Kw, Token};
 
 use crate::lex::tokens::Tokens;
+use crate::source::Span;
 
 /// A single trait.
+#[derive(Debug)]
 pub struct TraitStatement {
     pub name: String,
+    /// Whether the trait is only visible within its module.
+    pub private: bool,
     pub parameters: Vec<(String, TypeStatement)>,
     pub output: TypeStatement,
+    span: Span,
 }
 
 /// The traits keyword and its traits.
@@ -46,12 +52,22 @@ impl Parse for TraitsStatement {
 
 fn parse_trait(tokens: &mut Tokens) -> CResult<TraitStatement> {
     let base_level = tokens.level();
+    let token_start = tokens.position();
+    let private = parse_private(tokens);
     let name = parse_global(tokens)?;
     let (parameters, output) = parse_in_out_types(tokens, base_level)?;
     let statement = TraitStatement {
         name,
+        private,
         parameters,
         output,
+        span: tokens.span_from(token_start),
     };
     Ok(statement)
 }
+
+impl Statement for TraitStatement {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
@@ -3,14 +3,14 @@ use crate::ast::raw_value::RawValue;
 use crate::ast::Statement;
 use crate::error::CResult;
 use crate::lex::token::{Kw, Op, Token};
-use std::ops::Range;
 
 use crate::lex::tokens::Tokens;
+use crate::source::Span;
 
 #[derive(Clone, Debug)]
 pub struct TypeStatement {
     pub typ: TypeStatementType,
-    token_range: Range<usize>,
+    span: Span,
 }
 
 #[derive(Clone, Debug)]
@@ -33,8 +33,8 @@ pub enum RawType {
 }
 
 impl Statement for TypeStatement {
-    fn token_range(&self) -> &Range<usize> {
-        &self.token_range
+    fn span(&self) -> Span {
+        self.span
     }
 }
 
@@ -64,7 +64,7 @@ impl Parse for TypeStatement {
 
                 let lhs = Box::new(TypeStatement {
                     typ,
-                    token_range: token_start..tokens.position(),
+                    span: tokens.span_from(token_start),
                 });
                 let rhs = Box::new(TypeStatement::parse(tokens)?);
 
@@ -79,7 +79,7 @@ impl Parse for TypeStatement {
 
         let statement = TypeStatement {
             typ,
-            token_range: token_start..tokens.position(),
+            span: tokens.span_from(token_start),
         };
         Ok(statement)
     }
@@ -1,12 +1,16 @@
 use crate::ast::parser::{parse_global, Parse};
+use crate::ast::Statement;
 use crate::error::CResult;
 use crate::lex::token::{Kw, Op, Token};
 use crate::lex::tokens::Tokens;
+use crate::source::Span;
 
 /// A single line of a 'using' statement
+#[derive(Debug)]
 pub struct SingleUsingStatement {
     pub name: String,
     pub wildcard: bool,
+    span: Span,
 }
 
 /// All lines of a 'using' statement
@@ -34,9 +38,14 @@ impl Parse for UsingStatement {
 }
 
 fn parse_single_using(tokens: &mut Tokens) -> CResult<SingleUsingStatement> {
+    let token_start = tokens.position();
     let name = parse_global(tokens)?;
     let wildcard = parse_wildcard(tokens);
-    let statement = SingleUsingStatement { name, wildcard };
+    let statement = SingleUsingStatement {
+        name,
+        wildcard,
+        span: tokens.span_from(token_start),
+    };
     Ok(statement)
 }
 
@@ -48,3 +57,9 @@ fn parse_wildcard(tokens: &mut Tokens) -> bool {
         false
     }
 }
+
+impl Statement for SingleUsingStatement {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
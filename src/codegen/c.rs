@@ -0,0 +1,382 @@
+use crate::ast::raw_value::RawValue;
+use crate::ast::type_statement::RawType;
+use crate::error::CResult;
+use crate::runtime::bytecode::{Function, Instruction, Pattern, Program};
+use crate::runtime::call_stack::Limits;
+use crate::runtime::compiler::compile;
+use crate::runtime::instance::Instance;
+use crate::runtime::raw_operation::RawOp;
+use crate::sem::semantic_analyser::SemanticContext;
+use std::fmt::Write;
+
+/// Generates a self-contained C program which prints the result of the Main let.
+pub fn generate_c(context: &SemanticContext) -> CResult<String> {
+    let main = context.lets.resolve("Main", "")?;
+
+    Ok(CGenerator::new(&compile(&main)).generate())
+}
+
+/// The bytes each call takes on the stack besides the locals and operands of its function.
+const FRAME_OVERHEAD: usize = 256;
+
+/// The stack which the runtime takes besides the calls.
+const BASE_STACK_SIZE: usize = 1 << 20;
+
+struct CGenerator<'a> {
+    program: &'a Program,
+    code: String,
+}
+
+impl<'a> CGenerator<'a> {
+    fn new(program: &'a Program) -> Self {
+        CGenerator {
+            program,
+            code: String::new(),
+        }
+    }
+
+    fn generate(mut self) -> String {
+        self.code += include_str!("../resources/c/runtime.c");
+
+        self.line("");
+        for (id, function) in self.program.functions.iter().enumerate() {
+            self.line(&format!(
+                "static Value *f{}(Value *self, Value **args); /* {} */",
+                id,
+                comment(&function.name)
+            ));
+        }
+
+        self.line("");
+        self.tables();
+
+        self.line("");
+        for (id, constant) in self.program.constants.iter().enumerate() {
+            self.line(&format!(
+                "static Value k{} = {};",
+                id,
+                constant_value(constant)
+            ));
+        }
+
+        self.line("");
+        for (id, pattern) in self.program.patterns.iter().enumerate() {
+            self.line(&format!(
+                "static bool p{}(Value *v, Value *self) {{ (void)v; (void)self; return {}; }}",
+                id,
+                pattern_condition(pattern)
+            ));
+        }
+
+        for (id, function) in self.program.functions.iter().enumerate() {
+            self.line("");
+            self.function(id, function);
+        }
+
+        self.line("");
+        self.line("int main(void) {");
+        self.line(&format!("    return run(f{});", self.program.main));
+        self.line("}");
+
+        self.code
+    }
+
+    fn line(&mut self, line: &str) {
+        self.code += line;
+        self.code += "\n";
+    }
+
+    /// The tables through which the runtime accesses traits and classes.
+    fn tables(&mut self) {
+        let program = self.program;
+        let num_traits = program.traits.len();
+
+        self.line(&format!("const int num_traits = {};", num_traits));
+        self.line(&format!(
+            "const int string_trait = {};",
+            program.string_trait
+        ));
+
+        self.table(
+            "const char *const trait_names[]",
+            program.traits.iter().map(|trayt| c_string(&trayt.name)),
+        );
+        self.table(
+            "const RawOp raw_ops[]",
+            program
+                .traits
+                .iter()
+                .map(|trayt| raw_op(trayt.raw_op).into()),
+        );
+        self.table(
+            "const char *const class_names[]",
+            program.classes.iter().map(|class| c_string(&class.name)),
+        );
+        self.table(
+            "const int class_sizes[]",
+            program
+                .classes
+                .iter()
+                .map(|class| class.slots.len().to_string()),
+        );
+        self.table(
+            "const bool class_is_struct[]",
+            program
+                .classes
+                .iter()
+                .map(|class| class.fields.is_some().to_string()),
+        );
+        self.table(
+            "const bool class_is_string[]",
+            program
+                .classes
+                .iter()
+                .map(|class| class.is_string().to_string()),
+        );
+        self.table(
+            "const int class_value_slots[]",
+            program.classes.iter().map(|class| {
+                match class.slots.iter().position(|slot| slot == "value") {
+                    Some(slot) => slot.to_string(),
+                    None => "-1".to_string(),
+                }
+            }),
+        );
+        // The same limit on nested calls as in the interpreter.
+        let max_depth = Limits::default().max_depth;
+        self.line(&format!("const int max_depth = {};", max_depth));
+        self.line(&format!(
+            "const size_t stack_size = {};",
+            self.stack_size(max_depth)
+        ));
+        let max_args = program
+            .functions
+            .iter()
+            .map(|function| function.params)
+            .chain(program.traits.iter().map(|trayt| trayt.inputs.len()))
+            .max()
+            .unwrap_or(0);
+        self.line(&format!("Value *tail_args[{}];", max_args.max(1)));
+
+        self.table(
+            "const Function vtables[]",
+            program.classes.iter().flat_map(|class| {
+                class.vtable.iter().map(|function| match function {
+                    Some(function) => format!("f{}", function),
+                    None => "NULL".to_string(),
+                })
+            }),
+        );
+    }
+
+    /// The size of a stack which has room for the deepest calls, from the largest function. Each
+    /// call also takes a frame of the runtime, which is part of the overhead.
+    fn stack_size(&self, max_depth: usize) -> usize {
+        let largest = self
+            .program
+            .functions
+            .iter()
+            .map(|function| function.locals.max(1) + function.code.len().max(1))
+            .max()
+            .unwrap_or(0);
+
+        max_depth * (largest * size_of::<u64>() + FRAME_OVERHEAD) + BASE_STACK_SIZE
+    }
+
+    /// Defines an array. Empty arrays get a dummy element, as C doesn't allow them.
+    fn table(&mut self, declaration: &str, elements: impl Iterator<Item = String>) {
+        let mut elements: Vec<_> = elements.collect();
+        if elements.is_empty() {
+            elements.push("0".to_string());
+        }
+
+        self.line(&format!("{} = {{{}}};", declaration, elements.join(", ")));
+    }
+
+    /// Translates a function, keeping the operand stack in a local array.
+    fn function(&mut self, id: usize, function: &Function) {
+        let targets: Vec<usize> = function
+            .code
+            .iter()
+            .filter_map(|instruction| match instruction {
+                Instruction::Jump(target)
+                | Instruction::JumpIfFalse(target)
+                | Instruction::JumpUnlessMatches { target, .. } => Some(*target),
+                _ => None,
+            })
+            .collect();
+
+        self.line(&format!(
+            "static Value *f{}(Value *self, Value **args) {{",
+            id
+        ));
+        self.line(&format!("    Value *l[{}];", function.locals.max(1)));
+        self.line(&format!("    Value *s[{}];", function.code.len().max(1)));
+        self.line("    int sp = 0;");
+        self.line("    (void)self;");
+        self.line("    (void)args;");
+        self.line("    (void)l;");
+        for slot in 0..function.locals {
+            if slot < function.params {
+                self.line(&format!("    l[{}] = args[{}];", slot, slot));
+            } else {
+                self.line(&format!("    l[{}] = &void_value;", slot));
+            }
+        }
+        self.line("    enter();");
+
+        for (index, instruction) in function.code.iter().enumerate() {
+            if targets.contains(&index) {
+                self.line(&format!("L{}:", index));
+            }
+
+            let statement = self.instruction(instruction);
+            self.line(&format!("    {}", statement));
+        }
+
+        self.line("}");
+    }
+
+    fn instruction(&self, instruction: &Instruction) -> String {
+        let program = self.program;
+
+        match instruction {
+            Instruction::Constant(index) => format!("s[sp++] = &k{};", index),
+            Instruction::Void => "s[sp++] = &void_value;".to_string(),
+            Instruction::LoadLocal(slot) => format!("s[sp++] = l[{}];", slot),
+            Instruction::StoreLocal(slot) => format!("l[{}] = s[--sp];", slot),
+            Instruction::LoadSelf => "s[sp++] = self;".to_string(),
+            Instruction::LoadSlot(slot) => format!("s[sp++] = self->as.object.slots[{}];", slot),
+            Instruction::FriendlyField { class, slot } => format!(
+                "s[sp - 1] = friendly_field(s[sp - 1], {}, {}, {});",
+                class,
+                slot,
+                c_string(&program.classes[*class].slots[*slot])
+            ),
+            Instruction::CallLet { function, .. } => format!(
+                "sp -= {}; s[sp] = finish(f{}(NULL, s + sp)); sp++;",
+                program.functions[*function].params, function
+            ),
+            Instruction::CallTrait { trayt, .. } => format!(
+                "sp -= {}; s[sp] = call_trait({}, s[sp], s + sp + 1); sp++;",
+                program.traits[*trayt].inputs.len() + 1,
+                trayt
+            ),
+            // Tail calls are left to the caller, once this call has returned.
+            Instruction::TailCallLet { function, .. } => {
+                let params = program.functions[*function].params;
+                format!(
+                    "sp -= {}; return leave(tail_call(f{}, NULL, s + sp, {}));",
+                    params, function, params
+                )
+            }
+            Instruction::TailCallTrait { trayt, .. } => {
+                let inputs = program.traits[*trayt].inputs.len();
+                format!(
+                    "sp -= {}; return leave(tail_call_trait({}, s[sp], s + sp + 1, {}));",
+                    inputs + 1,
+                    trayt,
+                    inputs
+                )
+            }
+            Instruction::New(class) => format!(
+                "sp -= {}; s[sp] = new_object({}, s + sp); sp++;",
+                program.classes[*class].slots.len(),
+                class
+            ),
+            Instruction::JumpIfFalse(target) => {
+                format!("if (!to_bool(s[--sp])) goto L{};", target)
+            }
+            Instruction::Jump(target) => format!("goto L{};", target),
+            Instruction::JumpUnlessMatches { pattern, target } => {
+                format!("if (!p{}(s[sp - 1], self)) goto L{};", pattern, target)
+            }
+            Instruction::Fail(message) => {
+                format!("fail(\"%s\", {});", c_string(&String::from(message)))
+            }
+            Instruction::Return => "return leave(s[--sp]);".to_string(),
+        }
+    }
+}
+
+fn constant_value(constant: &Instance) -> String {
+    match constant {
+        Instance::Raw(RawValue::Int(value)) if *value == i64::MIN => {
+            "{INT, {.integer = INT64_MIN}}".to_string()
+        }
+        Instance::Raw(RawValue::Int(value)) => format!("{{INT, {{.integer = {}LL}}}}", value),
+        Instance::Raw(RawValue::String(value)) => {
+            format!("{{STRING, {{.string = {}}}}}", c_string(value))
+        }
+        Instance::Raw(RawValue::Bool(value)) => format!("{{BOOL, {{.boolean = {}}}}}", value),
+        _ => "{VOID, {0}}".to_string(),
+    }
+}
+
+fn pattern_condition(pattern: &Pattern) -> String {
+    match pattern {
+        Pattern::Trait(trayt) => format!("has_trait(v, {})", trayt),
+        Pattern::Raw(RawType::Int) => "v->kind == INT".to_string(),
+        Pattern::Raw(RawType::String) => "v->kind == STRING".to_string(),
+        Pattern::Raw(RawType::Bool) => "v->kind == BOOL".to_string(),
+        Pattern::And(a, b) => format!("({} && {})", pattern_condition(a), pattern_condition(b)),
+        Pattern::Or(a, b) => format!("({} || {})", pattern_condition(a), pattern_condition(b)),
+        Pattern::Zelf => "(self != NULL && same_type(self, v))".to_string(),
+        Pattern::Void => "true".to_string(),
+    }
+}
+
+fn raw_op(op: Option<RawOp>) -> &'static str {
+    match op {
+        None => "OP_NONE",
+        Some(RawOp::Add) => "OP_ADD",
+        Some(RawOp::Sub) => "OP_SUB",
+        Some(RawOp::Mul) => "OP_MUL",
+        Some(RawOp::Div) => "OP_DIV",
+        Some(RawOp::Neg) => "OP_NEG",
+        Some(RawOp::Eq) => "OP_EQ",
+        Some(RawOp::Lt) => "OP_LT",
+        Some(RawOp::Gt) => "OP_GT",
+        Some(RawOp::And) => "OP_AND",
+        Some(RawOp::Or) => "OP_OR",
+        Some(RawOp::String) => "OP_STRING",
+        Some(RawOp::Ne) => "OP_NE",
+        Some(RawOp::Lte) => "OP_LTE",
+        Some(RawOp::Gte) => "OP_GTE",
+        Some(RawOp::Not) => "OP_NOT",
+    }
+}
+
+/// A C string literal. Other characters than printable ASCII are written as octal escapes.
+fn c_string(string: &str) -> String {
+    let mut literal = String::from("\"");
+
+    for byte in string.bytes() {
+        match byte {
+            b'"' => literal += "\\\"",
+            b'\\' => literal += "\\\\",
+            b' '..=b'~' => literal.push(byte as char),
+            _ => write!(literal, "\\{:03o}", byte).unwrap(),
+        }
+    }
+
+    literal + "\""
+}
+
+/// Makes a name safe to put inside a C comment.
+fn comment(name: &str) -> String {
+    name.replace("*/", "* /")
+}
+
+#[cfg(test)]
+mod test {
+    use crate::codegen::c::c_string;
+
+    #[test]
+    fn test_c_string() {
+        assert_eq!(c_string("Op\\Add"), "\"Op\\\\Add\"");
+        assert_eq!(c_string("say \"hi\""), "\"say \\\"hi\\\"\"");
+        assert_eq!(c_string("a\nb"), "\"a\\012b\"");
+        assert_eq!(c_string("é"), "\"\\303\\251\"");
+    }
+}
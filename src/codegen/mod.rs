@@ -0,0 +1,2 @@
+pub(crate) mod c;
+pub(crate) mod wat;
@@ -0,0 +1,638 @@
+use crate::ast::raw_value::RawValue;
+use crate::ast::type_statement::RawType;
+use crate::error::CResult;
+use crate::runtime::bytecode::{Function, Instruction, Pattern, Program};
+use crate::runtime::compiler::compile;
+use crate::runtime::instance::Instance;
+use crate::runtime::raw_operation::RawOp;
+use crate::sem::semantic_analyser::SemanticContext;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Write;
+
+/// Where the static data starts in linear memory. Lower addresses are left unused, so 0 can be
+/// used as a null pointer.
+const DATA_START: u32 = 16;
+
+const PAGE_SIZE: u32 = 65536;
+
+/// Strings used by the runtime, with the names of the globals pointing to them.
+const RUNTIME_STRINGS: [(&str, &str); 20] = [
+    ("int", "int"),
+    ("string", "string"),
+    ("bool", "bool"),
+    ("unknown", "?"),
+    ("true", "true"),
+    ("false", "false"),
+    ("overflow", "Integer overflow"),
+    ("division_by_zero", "Division by zero"),
+    ("operation", "Operation '"),
+    ("not_supported_by", "' is not supported by "),
+    ("non_raw_rhs", "a non-raw right-hand side"),
+    ("non_raw_value", "a non-raw value"),
+    ("call_trait", "Can't call trait '"),
+    ("on_void", "' on void"),
+    ("trait", "Trait '"),
+    ("not_defined", "' is not defined on this instance"),
+    ("field", "Field '"),
+    ("does_not_exist", "' does not exist"),
+    ("not_a_bool", "Value can't be resolved to a bool"),
+    ("out_of_memory", "Out of memory"),
+];
+
+/// Generates a WebAssembly text module. Its exported `main` function returns the address of the
+/// address and length of the string result of the Main let, inside the exported memory.
+pub fn generate_wat(context: &SemanticContext) -> CResult<String> {
+    let main = context.lets.resolve("Main", "")?;
+
+    Ok(WatGenerator::new(&compile(&main)).generate())
+}
+
+/// The static data of a module, as laid out in linear memory.
+struct Data {
+    bytes: Vec<u8>,
+    strings: HashMap<String, u32>,
+}
+
+impl Data {
+    fn new() -> Self {
+        Data {
+            bytes: vec![],
+            strings: HashMap::new(),
+        }
+    }
+
+    /// The address the next record will be placed at, aligned to 8 bytes.
+    fn next_address(&mut self) -> u32 {
+        while !self.bytes.len().is_multiple_of(8) {
+            self.bytes.push(0);
+        }
+
+        DATA_START + self.bytes.len() as u32
+    }
+
+    fn words(&mut self, words: &[u32]) -> u32 {
+        let address = self.next_address();
+        for word in words {
+            self.bytes.extend(word.to_le_bytes());
+        }
+        address
+    }
+
+    fn void(&mut self) -> u32 {
+        self.words(&[0, 0])
+    }
+
+    fn int(&mut self, value: i64) -> u32 {
+        let address = self.words(&[1, 0]);
+        self.bytes.extend(value.to_le_bytes());
+        address
+    }
+
+    fn bool(&mut self, value: bool) -> u32 {
+        self.words(&[3, value as u32])
+    }
+
+    /// A string value. Equal strings share the same value.
+    fn string(&mut self, value: &str) -> u32 {
+        if let Some(address) = self.strings.get(value) {
+            return *address;
+        }
+
+        let bytes = self.next_address();
+        self.bytes.extend(value.as_bytes());
+
+        let address = self.words(&[2, bytes, value.len() as u32, 0]);
+        self.strings.insert(value.to_string(), address);
+        address
+    }
+
+    fn end(&mut self) -> u32 {
+        self.next_address()
+    }
+}
+
+struct WatGenerator<'a> {
+    program: &'a Program,
+    data: Data,
+    code: String,
+}
+
+impl<'a> WatGenerator<'a> {
+    fn new(program: &'a Program) -> Self {
+        WatGenerator {
+            program,
+            data: Data::new(),
+            code: String::new(),
+        }
+    }
+
+    fn generate(mut self) -> String {
+        let program = self.program;
+
+        // The functions are generated first, as they add constants and messages to the data.
+        let void = self.data.void();
+        let functions: Vec<_> = program
+            .functions
+            .iter()
+            .enumerate()
+            .map(|(id, function)| self.function(id, function))
+            .collect();
+
+        self.line("(module");
+
+        let arities: BTreeSet<_> = [0]
+            .into_iter()
+            .chain(program.functions.iter().map(|function| function.params))
+            .chain(program.traits.iter().map(|trayt| trayt.inputs.len()))
+            .collect();
+        for arity in &arities {
+            self.line(&format!(
+                "  (type $arity_{} (func (param{}) (result i32)))",
+                arity,
+                " i32".repeat(arity + 1)
+            ));
+        }
+
+        self.line("");
+        self.globals(void);
+
+        self.line("");
+        self.vtables();
+
+        self.line("");
+        self.code += include_str!("../resources/wat/runtime.wat");
+
+        for arity in &arities {
+            self.line("");
+            self.call_trait(*arity);
+        }
+
+        for function in functions {
+            self.line("");
+            self.code += &function;
+        }
+
+        self.line("");
+        self.line("  ;; Returns a pointer to an (address, length) pair of the string result.");
+        self.line("  (func (export \"main\") (result i32)");
+        self.line(&format!(
+            "    (i32.add (call $to_string (call $f{} (i32.const 0))) (i32.const 4)))",
+            program.main
+        ));
+        self.line("");
+        self.line(
+            "  ;; Returns a pointer to an (address, length) pair of the error message, or 0.",
+        );
+        self.line("  (func (export \"error\") (result i32)");
+        self.line("    (if (i32.eqz (global.get $error))");
+        self.line("      (then (return (i32.const 0))))");
+        self.line("    (i32.add (global.get $error) (i32.const 4)))");
+
+        self.line("");
+        let end = self.data.end();
+        self.line(&format!(
+            "  (memory (export \"memory\") {})",
+            end.div_ceil(PAGE_SIZE) + 1
+        ));
+        self.line(&format!(
+            "  (data (i32.const {}) \"{}\")",
+            DATA_START,
+            escape(&self.data.bytes)
+        ));
+        self.line(")");
+
+        self.code
+    }
+
+    fn line(&mut self, line: &str) {
+        self.code += line;
+        self.code += "\n";
+    }
+
+    /// The globals pointing to the data through which the runtime accesses traits and classes.
+    fn globals(&mut self, void: u32) {
+        let program = self.program;
+
+        let trait_names: Vec<_> = program
+            .traits
+            .iter()
+            .map(|trayt| self.data.string(&trayt.name))
+            .collect();
+        let raw_ops: Vec<_> = program
+            .traits
+            .iter()
+            .map(|trayt| raw_op(trayt.raw_op))
+            .collect();
+        let class_names: Vec<_> = program
+            .classes
+            .iter()
+            .map(|class| self.data.string(&class.name))
+            .collect();
+        let class_sizes: Vec<_> = program
+            .classes
+            .iter()
+            .map(|class| class.slots.len() as u32)
+            .collect();
+        let class_is_string: Vec<_> = program
+            .classes
+            .iter()
+            .map(|class| class.is_string() as u32)
+            .collect();
+        let class_value_slots: Vec<_> = program
+            .classes
+            .iter()
+            .map(
+                |class| match class.slots.iter().position(|slot| slot == "value") {
+                    Some(slot) => slot as u32,
+                    None => u32::MAX,
+                },
+            )
+            .collect();
+        let vtable_defined: Vec<_> = program
+            .classes
+            .iter()
+            .flat_map(|class| {
+                class
+                    .vtable
+                    .iter()
+                    .map(|function| function.is_some() as u32)
+            })
+            .collect();
+
+        let strings: Vec<_> = RUNTIME_STRINGS
+            .iter()
+            .map(|(name, string)| (name, self.data.string(string)))
+            .collect();
+
+        let tables = [
+            ("trait_names", self.data.words(&trait_names)),
+            ("raw_ops", self.data.words(&raw_ops)),
+            ("class_names", self.data.words(&class_names)),
+            ("class_sizes", self.data.words(&class_sizes)),
+            ("class_is_string", self.data.words(&class_is_string)),
+            ("class_value_slots", self.data.words(&class_value_slots)),
+            ("vtable_defined", self.data.words(&vtable_defined)),
+        ];
+
+        let heap_start = self.data.end();
+
+        self.global("num_traits", program.traits.len() as u32);
+        self.global("string_trait", program.string_trait as u32);
+        self.global("void", void);
+        for (name, address) in tables {
+            self.global(name, address);
+        }
+        for (name, address) in strings {
+            self.global(&format!("str_{}", name), address);
+        }
+        self.line(&format!(
+            "  (global $heap (mut i32) (i32.const {}))",
+            heap_start
+        ));
+    }
+
+    fn global(&mut self, name: &str, value: u32) {
+        self.line(&format!(
+            "  (global ${} i32 (i32.const {}))",
+            name, value as i32
+        ));
+    }
+
+    /// The function table, holding the trait definitions of each class at
+    /// `class * num_traits + trait`.
+    fn vtables(&mut self) {
+        let program = self.program;
+        let size = (program.classes.len() * program.traits.len()).max(1);
+
+        self.line(&format!("  (table {} funcref)", size));
+
+        for (class, layout) in program.classes.iter().enumerate() {
+            for (trayt, function) in layout.vtable.iter().enumerate() {
+                if let Some(function) = function {
+                    self.line(&format!(
+                        "  (elem (i32.const {}) $f{})",
+                        class * program.traits.len() + trayt,
+                        function
+                    ));
+                }
+            }
+        }
+    }
+
+    /// A function that calls a trait with the given number of inputs, through the function
+    /// table or as a raw operation.
+    fn call_trait(&mut self, arity: usize) {
+        let inputs: String = (0..arity)
+            .map(|index| format!(" (param $i{} i32)", index))
+            .collect();
+        let args: String = (0..arity)
+            .map(|index| format!(" (local.get $i{})", index))
+            .collect();
+        let rhs = if arity > 0 { "$i0" } else { "$subject" };
+
+        self.line(&format!(
+            "  (func $call_trait_{} (param $trait i32) (param $subject i32){} (result i32)",
+            arity, inputs
+        ));
+        self.line("    (if (i32.and");
+        self.line("          (i32.ne (call $kind (local.get $subject)) (i32.const 0))");
+        self.line("          (i32.ne (call $kind (local.get $subject)) (i32.const 4)))");
+        self.line("      (then");
+        self.line(&format!(
+            "        (return (call $raw_operation (local.get $trait) (local.get $subject) (local.get {})))))",
+            rhs
+        ));
+        self.line(&format!(
+            "    (call_indirect (type $arity_{}) (local.get $subject){}",
+            arity, args
+        ));
+        self.line("      (call $vtable_index (local.get $trait) (local.get $subject))))");
+    }
+
+    /// Translates a function. Forward jumps become branches out of nested blocks, and the
+    /// operand stack is kept in locals, as its depth is known at each instruction.
+    fn function(&mut self, id: usize, function: &Function) -> String {
+        let depths = self.stack_depths(function);
+        let max_depth = depths.iter().flatten().max().copied().unwrap_or(0) + 1;
+
+        let targets: BTreeSet<usize> = function
+            .code
+            .iter()
+            .filter_map(|instruction| match instruction {
+                Instruction::Jump(target)
+                | Instruction::JumpIfFalse(target)
+                | Instruction::JumpUnlessMatches { target, .. } => Some(*target),
+                _ => None,
+            })
+            .collect();
+
+        let mut code = format!("  ;; {}\n", function.name);
+        let params: String = (0..function.params)
+            .map(|slot| format!(" (param $l{} i32)", slot))
+            .collect();
+        writeln!(
+            code,
+            "  (func $f{} (param $self i32){} (result i32)",
+            id, params
+        )
+        .unwrap();
+        for slot in function.params..function.locals {
+            writeln!(code, "    (local $l{} i32)", slot).unwrap();
+        }
+        for depth in 0..max_depth {
+            writeln!(code, "    (local $s{} i32)", depth).unwrap();
+        }
+        writeln!(code, "    (local $object i32)").unwrap();
+        for slot in function.params..function.locals {
+            writeln!(code, "    (local.set $l{} (global.get $void))", slot).unwrap();
+        }
+
+        for target in targets.iter().rev() {
+            writeln!(code, "    (block $L{}", target).unwrap();
+        }
+
+        for (index, instruction) in function.code.iter().enumerate() {
+            if targets.contains(&index) {
+                writeln!(code, "    ) ;; $L{}", index).unwrap();
+            }
+
+            // Skip unreachable instructions.
+            if let Some(depth) = depths[index] {
+                writeln!(code, "    {}", self.instruction(instruction, depth)).unwrap();
+            }
+        }
+
+        code += "    (unreachable))\n";
+        code
+    }
+
+    /// The depth of the operand stack before each instruction, or `None` if it is unreachable.
+    fn stack_depths(&self, function: &Function) -> Vec<Option<usize>> {
+        let mut depths = vec![None; function.code.len() + 1];
+        depths[0] = Some(0);
+
+        for (index, instruction) in function.code.iter().enumerate() {
+            let Some(depth) = depths[index] else {
+                continue;
+            };
+
+            let (next, jump) = match instruction {
+                Instruction::Constant(_)
+                | Instruction::Void
+                | Instruction::LoadLocal(_)
+                | Instruction::LoadSelf
+                | Instruction::LoadSlot(_) => (Some(depth + 1), None),
+                Instruction::StoreLocal(_) => (Some(depth - 1), None),
+                Instruction::FriendlyField { .. } => (Some(depth), None),
+                Instruction::CallLet { function, .. }
+                | Instruction::TailCallLet { function, .. } => (
+                    Some(depth + 1 - self.program.functions[*function].params),
+                    None,
+                ),
+                Instruction::CallTrait { trayt, .. } | Instruction::TailCallTrait { trayt, .. } => {
+                    (Some(depth - self.program.traits[*trayt].inputs.len()), None)
+                }
+                Instruction::New(class) => (
+                    Some(depth + 1 - self.program.classes[*class].slots.len()),
+                    None,
+                ),
+                Instruction::JumpIfFalse(target) => (Some(depth - 1), Some((*target, depth - 1))),
+                Instruction::Jump(target) => (None, Some((*target, depth))),
+                Instruction::JumpUnlessMatches { target, .. } => {
+                    (Some(depth), Some((*target, depth)))
+                }
+                Instruction::Fail(_) | Instruction::Return => (None, None),
+            };
+
+            if let Some(next) = next {
+                depths[index + 1] = Some(next);
+            }
+            if let Some((target, depth)) = jump {
+                depths[target] = Some(depth);
+            }
+        }
+
+        depths
+    }
+
+    fn instruction(&mut self, instruction: &Instruction, depth: usize) -> String {
+        let program = self.program;
+        let push = |value: String| format!("(local.set $s{} {})", depth, value);
+
+        match instruction {
+            Instruction::Constant(index) => {
+                let address = match program.constants[*index].as_ref() {
+                    Instance::Raw(RawValue::Int(value)) => self.data.int(*value),
+                    Instance::Raw(RawValue::String(value)) => self.data.string(value),
+                    Instance::Raw(RawValue::Bool(value)) => self.data.bool(*value),
+                    _ => self.data.void(),
+                };
+                push(format!("(i32.const {})", address))
+            }
+            Instruction::Void => push("(global.get $void)".to_string()),
+            Instruction::LoadLocal(slot) => push(format!("(local.get $l{})", slot)),
+            Instruction::StoreLocal(slot) => {
+                format!("(local.set $l{} (local.get $s{}))", slot, depth - 1)
+            }
+            Instruction::LoadSelf => push("(local.get $self)".to_string()),
+            Instruction::LoadSlot(slot) => push(format!(
+                "(i32.load offset={} (local.get $self))",
+                8 + 4 * slot
+            )),
+            Instruction::FriendlyField { class, slot } => {
+                let name = self.data.string(&program.classes[*class].slots[*slot]);
+                format!(
+                    "(local.set $s{0} (call $friendly_field (local.get $s{0}) (i32.const {1}) (i32.const {2}) (i32.const {3})))",
+                    depth - 1,
+                    class,
+                    slot,
+                    name
+                )
+            }
+            Instruction::CallLet { function, .. } | Instruction::TailCallLet { function, .. } => {
+                let params = program.functions[*function].params;
+                let bottom = depth - params;
+                format!(
+                    "(local.set $s{} (call $f{} (i32.const 0){}))",
+                    bottom,
+                    function,
+                    stack_values(bottom, depth)
+                )
+            }
+            Instruction::CallTrait { trayt, .. } | Instruction::TailCallTrait { trayt, .. } => {
+                let inputs = program.traits[*trayt].inputs.len();
+                let bottom = depth - inputs - 1;
+                format!(
+                    "(local.set $s{} (call $call_trait_{} (i32.const {}){}))",
+                    bottom,
+                    inputs,
+                    trayt,
+                    stack_values(bottom, depth)
+                )
+            }
+            Instruction::New(class) => {
+                let layout = &program.classes[*class];
+                let bottom = depth - layout.slots.len();
+
+                let mut code = format!(
+                    "(local.set $object (call $new_object (i32.const {})))",
+                    class
+                );
+                for slot in 0..layout.slots.len() {
+                    let value = match layout.fields {
+                        Some(_) => format!(
+                            "(call $check_raw (i32.const {}) (local.get $s{}))",
+                            class,
+                            bottom + slot
+                        ),
+                        None => format!("(local.get $s{})", bottom + slot),
+                    };
+                    write!(
+                        code,
+                        "\n    (i32.store offset={} (local.get $object) {})",
+                        8 + 4 * slot,
+                        value
+                    )
+                    .unwrap();
+                }
+                write!(code, "\n    (local.set $s{} (local.get $object))", bottom).unwrap();
+                code
+            }
+            Instruction::JumpIfFalse(target) => format!(
+                "(br_if $L{} (i32.eqz (call $to_bool (local.get $s{}))))",
+                target,
+                depth - 1
+            ),
+            Instruction::Jump(target) => format!("(br $L{})", target),
+            Instruction::JumpUnlessMatches { pattern, target } => format!(
+                "(br_if $L{} (i32.eqz {}))",
+                target,
+                pattern_condition(&program.patterns[*pattern], depth - 1)
+            ),
+            Instruction::Fail(message) => {
+                let message = self.data.string(&String::from(message));
+                format!("(call $fail (i32.const {}))", message)
+            }
+            Instruction::Return => format!("(return (local.get $s{}))", depth - 1),
+        }
+    }
+}
+
+/// The operand stack locals from the bottom up to the given depth, as arguments.
+fn stack_values(bottom: usize, depth: usize) -> String {
+    (bottom..depth)
+        .map(|index| format!(" (local.get $s{})", index))
+        .collect()
+}
+
+fn pattern_condition(pattern: &Pattern, subject: usize) -> String {
+    match pattern {
+        Pattern::Trait(trayt) => format!(
+            "(call $has_trait (local.get $s{}) (i32.const {}))",
+            subject, trayt
+        ),
+        Pattern::Raw(raw_type) => {
+            let kind = match raw_type {
+                RawType::Int => 1,
+                RawType::String => 2,
+                RawType::Bool => 3,
+            };
+            format!(
+                "(i32.eq (call $kind (local.get $s{})) (i32.const {}))",
+                subject, kind
+            )
+        }
+        Pattern::And(a, b) => format!(
+            "(i32.and {} {})",
+            pattern_condition(a, subject),
+            pattern_condition(b, subject)
+        ),
+        Pattern::Or(a, b) => format!(
+            "(i32.or {} {})",
+            pattern_condition(a, subject),
+            pattern_condition(b, subject)
+        ),
+        Pattern::Zelf => format!(
+            "(i32.and (i32.ne (local.get $self) (i32.const 0)) (call $same_type (local.get $self) (local.get $s{})))",
+            subject
+        ),
+        Pattern::Void => "(i32.const 1)".to_string(),
+    }
+}
+
+/// The number of a raw operation in the runtime.
+fn raw_op(op: Option<RawOp>) -> u32 {
+    match op {
+        None => 0,
+        Some(RawOp::Add) => 1,
+        Some(RawOp::Sub) => 2,
+        Some(RawOp::Mul) => 3,
+        Some(RawOp::Div) => 4,
+        Some(RawOp::Neg) => 5,
+        Some(RawOp::Eq) => 6,
+        Some(RawOp::Lt) => 7,
+        Some(RawOp::Gt) => 8,
+        Some(RawOp::And) => 9,
+        Some(RawOp::Or) => 10,
+        Some(RawOp::String) => 11,
+        Some(RawOp::Ne) => 12,
+        Some(RawOp::Lte) => 13,
+        Some(RawOp::Gte) => 14,
+        Some(RawOp::Not) => 15,
+    }
+}
+
+/// Escapes bytes for a WebAssembly string literal.
+fn escape(bytes: &[u8]) -> String {
+    let mut string = String::new();
+
+    for byte in bytes {
+        match byte {
+            b'"' | b'\\' => write!(string, "\\{:02x}", byte).unwrap(),
+            b' '..=b'~' => string.push(*byte as char),
+            _ => write!(string, "\\{:02x}", byte).unwrap(),
+        }
+    }
+
+    string
+}
@@ -0,0 +1,284 @@
+use crate::source::{SourceMap, Span};
+use std::fmt::{Display, Formatter};
+
+/// A failure to compile or run a program, as reported to the user.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub kind: DiagnosticKind,
+    /// The code of the error, as in "C0004", if it has one.
+    pub code: Option<&'static str>,
+    /// What went wrong, in a single line.
+    pub message: String,
+    /// Where it went wrong, if known.
+    pub location: Option<Snippet>,
+    /// Other places in the code which help to explain the error.
+    pub labels: Vec<Label>,
+    /// Further details, shown below the code.
+    pub notes: Vec<String>,
+    /// Suggestions on how to fix the error, shown below the notes.
+    pub help: Vec<String>,
+    /// The traceback of a runtime error, one line per call.
+    pub trace: Vec<String>,
+}
+
+/// The stage in which a program failed.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum DiagnosticKind {
+    /// Reading the files of a program, or the manifests of a package and its dependencies.
+    Package,
+    Lexing,
+    Parsing,
+    Semantic,
+    Runtime,
+}
+
+/// A line of code, with the part of it that a diagnostic points at.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Snippet {
+    /// The name of the file.
+    pub file: String,
+    pub line: usize,
+    pub col: usize,
+    /// The number of characters pointed at, which is at least one.
+    pub len: usize,
+    /// The full line of code.
+    pub source: String,
+}
+
+/// A secondary place in the code, with an explanation.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Label {
+    pub snippet: Snippet,
+    pub message: String,
+}
+
+const RED: &str = "1;31";
+const BLUE: &str = "1;34";
+const BOLD: &str = "1";
+
+impl DiagnosticKind {
+    /// The exit status of the binary for a failure in this stage.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            DiagnosticKind::Package => 2,
+            DiagnosticKind::Lexing => 3,
+            DiagnosticKind::Parsing => 4,
+            DiagnosticKind::Semantic => 5,
+            DiagnosticKind::Runtime => 6,
+        }
+    }
+}
+
+impl Diagnostic {
+    /// Renders the message with the code it points at, optionally coloured with ANSI codes.
+    pub fn render(&self, colour: bool) -> String {
+        let painter = Painter { colour };
+
+        let snippets = self
+            .location
+            .iter()
+            .chain(self.labels.iter().map(|label| &label.snippet));
+        let width = snippets
+            .map(|snippet| snippet.line.to_string().len())
+            .max()
+            .unwrap_or(0);
+
+        let error = match self.code {
+            Some(code) => format!("error[{}]", code),
+            None => "error".to_string(),
+        };
+
+        let mut output = format!(
+            "{}{}",
+            painter.paint(&error, RED),
+            painter.paint(&format!(": {}", self.message), BOLD)
+        );
+
+        if let Some(location) = &self.location {
+            output += &painter.file(location, width, "-->");
+            output += &painter.snippet(location, width, '^', RED, None);
+        }
+
+        for label in &self.labels {
+            // Point out the file of labels which are elsewhere.
+            if self
+                .location
+                .as_ref()
+                .is_none_or(|location| location.file != label.snippet.file)
+            {
+                output += &painter.file(&label.snippet, width, ":::");
+            }
+
+            output += &painter.snippet(&label.snippet, width, '-', BLUE, Some(&label.message));
+        }
+
+        if !(self.notes.is_empty() && self.help.is_empty()) && width > 0 {
+            output += &format!("\n{}", painter.gutter(width));
+        }
+        for note in &self.notes {
+            output += &format!(
+                "\n{} {} {}",
+                " ".repeat(width),
+                painter.paint("= note:", BOLD),
+                note
+            );
+        }
+        for help in &self.help {
+            output += &format!(
+                "\n{} {} {}",
+                " ".repeat(width),
+                painter.paint("= help:", BOLD),
+                help
+            );
+        }
+
+        for line in &self.trace {
+            output += &format!("\n{}", line);
+        }
+
+        output
+    }
+}
+
+/// Applies ANSI styles to text, if colours are enabled.
+struct Painter {
+    colour: bool,
+}
+
+impl Painter {
+    fn paint(&self, text: &str, style: &str) -> String {
+        if self.colour {
+            format!("\x1b[{}m{}\x1b[0m", style, text)
+        } else {
+            text.to_string()
+        }
+    }
+
+    fn gutter(&self, width: usize) -> String {
+        self.paint(&format!("{} |", " ".repeat(width)), BLUE)
+    }
+
+    /// Renders the file, line and column of the snippet.
+    fn file(&self, snippet: &Snippet, width: usize, arrow: &str) -> String {
+        format!(
+            "\n{}{} {}:{}:{}",
+            " ".repeat(width),
+            self.paint(arrow, BLUE),
+            snippet.file,
+            snippet.line,
+            snippet.col
+        )
+    }
+
+    /// Renders the line of code, with marks under the part that is pointed at.
+    fn snippet(
+        &self,
+        snippet: &Snippet,
+        width: usize,
+        mark: char,
+        style: &str,
+        label: Option<&str>,
+    ) -> String {
+        let line_number = format!("{:>width$} |", snippet.line, width = width);
+
+        let mut marks = mark.to_string().repeat(snippet.len);
+        if let Some(label) = label {
+            marks = format!("{} {}", marks, label);
+        }
+
+        format!(
+            "\n{}\n{} {}\n{} {}{}",
+            self.gutter(width),
+            self.paint(&line_number, BLUE),
+            snippet.source,
+            self.gutter(width),
+            " ".repeat(snippet.col - 1),
+            self.paint(&marks, style)
+        )
+    }
+}
+
+impl Snippet {
+    /// The snippet of the code the span points at.
+    pub(crate) fn new(span: &Span, sources: &SourceMap) -> Self {
+        let code = sources.file_code(span.file);
+        let location = sources.location(span);
+
+        let line_start = code[..span.start].rfind('\n').map_or(0, |index| index + 1);
+        let line_end = code[span.start..]
+            .find('\n')
+            .map_or(code.len(), |index| span.start + index);
+        let end = span.end.min(line_end);
+
+        Snippet {
+            file: location.file,
+            line: location.line,
+            col: location.col,
+            len: code[span.start..end].chars().count().max(1),
+            source: code[line_start..line_end].to_string(),
+        }
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.render(false))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::diagnostic::{Diagnostic, DiagnosticKind, Snippet};
+    use crate::source::{SourceMap, Span};
+
+    #[test]
+    fn test_snippet() {
+        let mut sources = SourceMap::new();
+        sources.add("std.compost", "std\n");
+        sources.add("user.compost", "lets\n    Main: Int\n        1 + x\n");
+
+        let span = Span {
+            file: 1,
+            start: 31,
+            end: 32,
+        };
+        let snippet = Snippet::new(&span, &sources);
+
+        assert_eq!(
+            snippet,
+            Snippet {
+                file: "user.compost".to_string(),
+                line: 3,
+                col: 13,
+                len: 1,
+                source: "        1 + x".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let diagnostic = Diagnostic {
+            kind: DiagnosticKind::Semantic,
+            code: Some("C0004"),
+            message: "Something went wrong".to_string(),
+            location: Some(Snippet {
+                file: "user.compost".to_string(),
+                line: 12,
+                col: 3,
+                len: 2,
+                source: "1 + 2".to_string(),
+            }),
+            labels: vec![],
+            notes: vec!["Details".to_string()],
+            help: vec!["Suggestion".to_string()],
+            trace: vec![],
+        };
+
+        assert_eq!(
+            diagnostic.render(false),
+            "error[C0004]: Something went wrong\n  --> user.compost:12:3\n   |\n12 | 1 + 2\n   |   ^^\n   |\n   = note: Details\n   = help: Suggestion"
+        );
+        assert!(diagnostic.render(true).contains("\x1b[1;31m^^\x1b[0m"));
+    }
+}
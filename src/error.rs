@@ -1,41 +1,95 @@
+use crate::diagnostic::{Diagnostic, DiagnosticKind, Label, Snippet};
 use crate::lex::token::Token;
-use crate::lex::tokenizer::get_position_of_token;
+use crate::runtime::call_stack::Frame;
 use crate::sem::typ::Type;
-use std::ops::Range;
+use crate::source::{SourceMap, Span};
 
 /// An error during compilation.
 #[derive(Debug, PartialEq)]
 pub struct CompilationError {
     pub message: ErrorMessage,
-    pub context: Option<ErrorContext>,
+    /// Where the error occurred.
+    pub context: Option<Span>,
+    pub annotations: Vec<Annotation>,
+}
+
+/// Additional information about a compilation error.
+#[derive(Debug, PartialEq)]
+pub enum Annotation {
+    /// Another place in the code which helps to explain the error.
+    Label(Span, String),
+    /// A suggestion on how to fix the error.
+    Help(String),
 }
 
 impl CompilationError {
-    pub fn to_string(&self, std_code: &str, all_code: &str) -> String {
+    pub fn to_diagnostic(&self, kind: DiagnosticKind, sources: &SourceMap) -> Diagnostic {
         let message = String::from(&self.message);
+        let mut lines = message.lines();
 
-        match &self.context {
-            Some(context) => {
-                let position = context.get_position_in_code(all_code);
-                let (line, col) = get_line_and_col_number(std_code, all_code, position);
-                format!("{} at line {} col {}", message, line, col)
-            }
-            None => message,
+        Diagnostic {
+            kind,
+            code: self.message.code(),
+            message: lines.next().unwrap_or_default().to_string(),
+            location: self
+                .context
+                .as_ref()
+                .map(|context| Snippet::new(context, sources)),
+            labels: self
+                .annotations
+                .iter()
+                .filter_map(|annotation| match annotation {
+                    Annotation::Label(context, message) => Some(Label {
+                        snippet: Snippet::new(context, sources),
+                        message: message.clone(),
+                    }),
+                    Annotation::Help(_) => None,
+                })
+                .collect(),
+            notes: lines.map(|line| line.trim().to_string()).collect(),
+            help: self
+                .annotations
+                .iter()
+                .filter_map(|annotation| match annotation {
+                    Annotation::Help(message) => Some(message.clone()),
+                    Annotation::Label(_, _) => None,
+                })
+                .collect(),
+            trace: vec![],
         }
     }
 
-    pub fn context(self, context: ErrorContext) -> Self {
+    pub fn context(self, context: Span) -> Self {
         CompilationError {
-            message: self.message,
             context: Some(context),
+            ..self
         }
     }
+
+    /// Adds a label pointing at another place in the code.
+    pub fn label(mut self, context: Span, message: &str) -> Self {
+        self.annotations
+            .push(Annotation::Label(context, message.to_string()));
+        self
+    }
+
+    /// Adds a suggestion on how to fix the error.
+    pub fn help(mut self, message: &str) -> Self {
+        self.annotations.push(Annotation::Help(message.to_string()));
+        self
+    }
 }
 
 /// A compilation error message.
 #[derive(Debug, PartialEq)]
 pub enum ErrorMessage {
     UnexpectedChar(String),
+    /// A string literal without closing quotes.
+    UnterminatedString,
+    /// An escape in a string literal which doesn't stand for a character.
+    InvalidEscape(String),
+    /// An integer literal which doesn't fit in an Int.
+    IntegerTooLarge(String),
     UnexpectedToken(Token, Option<String>),
     NoSelf,
     NoResolution(&'static str, String),
@@ -46,15 +100,63 @@ pub enum ErrorMessage {
     DuplicateStruct(String),
     ClassAndStruct(String),
     UnknownRawType(String),
+    /// A trait without a built-in operation for raw values, which is a bug in the compiler.
     UndefinedTrait(String),
     MissingInput(String),
-    TypeMismatch(String, Type, Type),
+    /// The types are boxed to keep errors small.
+    TypeMismatch(String, Box<Type>, Box<Type>),
+    /// An import of a file which can't be read, with the path of the file.
+    NoImport(String, String),
+    /// An import which leads back to the importing file, with the files in the cycle.
+    ImportCycle(String),
+    /// A reference to a private item from outside of where it is declared.
+    Private(&'static str, String),
+}
+
+impl ErrorMessage {
+    /// The stable identifier of this kind of error, which can be explained with `compost explain`.
+    /// Errors which point at a bug in the compiler have none.
+    pub fn code(&self) -> Option<&'static str> {
+        let code = match self {
+            ErrorMessage::UnexpectedChar(_) => "C0001",
+            ErrorMessage::UnexpectedToken(_, _) => "C0002",
+            ErrorMessage::NoSelf => "C0003",
+            ErrorMessage::NoResolution(_, _) => "C0004",
+            ErrorMessage::DoubleDeclaration(_, _) => "C0005",
+            ErrorMessage::NoModuleOrTrait(_) => "C0006",
+            ErrorMessage::NoTrait(_) => "C0007",
+            ErrorMessage::DuplicateClass(_) => "C0008",
+            ErrorMessage::DuplicateStruct(_) => "C0009",
+            ErrorMessage::ClassAndStruct(_) => "C0010",
+            ErrorMessage::UnknownRawType(_) => "C0011",
+            ErrorMessage::UndefinedTrait(_) => return None,
+            ErrorMessage::MissingInput(_) => "C0013",
+            ErrorMessage::TypeMismatch(_, _, _) => "C0014",
+            ErrorMessage::NoImport(_, _) => "C0015",
+            ErrorMessage::ImportCycle(_) => "C0016",
+            ErrorMessage::Private(_, _) => "C0017",
+            ErrorMessage::UnterminatedString => "C0018",
+            ErrorMessage::InvalidEscape(_) => "C0019",
+            ErrorMessage::IntegerTooLarge(_) => "C0020",
+        };
+
+        Some(code)
+    }
 }
 
 impl From<&ErrorMessage> for String {
     fn from(message: &ErrorMessage) -> Self {
         match message {
             ErrorMessage::UnexpectedChar(char) => format!("Unexpected character {}", char),
+            ErrorMessage::UnterminatedString => "Unterminated string".to_string(),
+            ErrorMessage::InvalidEscape(escape) => {
+                format!("Invalid escape '{}' in string", escape)
+            }
+            ErrorMessage::IntegerTooLarge(digits) => format!(
+                "Integer {} is too large, the largest Int is {}",
+                digits,
+                i64::MAX
+            ),
             ErrorMessage::UnexpectedToken(token, expectation) => match expectation {
                 Some(expectation) => {
                     format!("Unexpected token {:?}, expecting {}", token, expectation)
@@ -84,7 +186,10 @@ impl From<&ErrorMessage> for String {
             ),
             ErrorMessage::UnknownRawType(typ) => format!("Unknown raw type '{}'", typ),
             ErrorMessage::UndefinedTrait(trait_name) => {
-                format!("Trait '{}' is not available for this type", trait_name)
+                format!(
+                    "Internal error: trait '{}' has no operation for raw values, please report this as a bug",
+                    trait_name
+                )
             }
             ErrorMessage::MissingInput(name) => format!("Missing input for '{}'", name),
             ErrorMessage::TypeMismatch(name, expected, given) => {
@@ -93,52 +198,155 @@ impl From<&ErrorMessage> for String {
                     name, expected, given
                 )
             }
+            ErrorMessage::NoImport(path, file) => {
+                format!(
+                    "Unable to import '{}', the file '{}' can't be read",
+                    path, file
+                )
+            }
+            ErrorMessage::ImportCycle(files) => format!("Import cycle: {}", files),
+            ErrorMessage::Private(typ, name) => format!("{} '{}' is private", typ, name),
         }
     }
 }
 
-/// Where the error occurred.
-#[derive(Debug, Eq, PartialEq, Clone)]
-pub enum ErrorContext {
-    Character(usize),
-    Token(usize),
-    TokenRange(Range<usize>),
+pub fn error<T>(message: ErrorMessage) -> CResult<T> {
+    Err(CompilationError {
+        message,
+        context: None,
+        annotations: vec![],
+    })
+}
+
+pub type CResult<T> = Result<T, CompilationError>;
+
+/// The number of frames shown at either end of a long traceback.
+const TRACE_EDGE: usize = 10;
+
+/// An error during the evaluation of a program.
+#[derive(Debug, PartialEq)]
+pub struct RuntimeError {
+    pub message: RuntimeErrorMessage,
+    /// The full name of the trait or let that was being called.
+    pub callee: Option<String>,
+    pub context: Option<Span>,
+    /// The Compost call stack at the moment of the error, outermost call first.
+    pub trace: Vec<Frame>,
 }
 
-impl ErrorContext {
-    pub fn get_position_in_code(&self, code: &str) -> usize {
-        match self {
-            ErrorContext::Character(position) => *position,
-            ErrorContext::Token(token_number) => get_position_of_token(code, *token_number),
-            ErrorContext::TokenRange(range) => get_position_of_token(code, range.start),
+impl RuntimeError {
+    pub fn to_diagnostic(&self, sources: &SourceMap) -> Diagnostic {
+        let mut message = String::from(&self.message);
+
+        if let Some(callee) = &self.callee {
+            message = format!("{} in '{}'", message, callee);
+        }
+
+        let mut trace = vec![];
+
+        if !self.trace.is_empty() {
+            trace.push("Traceback (most recent call last):".to_string());
+
+            for (index, frame) in self.trace.iter().enumerate() {
+                // Leave out the middle of very deep traces, as in runaway recursion.
+                let omitted = self.trace.len().saturating_sub(2 * TRACE_EDGE);
+                if omitted > 0 && index >= TRACE_EDGE && index < TRACE_EDGE + omitted {
+                    if index == TRACE_EDGE {
+                        trace.push(format!("  ... {} more calls ...", omitted));
+                    }
+                    continue;
+                }
+
+                if frame.omitted_tail_calls > 0 {
+                    trace.push(format!(
+                        "  ... {} more tail calls ...",
+                        frame.omitted_tail_calls
+                    ));
+                }
+
+                let location = sources.location(&frame.span);
+                trace.push(format!(
+                    "  {} at {}:{}:{}",
+                    frame, location.file, location.line, location.col
+                ));
+            }
+        }
+
+        Diagnostic {
+            kind: DiagnosticKind::Runtime,
+            code: None,
+            message,
+            location: self
+                .context
+                .as_ref()
+                .map(|context| Snippet::new(context, sources)),
+            labels: vec![],
+            notes: vec![],
+            help: vec![],
+            trace,
         }
     }
 }
 
-pub fn get_line_and_col_number(std_code: &str, all_code: &str, position: usize) -> (usize, usize) {
-    let lines_in_std_code = std_code.chars().filter(|char| *char == '\n').count();
-    let mut line = 1;
-    let mut col = 1;
-    let mut chars = all_code.chars();
-
-    for _ in 0..position {
-        let char = chars.next().unwrap();
-        if char == '\n' {
-            line += 1;
-            col = 1;
-        } else {
-            col += 1;
+/// A runtime error message.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RuntimeErrorMessage {
+    DivisionByZero,
+    IntegerOverflow,
+    MissingInput(String),
+    MissingLocal(String),
+    MissingField(String),
+    NoSelf,
+    CallOnVoid(String),
+    UndefinedTrait(String),
+    UnsupportedOperation(String, &'static str),
+    NoMatchingBranch(String),
+    NotABool,
+    RecursionLimitExceeded(usize),
+    StepBudgetExhausted(u64),
+}
+
+impl From<&RuntimeErrorMessage> for String {
+    fn from(message: &RuntimeErrorMessage) -> Self {
+        match message {
+            RuntimeErrorMessage::DivisionByZero => "Division by zero".to_string(),
+            RuntimeErrorMessage::IntegerOverflow => "Integer overflow".to_string(),
+            RuntimeErrorMessage::MissingInput(name) => format!("Missing input '{}'", name),
+            RuntimeErrorMessage::MissingLocal(name) => {
+                format!("Local variable '{}' is not available", name)
+            }
+            RuntimeErrorMessage::MissingField(name) => format!("Field '{}' does not exist", name),
+            RuntimeErrorMessage::NoSelf => "'Self' is not available".to_string(),
+            RuntimeErrorMessage::CallOnVoid(trait_name) => {
+                format!("Can't call trait '{}' on void", trait_name)
+            }
+            RuntimeErrorMessage::UndefinedTrait(trait_name) => {
+                format!("Trait '{}' is not defined on this instance", trait_name)
+            }
+            RuntimeErrorMessage::UnsupportedOperation(trait_name, typ) => {
+                format!("Operation '{}' is not supported by {}", trait_name, typ)
+            }
+            RuntimeErrorMessage::NoMatchingBranch(local_name) => {
+                format!("None of the branches for '{}' matched", local_name)
+            }
+            RuntimeErrorMessage::NotABool => "Value can't be resolved to a bool".to_string(),
+            RuntimeErrorMessage::RecursionLimitExceeded(max_depth) => {
+                format!("Recursion limit exceeded (max depth: {})", max_depth)
+            }
+            RuntimeErrorMessage::StepBudgetExhausted(fuel) => {
+                format!("Step budget exhausted (fuel: {})", fuel)
+            }
         }
     }
-
-    (line - lines_in_std_code, col)
 }
 
-pub fn error<T>(message: ErrorMessage) -> CResult<T> {
-    Err(CompilationError {
+pub fn runtime_error<T>(message: RuntimeErrorMessage) -> RResult<T> {
+    Err(RuntimeError {
         message,
+        callee: None,
         context: None,
+        trace: vec![],
     })
 }
 
-pub type CResult<T> = Result<T, CompilationError>;
+pub type RResult<T> = Result<T, RuntimeError>;
@@ -0,0 +1,36 @@
+/// Longer explanations of the compilation errors by their code, with examples of the error and
+/// its fix.
+const EXPLANATIONS: [(&str, &str); 19] = [
+    ("C0001", include_str!("resources/explanations/C0001.md")),
+    ("C0002", include_str!("resources/explanations/C0002.md")),
+    ("C0003", include_str!("resources/explanations/C0003.md")),
+    ("C0004", include_str!("resources/explanations/C0004.md")),
+    ("C0005", include_str!("resources/explanations/C0005.md")),
+    ("C0006", include_str!("resources/explanations/C0006.md")),
+    ("C0007", include_str!("resources/explanations/C0007.md")),
+    ("C0008", include_str!("resources/explanations/C0008.md")),
+    ("C0009", include_str!("resources/explanations/C0009.md")),
+    ("C0010", include_str!("resources/explanations/C0010.md")),
+    ("C0011", include_str!("resources/explanations/C0011.md")),
+    ("C0013", include_str!("resources/explanations/C0013.md")),
+    ("C0014", include_str!("resources/explanations/C0014.md")),
+    ("C0015", include_str!("resources/explanations/C0015.md")),
+    ("C0016", include_str!("resources/explanations/C0016.md")),
+    ("C0017", include_str!("resources/explanations/C0017.md")),
+    ("C0018", include_str!("resources/explanations/C0018.md")),
+    ("C0019", include_str!("resources/explanations/C0019.md")),
+    ("C0020", include_str!("resources/explanations/C0020.md")),
+];
+
+/// The explanation of the error with the given code, as in "C0004".
+pub fn explain(code: &str) -> Option<&'static str> {
+    EXPLANATIONS
+        .iter()
+        .find(|(known_code, _)| known_code.eq_ignore_ascii_case(code))
+        .map(|(_, explanation)| *explanation)
+}
+
+/// The codes of all errors which have an explanation, in order.
+pub fn error_codes() -> impl Iterator<Item = &'static str> {
+    EXPLANATIONS.iter().map(|(code, _)| *code)
+}
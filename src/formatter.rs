@@ -0,0 +1,1432 @@
+use crate::ast::abstract_syntax_tree::AbstractSyntaxTree;
+use crate::ast::class_statement::ClassStatement;
+use crate::ast::def_statement::DefStatement;
+use crate::ast::expr::if_else_call::IfElseCall;
+use crate::ast::expression::{BinaryOp, Expression, ExpressionStatement, UnaryOp};
+use crate::ast::import_statement::ImportStatement;
+use crate::ast::let_statement::LetStatement;
+use crate::ast::module_statement::ModuleStatement;
+use crate::ast::raw_value::RawValue;
+use crate::ast::struct_statement::StructStatement;
+use crate::ast::trait_statement::TraitStatement;
+use crate::ast::type_statement::{RawType, TypeStatement, TypeStatementType};
+use crate::ast::using_statement::SingleUsingStatement;
+use crate::ast::Statement;
+use crate::error::CResult;
+use crate::lex::concrete_syntax_tree::ConcreteSyntaxTree;
+use crate::lex::token::{Kw, Token};
+use crate::lex::tokenizer::concrete_syntax_tree;
+use crate::source::{FileId, SourceMap};
+use std::cmp::max;
+use std::collections::HashMap;
+
+/// The width within which an expression is written on a single line, when it fits.
+const WIDTH: usize = 80;
+
+/// The number of spaces of each level of indentation.
+const INDENT: usize = 4;
+
+/// Writes the code of a file in the canonical layout, keeping its comments. Declarations are
+/// written one per line, and expressions on a single line when they fit within the width.
+pub fn format(sources: &SourceMap, file: FileId, ast: &AbstractSyntaxTree) -> CResult<String> {
+    let tree = concrete_syntax_tree(sources, file)?;
+    let tokens = tree.tokens();
+    let tokens = (0..tokens.remaining().len())
+        .map(|index| {
+            (
+                tokens.span(index).start,
+                tokens.remaining()[index].0.clone(),
+            )
+        })
+        .collect();
+
+    let code = sources.file_code(file);
+    let mut formatter = Formatter {
+        code,
+        tokens,
+        comments: comments(code, &tree),
+        written: 0,
+        lines: vec![],
+        indents: vec![],
+        separate: false,
+    };
+
+    formatter.tree(ast);
+
+    Ok(formatter.lines.join("\n") + "\n")
+}
+
+/// A comment in the code being formatted.
+struct Comment {
+    start: usize,
+    text: String,
+    /// Whether there is code before the comment on its line.
+    trailing: bool,
+    /// The indentation of the line of the comment.
+    indent: usize,
+}
+
+fn comments(code: &str, tree: &ConcreteSyntaxTree) -> Vec<Comment> {
+    tree.comments()
+        .map(|(comment, trailing)| Comment {
+            start: comment.span.start,
+            text: comment.text.trim_end().to_string(),
+            trailing,
+            indent: indentation(code, comment.span.start),
+        })
+        .collect()
+}
+
+/// The position of the start of the line the position is on.
+fn line_start(code: &str, position: usize) -> usize {
+    code[..position]
+        .rfind('\n')
+        .map_or(0, |newline| newline + 1)
+}
+
+/// The number of spaces the line the position is on starts with.
+fn indentation(code: &str, position: usize) -> usize {
+    let start = line_start(code, position);
+
+    code[start..].len() - code[start..].trim_start_matches(' ').len()
+}
+
+/// Whether the text fits within the width when written at the indentation after the prefix. Each
+/// line of a multi-line string has to fit.
+fn fits(indent: usize, prefix: &str, text: &str) -> bool {
+    let prefix = prefix.rsplit('\n').next().unwrap_or_default();
+
+    text.split('\n').enumerate().all(|(number, line)| {
+        let start = match number {
+            0 => indent + prefix.chars().count(),
+            _ => indent,
+        };
+        start + line.chars().count() <= WIDTH
+    })
+}
+
+/// The text with the lines of its multi-line strings on the indentation of the line they start
+/// on, which the lexer takes off them again.
+fn indented(text: &str, indent: usize) -> String {
+    text.split('\n')
+        .enumerate()
+        .map(|(number, line)| match number {
+            0 => line.to_string(),
+            _ if line.is_empty() => String::new(),
+            _ => format!("{}{}", " ".repeat(indent), line),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// What follows an expression on the lines after it, which it must not take in.
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum Follow {
+    /// Nothing which is as deep as the expression.
+    Nothing,
+    /// A line on the indentation of the first line of the expression.
+    Line,
+    /// A line on the indentation which continues the expression.
+    Continuation,
+}
+
+/// The indentation of the lines which continue an expression. They are deeper than its first
+/// line when that starts with something else.
+fn next_indent(indent: usize, prefix: &str) -> usize {
+    if prefix.is_empty() {
+        indent
+    } else {
+        indent + INDENT
+    }
+}
+
+/// Whether an operand of an operation with the precedence has to be grouped, so that it isn't
+/// taken apart by the operations around it, or takes in those which follow it.
+fn needs_group(operand: &ExpressionStatement, precedence: usize, rhs: bool, last: bool) -> bool {
+    match &operand.expression {
+        Expression::Binary(call) => {
+            call.op.precedence() < precedence || rhs && call.op.precedence() == precedence
+        }
+        Expression::IfElse(_) | Expression::Match(_) => !last,
+        _ => false,
+    }
+}
+
+/// Whether the subject of a unary operation, or of a call of a trait, has to be grouped.
+fn is_grouped(subject: &ExpressionStatement, call: bool) -> bool {
+    match subject.expression {
+        Expression::Binary(_) | Expression::IfElse(_) | Expression::Match(_) => true,
+        Expression::Unary(_) => call,
+        _ => false,
+    }
+}
+
+/// Whether the first line of the expression holds a group within parentheses, which would take in
+/// the deeper lines after it.
+fn starts_with_group(expr: &ExpressionStatement) -> bool {
+    match &expr.expression {
+        Expression::Binary(call) => {
+            needs_group(&call.lhs, call.op.precedence(), false, false)
+                || starts_with_group(&call.lhs)
+        }
+        Expression::Unary(call) => {
+            is_grouped(&call.subject, false) || starts_with_group(&call.subject)
+        }
+        Expression::Def(call) => {
+            is_grouped(&call.subject, true) || starts_with_group(&call.subject)
+        }
+        _ => false,
+    }
+}
+
+/// The section of a module which an item is declared in.
+enum Section<'a> {
+    Class(&'a ClassStatement),
+    Struct(&'a StructStatement),
+    Using(&'a SingleUsingStatement),
+    Trait(&'a TraitStatement),
+    Def(&'a DefStatement),
+    Let(&'a LetStatement),
+}
+
+impl Section<'_> {
+    fn start(&self) -> usize {
+        match self {
+            Section::Class(class) => class.span().start,
+            Section::Struct(strukt) => strukt.span().start,
+            Section::Using(using) => using.span().start,
+            Section::Trait(trayt) => trayt.span().start,
+            Section::Def(def) => def.span().start,
+            Section::Let(lett) => lett.span().start,
+        }
+    }
+
+    /// The keyword which starts the section.
+    fn keyword(&self) -> Kw {
+        match self {
+            Section::Class(_) => Kw::Class,
+            Section::Struct(_) => Kw::Struct,
+            Section::Using(_) => Kw::Using,
+            Section::Trait(_) => Kw::Traits,
+            Section::Def(_) => Kw::Defs,
+            Section::Let(_) => Kw::Lets,
+        }
+    }
+}
+
+/// A statement at the top of a file.
+enum Item<'a> {
+    Import(&'a ImportStatement),
+    Module(&'a ModuleStatement),
+    Let(&'a LetStatement),
+}
+
+struct Formatter<'a> {
+    code: &'a str,
+    /// The start of each token, with the token.
+    tokens: Vec<(usize, Token)>,
+    comments: Vec<Comment>,
+    /// The number of comments which have been written.
+    written: usize,
+    lines: Vec<String>,
+    /// The indentation of each line of code that has been written, in the code and in the output.
+    indents: Vec<(usize, usize)>,
+    /// Whether the next line is separated from the previous one by an empty line.
+    separate: bool,
+}
+
+/// What has been written at a point, to go back to.
+struct Checkpoint {
+    lines: usize,
+    /// The last line, which trailing comments are added to.
+    last: Option<String>,
+    indents: usize,
+    written: usize,
+    separate: bool,
+}
+
+impl Formatter<'_> {
+    fn tree(&mut self, ast: &AbstractSyntaxTree) {
+        let mut items: Vec<(usize, Item)> = ast
+            .imports
+            .iter()
+            .map(|import| (import.span().start, Item::Import(import)))
+            .chain(
+                ast.mods
+                    .iter()
+                    .map(|module| (module.span().start, Item::Module(module))),
+            )
+            .chain(
+                ast.lets
+                    .iter()
+                    .map(|lett| (lett.span().start, Item::Let(lett))),
+            )
+            .collect();
+        items.sort_by_key(|(start, _)| *start);
+
+        let mut previous: Option<&Item> = None;
+        for (start, item) in &items {
+            match item {
+                Item::Import(import) => {
+                    self.separate = previous.is_some_and(|item| !matches!(item, Item::Import(_)));
+                    self.item(0, *start, format!("import {}", import.path));
+                }
+                Item::Module(module) => {
+                    self.separate = previous.is_some();
+                    self.module(module);
+                }
+                Item::Let(lett) => {
+                    // Lets which follow each other are in one section.
+                    if !matches!(previous, Some(Item::Let(_))) {
+                        self.separate = previous.is_some();
+                        let keyword = self.keyword_before(Kw::Lets, *start);
+                        self.item(0, keyword, "lets".to_string());
+                    }
+
+                    self.lett(lett, INDENT);
+                }
+            }
+
+            previous = Some(item);
+        }
+
+        // The comments after the last statement.
+        self.comments_before(usize::MAX, 0, 0, true);
+    }
+
+    fn module(&mut self, module: &ModuleStatement) {
+        let private = if module.private { "private " } else { "" };
+        self.item(
+            0,
+            module.span().start,
+            format!("{}mod {}", private, module.name),
+        );
+
+        let mut sections: Vec<Section> = module
+            .class
+            .iter()
+            .map(Section::Class)
+            .chain(module.strukt.iter().map(Section::Struct))
+            .chain(module.using.iter().map(Section::Using))
+            .chain(module.traits.iter().map(Section::Trait))
+            .chain(module.defs.iter().map(Section::Def))
+            .chain(module.lets.iter().map(Section::Let))
+            .collect();
+        sections.sort_by_key(Section::start);
+
+        let mut previous: Option<Kw> = None;
+        for section in &sections {
+            let keyword = section.keyword();
+
+            match section {
+                Section::Class(class) => {
+                    self.item(INDENT, class.span().start, "class".to_string());
+
+                    for (name, typ) in &class.dependencies {
+                        self.item(
+                            INDENT * 2,
+                            typ.span().start,
+                            format!("{}: {}", name, type_text(typ)),
+                        );
+                    }
+                }
+                Section::Struct(strukt) => self.strukt(strukt),
+                _ => {
+                    // Items which follow each other in sections of the same kind are in one.
+                    if previous.as_ref() != Some(&keyword) {
+                        let start = self.keyword_before(keyword.clone(), section.start());
+                        self.item(INDENT, start, keyword_text(&keyword).to_string());
+                    }
+
+                    match section {
+                        Section::Using(using) => {
+                            let wildcard = if using.wildcard { "*" } else { "" };
+                            self.item(
+                                INDENT * 2,
+                                using.span().start,
+                                format!("{}{}", using.name, wildcard),
+                            );
+                        }
+                        Section::Trait(trayt) => {
+                            let private = if trayt.private { "private " } else { "" };
+                            self.item(
+                                INDENT * 2,
+                                trayt.span().start,
+                                format!(
+                                    "{}{}: {}",
+                                    private,
+                                    trayt.name,
+                                    signature(&trayt.parameters, &trayt.output)
+                                ),
+                            );
+                        }
+                        Section::Def(def) => self.def(def),
+                        Section::Let(lett) => self.lett(lett, INDENT * 2),
+                        Section::Class(_) | Section::Struct(_) => unreachable!(),
+                    }
+                }
+            }
+
+            previous = Some(keyword);
+        }
+    }
+
+    fn strukt(&mut self, strukt: &StructStatement) {
+        let start = strukt.span().start;
+        self.item(INDENT, start, "struct".to_string());
+
+        // Fields have no spans, but each is a name token followed by a type token.
+        let keyword = self
+            .tokens
+            .iter()
+            .position(|(token_start, _)| *token_start == start)
+            .unwrap_or_default();
+
+        for (index, (name, typ)) in strukt.fields.iter().enumerate() {
+            let field_start = self
+                .tokens
+                .get(keyword + 1 + index * 2)
+                .map_or(start, |(token_start, _)| *token_start);
+            let typ = match typ {
+                RawType::Int => "int",
+                RawType::String => "string",
+                RawType::Bool => "bool",
+            };
+
+            self.item(INDENT * 2, field_start, format!("{}: {}", name, typ));
+        }
+    }
+
+    fn def(&mut self, def: &DefStatement) {
+        let indent = INDENT * 2;
+        let prefix = format!("{}: ", def.name);
+
+        match self.inline(&def.expr, false) {
+            Some(inline) if fits(indent, &prefix, &inline) => {
+                self.item(indent, def.span().start, prefix + &inline)
+            }
+            _ => {
+                self.item(indent, def.span().start, def.name.clone());
+                let start = def.expr.span().start;
+                self.expression(&def.expr, indent + INDENT, start, "", Follow::Nothing);
+            }
+        }
+    }
+
+    fn lett(&mut self, lett: &LetStatement, indent: usize) {
+        let private = if lett.private { "private " } else { "" };
+        self.item(
+            indent,
+            lett.span().start,
+            format!(
+                "{}{}: {}",
+                private,
+                lett.name,
+                signature(&lett.parameters, &lett.output)
+            ),
+        );
+
+        let start = lett.expr.span().start;
+        self.expression(&lett.expr, indent + INDENT, start, "", Follow::Nothing);
+    }
+
+    /// Writes an expression after the prefix, starting on a line at the indentation, so that it
+    /// doesn't take in what follows it.
+    fn expression(
+        &mut self,
+        expr: &ExpressionStatement,
+        indent: usize,
+        start: usize,
+        prefix: &str,
+        follow: Follow,
+    ) {
+        // Without a prefix, the expression is continued on the indentation of its first line.
+        let follow = match follow {
+            Follow::Line if prefix.is_empty() => Follow::Continuation,
+            follow => follow,
+        };
+
+        if let Some(inline) = self.inline(expr, follow != Follow::Nothing) {
+            if fits(indent, prefix, &inline) {
+                self.line(indent, start, format!("{}{}", prefix, inline));
+                return;
+            }
+        }
+
+        let next = next_indent(indent, prefix);
+
+        match &expr.expression {
+            Expression::Binary(_) => {
+                let checkpoint = self.checkpoint();
+                if !self.operations(expr, indent, start, prefix) {
+                    // The lines which continue the operations would be taken in by a call or group
+                    // that ends the first one, so the operations start on a line of their own.
+                    self.restore(checkpoint);
+                    self.line(indent, start, prefix.trim_end().to_string());
+                    self.operations(expr, next, expr.span().start, "");
+                }
+            }
+            // A subject which would take in what follows it goes on a deeper line, which groups it.
+            Expression::Unary(call)
+                if is_grouped(&call.subject, false) || starts_with_group(&call.subject) =>
+            {
+                self.line(indent, start, format!("{}{}", prefix, unary_op(&call.op)));
+                self.expression(
+                    &call.subject,
+                    next + INDENT,
+                    call.subject.span().start,
+                    "",
+                    Follow::Nothing,
+                );
+            }
+            Expression::Unary(call) => {
+                let prefix = format!("{}{}", prefix, unary_op(&call.op));
+                self.expression(&call.subject, indent, start, &prefix, Follow::Nothing);
+            }
+            Expression::Let(call) => {
+                let end = expr.span().end;
+                self.call(&call.name, &call.inputs, indent, (start, end), prefix)
+            }
+            Expression::Def(_) => self.calls(expr, indent, start, prefix),
+            Expression::Match(call) => {
+                // What continues the match would be taken as branches when the match doesn't
+                // start its line, so then it goes on the next one.
+                if follow == Follow::Continuation && !prefix.is_empty() {
+                    self.line(indent, start, prefix.trim_end().to_string());
+                    self.expression(expr, next, expr.span().start, "", follow);
+                    return;
+                }
+
+                let prefix = format!("{}match {}: ", prefix, call.local_name);
+                self.expression(&call.subject, indent, start, &prefix, Follow::Nothing);
+
+                for (typ, branch) in &call.branches {
+                    let typ_text = type_text(typ);
+                    let branch_prefix = format!("{}: ", typ_text);
+
+                    match self.inline(branch, false) {
+                        Some(inline) if fits(indent + INDENT, &branch_prefix, &inline) => {
+                            self.line(indent + INDENT, typ.span().start, branch_prefix + &inline)
+                        }
+                        _ => {
+                            self.line(indent + INDENT, typ.span().start, typ_text);
+                            self.expression(
+                                branch,
+                                indent + INDENT * 2,
+                                branch.span().start,
+                                "",
+                                Follow::Nothing,
+                            );
+                        }
+                    }
+                }
+            }
+            Expression::IfElse(call) => self.if_else(call, indent, start, prefix, follow),
+            // The rest fits on a line, or can't be split over more.
+            _ => {
+                let text = self.inline(expr, false).unwrap_or_default();
+                self.line(indent, start, format!("{}{}", prefix, text));
+            }
+        }
+    }
+
+    /// Writes a chain of binary operations with the same precedence, putting as many operands on
+    /// a line as fit. It stops and returns false when a line it starts wouldn't continue the chain.
+    fn operations(
+        &mut self,
+        expr: &ExpressionStatement,
+        indent: usize,
+        start: usize,
+        prefix: &str,
+    ) -> bool {
+        let Expression::Binary(call) = &expr.expression else {
+            return true;
+        };
+        let precedence = call.op.precedence();
+
+        // Operations are applied from left to right, so the left-hand side holds the rest of the
+        // chain.
+        let mut operands = vec![];
+        let mut rest = expr;
+        while let Expression::Binary(call) = &rest.expression {
+            if call.op.precedence() != precedence {
+                break;
+            }
+
+            operands.push((Some(&call.op), call.rhs.as_ref()));
+            rest = &call.lhs;
+        }
+        operands.push((None, rest));
+        operands.reverse();
+
+        let next = next_indent(indent, prefix);
+
+        // Whether the last line ends with an operand, so that the next one can be added to it.
+        let mut open = false;
+        for (index, (op, operand)) in operands.iter().enumerate() {
+            let last = index == operands.len() - 1;
+            let grouped = needs_group(operand, precedence, op.is_some(), last);
+            let inline = match grouped {
+                true => self
+                    .inline(operand, false)
+                    .map(|inline| format!("({})", inline)),
+                false => self.inline(operand, !last),
+            };
+
+            let (line_indent, line_prefix, line_start) = match op {
+                None => (indent, prefix.to_string(), start),
+                Some(op) => {
+                    let previous_end = operands[index - 1].1.span().end;
+                    let op_start = self.token_after(previous_end);
+
+                    if let Some(inline) = &inline {
+                        let addition = format!(" {} {}", binary_op(op), inline);
+                        let line = self.lines.last().map_or("", String::as_str);
+
+                        // A comment stays on the line of the token before it, so it ends the line
+                        // of the operand before, or of the operand which follows its operator.
+                        if open
+                            && !self.has_comment(previous_end, op_start)
+                            && fits(0, line, &addition)
+                        {
+                            let line = self.lines.last_mut().unwrap();
+                            let line_indent = line.len() - line.trim_start().len();
+                            line.push_str(&indented(&addition, line_indent));
+                            open = !self.has_comment(op_start, operand.span().end);
+                            continue;
+                        }
+                    }
+
+                    if !self.continues(next) {
+                        return false;
+                    }
+
+                    // An operand on a deeper line is grouped, without parentheses. Lines which
+                    // continue a group within parentheses can't be deeper than it either.
+                    let op_prefix = format!("{} ", binary_op(op));
+                    if (grouped || starts_with_group(operand))
+                        && !inline
+                            .as_ref()
+                            .is_some_and(|inline| fits(next, &op_prefix, inline))
+                    {
+                        self.line(next, op_start, binary_op(op).to_string());
+                        self.expression(
+                            operand,
+                            next + INDENT,
+                            operand.span().start,
+                            "",
+                            Follow::Nothing,
+                        );
+                        open = false;
+                        continue;
+                    }
+
+                    (next, op_prefix, op_start)
+                }
+            };
+
+            match inline {
+                Some(inline) if fits(line_indent, &line_prefix, &inline) || grouped => {
+                    self.line(line_indent, line_start, line_prefix + &inline);
+                    open = !self.has_comment(line_start, operand.span().end);
+                }
+                _ => {
+                    let follow = match index {
+                        _ if last => Follow::Nothing,
+                        0 => Follow::Continuation,
+                        _ => Follow::Line,
+                    };
+                    self.expression(operand, line_indent, line_start, &line_prefix, follow);
+                    open = false;
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Writes a chain of calls of traits, each on its own line after the subject.
+    fn calls(&mut self, expr: &ExpressionStatement, indent: usize, start: usize, prefix: &str) {
+        let mut calls = vec![];
+        let mut subject = expr;
+        while let Expression::Def(call) = &subject.expression {
+            calls.push((call, subject.span().end));
+            subject = &call.subject;
+        }
+        calls.reverse();
+
+        let mut calls = calls.into_iter();
+        if matches!(subject.expression, Expression::Zelf) {
+            // Calls on Self start with their dot.
+            let (call, end) = calls.next().unwrap();
+            let prefix = format!("{}.", prefix);
+            self.call(&call.name, &call.inputs, indent, (start, end), &prefix);
+        } else if let Some(inline) = self
+            .inline(subject, false)
+            .filter(|_| is_grouped(subject, true))
+        {
+            self.line(indent, start, format!("{}({})", prefix, inline));
+        } else {
+            self.expression(subject, indent, start, prefix, Follow::Continuation);
+        }
+
+        for (call, end) in calls {
+            let call_start = self.token_after(call.subject.span().end);
+            let next = next_indent(indent, prefix);
+            self.call(&call.name, &call.inputs, next, (call_start, end), ".");
+        }
+    }
+
+    /// Writes a call of a let or trait, with its inputs within parentheses when they fit, and
+    /// otherwise each on its own line. It takes up the code between the start and end.
+    fn call(
+        &mut self,
+        name: &str,
+        inputs: &HashMap<String, ExpressionStatement>,
+        indent: usize,
+        (start, end): (usize, usize),
+        prefix: &str,
+    ) {
+        if let Some(inline) = self.inline_call(name, inputs) {
+            if fits(indent, prefix, &inline) && !self.splits(start, end) {
+                self.line(indent, start, format!("{}{}", prefix, inline));
+                return;
+            }
+        }
+
+        self.line(indent, start, format!("{}{}", prefix, name));
+
+        for (name, input) in ordered(inputs) {
+            let prefix = format!("{}: ", name);
+            self.expression(
+                input,
+                indent + INDENT,
+                input.span().start,
+                &prefix,
+                Follow::Nothing,
+            );
+        }
+    }
+
+    fn if_else(
+        &mut self,
+        call: &IfElseCall,
+        indent: usize,
+        start: usize,
+        prefix: &str,
+        follow: Follow,
+    ) {
+        let next = next_indent(indent, prefix);
+        let closed = follow == Follow::Continuation;
+
+        let prefix = format!("{}if ", prefix);
+        self.expression(&call.condition, indent, start, &prefix, Follow::Nothing);
+
+        let mut call = call;
+        loop {
+            let then_start = self.token_after(call.condition.span().end);
+            self.expression(&call.iff, next, then_start, "then ", Follow::Nothing);
+
+            let else_start = self.token_after(call.iff.span().end);
+            match &call.els.expression {
+                // Conditions which follow each other are on the same indentation.
+                Expression::IfElse(els)
+                    if !self
+                        .inline(&call.els, closed)
+                        .is_some_and(|inline| fits(next, "else ", &inline)) =>
+                {
+                    let prefix = "else if ";
+                    self.expression(&els.condition, next, else_start, prefix, Follow::Nothing);
+                    call = els;
+                }
+                // The else branch goes on a deeper line, so that it ends before what follows.
+                _ if closed => {
+                    self.line(next, else_start, "else".to_string());
+                    self.expression(
+                        &call.els,
+                        next + INDENT,
+                        call.els.span().start,
+                        "",
+                        Follow::Nothing,
+                    );
+                    break;
+                }
+                _ => {
+                    self.expression(&call.els, next, else_start, "else ", Follow::Nothing);
+                    break;
+                }
+            }
+        }
+    }
+
+    /// The expression written on a single line, if it can be. When it is closed, it can't end
+    /// with an operation that would take in what follows it.
+    fn inline(&self, expr: &ExpressionStatement, closed: bool) -> Option<String> {
+        let span = expr.span();
+        if self.splits(span.start, span.end) {
+            return None;
+        }
+
+        let text = match &expr.expression {
+            Expression::IfElse(_) if closed => return None,
+            Expression::Binary(call) => {
+                let precedence = call.op.precedence();
+
+                format!(
+                    "{} {} {}",
+                    self.inline_operand(&call.lhs, precedence, false, false)?,
+                    binary_op(&call.op),
+                    self.inline_operand(&call.rhs, precedence, true, !closed)?
+                )
+            }
+            Expression::Unary(call) => match is_grouped(&call.subject, false) {
+                true => format!(
+                    "{}({})",
+                    unary_op(&call.op),
+                    self.inline(&call.subject, false)?
+                ),
+                false => format!(
+                    "{}{}",
+                    unary_op(&call.op),
+                    self.inline(&call.subject, closed)?
+                ),
+            },
+            Expression::Let(call) => self.inline_call(&call.name, &call.inputs)?,
+            Expression::Def(call) => {
+                let subject = match call.subject.expression {
+                    Expression::Zelf => String::new(),
+                    _ => self.inline_subject(&call.subject)?,
+                };
+
+                format!(
+                    "{}.{}",
+                    subject,
+                    self.inline_call(&call.name, &call.inputs)?
+                )
+            }
+            Expression::Literal(value) => match value {
+                RawValue::Int(value) => value.to_string(),
+                RawValue::String(value) => string_literal(value),
+                RawValue::Bool(value) => value.to_string(),
+            },
+            Expression::Local(name) => name.clone(),
+            Expression::FriendlyField(field) => {
+                format!("{}.{}", field.local_name, field.field_name)
+            }
+            Expression::Match(_) => return None,
+            Expression::IfElse(call) => format!(
+                "if {} then {} else {}",
+                self.inline(&call.condition, false)?,
+                self.inline(&call.iff, false)?,
+                self.inline(&call.els, false)?
+            ),
+            Expression::Zelf => "Self".to_string(),
+            Expression::Void => "?".to_string(),
+        };
+
+        Some(text)
+    }
+
+    /// An operand of an operation with the precedence written on a single line, within parentheses
+    /// when it would otherwise be taken apart or take in what follows it.
+    fn inline_operand(
+        &self,
+        operand: &ExpressionStatement,
+        precedence: usize,
+        rhs: bool,
+        last: bool,
+    ) -> Option<String> {
+        match needs_group(operand, precedence, rhs, last) {
+            true => Some(format!("({})", self.inline(operand, false)?)),
+            false => self.inline(operand, !last),
+        }
+    }
+
+    /// The subject of a call of a trait written on a single line.
+    fn inline_subject(&self, subject: &ExpressionStatement) -> Option<String> {
+        match is_grouped(subject, true) {
+            true => Some(format!("({})", self.inline(subject, false)?)),
+            false => self.inline(subject, true),
+        }
+    }
+
+    /// The call written on a single line, if its inputs can be.
+    fn inline_call(
+        &self,
+        name: &str,
+        inputs: &HashMap<String, ExpressionStatement>,
+    ) -> Option<String> {
+        if inputs.is_empty() {
+            return Some(name.to_string());
+        }
+
+        let inputs = ordered(inputs)
+            .into_iter()
+            .map(|(name, input)| Some(format!("{}: {}", name, self.inline(input, false)?)))
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(format!("{}({})", name, inputs.join(", ")))
+    }
+
+    /// Writes a declaration, which keeps an empty line before it.
+    fn item(&mut self, indent: usize, start: usize, text: String) {
+        self.comments_before(start, indentation(self.code, start), indent, true);
+        self.push(indent, start, text, true);
+        self.indents.push((indentation(self.code, start), indent));
+    }
+
+    /// Writes a line of an expression.
+    fn line(&mut self, indent: usize, start: usize, text: String) {
+        self.comments_before(start, indentation(self.code, start), indent, false);
+        self.push(indent, start, text, false);
+        self.indents.push((indentation(self.code, start), indent));
+    }
+
+    /// Whether a line on the indentation continues the expression of the last line. A deeper one
+    /// would be taken in by a call or group which that line ends with.
+    fn continues(&self, indent: usize) -> bool {
+        self.lines.last().is_none_or(|line| {
+            indent <= line.len() - line.trim_start().len() || !line.ends_with(')')
+        })
+    }
+
+    fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            lines: self.lines.len(),
+            last: self.lines.last().cloned(),
+            indents: self.indents.len(),
+            written: self.written,
+            separate: self.separate,
+        }
+    }
+
+    /// Takes back what was written after the checkpoint.
+    fn restore(&mut self, checkpoint: Checkpoint) {
+        self.lines.truncate(checkpoint.lines);
+        if let (Some(line), Some(last)) = (self.lines.last_mut(), checkpoint.last) {
+            *line = last;
+        }
+        self.indents.truncate(checkpoint.indents);
+        self.written = checkpoint.written;
+        self.separate = checkpoint.separate;
+    }
+
+    /// Writes the comments before the start of a line which is written next. The indentation of
+    /// that line in the code and in the output decide the indentation of the comments.
+    fn comments_before(&mut self, start: usize, source_indent: usize, indent: usize, item: bool) {
+        while let Some(comment) = self.comments.get(self.written) {
+            if comment.start >= start {
+                break;
+            }
+
+            let text = comment.text.clone();
+            if comment.trailing && !self.lines.is_empty() {
+                let line = self.lines.last_mut().unwrap();
+                line.push(' ');
+                line.push_str(&text);
+            } else {
+                let comment_start = comment.start;
+                let comment_indent = self.comment_indent(comment.indent, source_indent, indent);
+
+                // A comment which ends a deeper block isn't separated from it.
+                if comment_indent > indent {
+                    let separate = std::mem::take(&mut self.separate);
+                    self.push(comment_indent, comment_start, text, item);
+                    self.separate = separate;
+                } else {
+                    self.push(comment_indent, comment_start, text, item);
+                }
+            }
+
+            self.written += 1;
+        }
+    }
+
+    /// The indentation of a comment on its own line. It is that of the line after it, unless it
+    /// is deeper in the code, which makes it end the block of a line before it.
+    fn comment_indent(&self, comment: usize, source_indent: usize, indent: usize) -> usize {
+        if comment <= source_indent {
+            return indent;
+        }
+
+        self.indents
+            .iter()
+            .rev()
+            .find(|(source, _)| *source <= comment)
+            .map_or(indent, |(_, output)| max(*output, indent))
+    }
+
+    fn push(&mut self, indent: usize, start: usize, text: String, item: bool) {
+        // Empty lines are kept between declarations, but not before the first one in a block.
+        let keep_empty = item
+            && self.is_after_empty_line(start)
+            && self
+                .lines
+                .last()
+                .is_some_and(|line| line.len() - line.trim_start().len() >= indent);
+
+        if (self.separate || keep_empty) && self.lines.last().is_some_and(|line| !line.is_empty()) {
+            self.lines.push(String::new());
+        }
+        self.separate = false;
+
+        self.lines
+            .push(format!("{}{}", " ".repeat(indent), indented(&text, indent)));
+    }
+
+    /// Whether the position starts its line, and the line before it is empty.
+    fn is_after_empty_line(&self, position: usize) -> bool {
+        let start = line_start(self.code, position.min(self.code.len()));
+        if start == 0
+            || !self.code[start..position.min(self.code.len())]
+                .trim()
+                .is_empty()
+        {
+            return false;
+        }
+
+        let previous = line_start(self.code, start - 1);
+        self.code[previous..start].trim().is_empty()
+    }
+
+    /// Whether the comments between the positions keep the code between them off a single line.
+    /// A comment after code stays on the line of the token before it, so one of them can end it,
+    /// unless there is another one after the code.
+    fn splits(&self, start: usize, end: usize) -> bool {
+        let after = self.token_after(end);
+        let mut comments = self
+            .comments
+            .iter()
+            .filter(|comment| comment.start >= start && comment.start < after);
+
+        match (comments.next(), comments.next()) {
+            (Some(comment), None) => comment.start < end && !comment.trailing,
+            (Some(comment), Some(_)) => comment.start < end,
+            (None, _) => false,
+        }
+    }
+
+    /// Whether there is a comment between the positions.
+    fn has_comment(&self, start: usize, end: usize) -> bool {
+        self.comments
+            .iter()
+            .any(|comment| comment.start >= start && comment.start < end)
+    }
+
+    /// The start of the first token at or after the position.
+    fn token_after(&self, position: usize) -> usize {
+        self.tokens
+            .iter()
+            .find(|(start, _)| *start >= position)
+            .map_or(position, |(start, _)| *start)
+    }
+
+    /// The start of the last keyword before the position, as the start of the section it begins.
+    fn keyword_before(&self, keyword: Kw, position: usize) -> usize {
+        self.tokens
+            .iter()
+            .rev()
+            .find(|(start, token)| *start < position && *token == Token::Kw(keyword.clone()))
+            .map_or(position, |(start, _)| *start)
+    }
+}
+
+/// The inputs of a call, in the order they are written in.
+fn ordered(inputs: &HashMap<String, ExpressionStatement>) -> Vec<(&String, &ExpressionStatement)> {
+    let mut inputs: Vec<_> = inputs.iter().collect();
+    inputs.sort_by_key(|(_, input)| input.span().start);
+    inputs
+}
+
+fn keyword_text(keyword: &Kw) -> &'static str {
+    match keyword {
+        Kw::Class => "class",
+        Kw::Struct => "struct",
+        Kw::Using => "using",
+        Kw::Traits => "traits",
+        Kw::Defs => "defs",
+        _ => "lets",
+    }
+}
+
+/// The parameters and output of a trait or let, as in "(rhs: Int) -> Bool".
+fn signature(parameters: &[(String, TypeStatement)], output: &TypeStatement) -> String {
+    if parameters.is_empty() {
+        return type_text(output);
+    }
+
+    let parameters: Vec<String> = parameters
+        .iter()
+        .map(|(name, typ)| format!("{}: {}", name, type_text(typ)))
+        .collect();
+
+    format!("({}) -> {}", parameters.join(", "), type_text(output))
+}
+
+fn type_text(typ: &TypeStatement) -> String {
+    match &typ.typ {
+        TypeStatementType::Name(name) => name.clone(),
+        TypeStatementType::AtName(name) => format!("@{}", name),
+        TypeStatementType::And(lhs, rhs) => format!("{} & {}", type_text(lhs), type_text(rhs)),
+        TypeStatementType::Or(lhs, rhs) => format!("{} | {}", type_text(lhs), type_text(rhs)),
+        TypeStatementType::Zelf => "Self".to_string(),
+        TypeStatementType::Void => "?".to_string(),
+    }
+}
+
+fn binary_op(op: &BinaryOp) -> &'static str {
+    match op {
+        BinaryOp::Add => "+",
+        BinaryOp::Sub => "-",
+        BinaryOp::Mul => "*",
+        BinaryOp::Div => "/",
+        BinaryOp::Eq => "=",
+        BinaryOp::Lt => "<",
+        BinaryOp::Gt => ">",
+        BinaryOp::Ne => "!=",
+        BinaryOp::Lte => "<=",
+        BinaryOp::Gte => ">=",
+        BinaryOp::And => "&",
+        BinaryOp::Or => "|",
+    }
+}
+
+fn unary_op(op: &UnaryOp) -> &'static str {
+    match op {
+        UnaryOp::Neg => "-",
+        UnaryOp::Not => "!",
+    }
+}
+
+/// A string in single quotes, with escapes for the characters which can't be written as they are.
+/// A string of more than one line is written in triple quotes, with each line on its own.
+fn string_literal(value: &str) -> String {
+    if value.contains('\n') {
+        return block_string_literal(value);
+    }
+
+    let mut literal = "'".to_string();
+
+    for char in value.chars() {
+        match char {
+            '\'' => literal.push_str("\\'"),
+            '\\' => literal.push_str("\\\\"),
+            '\n' => literal.push_str("\\n"),
+            '\t' => literal.push_str("\\t"),
+            _ if char.is_control() => literal += &format!("\\u{{{:x}}}", char as u32),
+            _ => literal.push(char),
+        }
+    }
+
+    literal + "'"
+}
+
+/// A multi-line string with the quotes on lines of their own. A quote is only escaped after two
+/// others, which would end the string with it.
+fn block_string_literal(value: &str) -> String {
+    let mut literal = "'''\n".to_string();
+    let mut quotes = 0;
+
+    for char in value.chars() {
+        match char {
+            '\'' if quotes == 2 => literal.push_str("\\'"),
+            '\'' => literal.push(char),
+            '\\' => literal.push_str("\\\\"),
+            '\n' => literal.push(char),
+            '\t' => literal.push_str("\\t"),
+            _ if char.is_control() => literal += &format!("\\u{{{:x}}}", char as u32),
+            _ => literal.push(char),
+        }
+
+        quotes = match char {
+            '\'' if quotes < 2 => quotes + 1,
+            _ => 0,
+        };
+    }
+
+    literal + "\n'''"
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ast::abstract_syntax_tree::AbstractSyntaxTree;
+    use crate::ast::expression::{Expression, ExpressionStatement};
+    use crate::formatter::{binary_op, format, type_text, unary_op};
+    use crate::loader::parse_file;
+    use crate::source::SourceMap;
+    use std::fs;
+
+    fn formatted(code: &str) -> String {
+        let sources = SourceMap::single("test.compost", code);
+        let ast = parse_file(&sources, 0).ok().unwrap();
+
+        format(&sources, 0, &ast).unwrap()
+    }
+
+    /// The expressions of the lets and defs of the code, grouped within parentheses.
+    fn tree(code: &str) -> Vec<String> {
+        let sources = SourceMap::single("test.compost", code);
+        let AbstractSyntaxTree { mods, lets, .. } = parse_file(&sources, 0).ok().unwrap();
+
+        let mut exprs = vec![];
+        for module in &mods {
+            exprs.extend(module.defs.iter().map(|def| &def.expr));
+            exprs.extend(module.lets.iter().map(|lett| &lett.expr));
+        }
+        exprs.extend(lets.iter().map(|lett| &lett.expr));
+
+        exprs.into_iter().map(grouped).collect()
+    }
+
+    fn grouped(expr: &ExpressionStatement) -> String {
+        let calls = |inputs: &std::collections::HashMap<String, ExpressionStatement>| {
+            let mut inputs: Vec<_> = inputs
+                .iter()
+                .map(|(name, input)| format!("{}: {}", name, grouped(input)))
+                .collect();
+            inputs.sort();
+            inputs.join(", ")
+        };
+
+        match &expr.expression {
+            Expression::Binary(call) => format!(
+                "({} {} {})",
+                grouped(&call.lhs),
+                binary_op(&call.op),
+                grouped(&call.rhs)
+            ),
+            Expression::Unary(call) => {
+                format!("({}{})", unary_op(&call.op), grouped(&call.subject))
+            }
+            Expression::Let(call) => format!("{}({})", call.name, calls(&call.inputs)),
+            Expression::Def(call) => format!(
+                "{}.{}({})",
+                grouped(&call.subject),
+                call.name,
+                calls(&call.inputs)
+            ),
+            Expression::Match(call) => {
+                let branches: Vec<_> = call
+                    .branches
+                    .iter()
+                    .map(|(typ, branch)| format!("{}: {}", type_text(typ), grouped(branch)))
+                    .collect();
+                format!(
+                    "(match {}: {} [{}])",
+                    call.local_name,
+                    grouped(&call.subject),
+                    branches.join(", ")
+                )
+            }
+            Expression::IfElse(call) => format!(
+                "(if {} then {} else {})",
+                grouped(&call.condition),
+                grouped(&call.iff),
+                grouped(&call.els)
+            ),
+            expression => format!("{:?}", expression),
+        }
+    }
+
+    #[test]
+    fn test_layout() {
+        let code = "mod Point
+  class
+        x: Int
+  traits
+   Moved: (dx: Int) -> Point
+  defs
+      Moved:    Point(x: x + dx)
+lets
+    Main: Int
+            Half(of:   84)
+    Half(of: Int): Int
+        of/2
+";
+
+        assert_eq!(
+            formatted(code),
+            "mod Point
+    class
+        x: Int
+    traits
+        Moved: (dx: Int) -> Point
+    defs
+        Moved: Point(x: x + dx)
+
+lets
+    Main: Int
+        Half(of: 84)
+    Half: (of: Int) -> Int
+        of / 2
+"
+        );
+    }
+
+    #[test]
+    fn test_line_width() {
+        let long = format!("'{}'", "a".repeat(60));
+        let code = format!(
+            "lets\n    Main: String\n        if 1 > 0 then {} else {}\n",
+            long, long
+        );
+
+        assert_eq!(
+            formatted(&code),
+            format!(
+                "lets\n    Main: String\n        if 1 > 0\n        then {}\n        else {}\n",
+                long, long
+            )
+        );
+    }
+
+    #[test]
+    fn test_strings() {
+        let code = "lets\n    Main: String\n        '''\n        It's\n        \\u{1F331}\tgrown\\\\\n        '''\n";
+
+        assert_eq!(
+            formatted(code),
+            "lets\n    Main: String\n        '''\n        It's\n        🌱\\tgrown\\\\\n        '''\n"
+        );
+
+        let code = "lets
+    Main: String
+        Quoted(text: 'It\\'s \\'\\'\\'\\'\\nquoted\\n\\n') + '\\u{1F331}'
+";
+
+        let once = formatted(code);
+        assert_eq!(
+            once,
+            "lets
+    Main: String
+        Quoted(text: '''
+        It's ''\\''
+        quoted
+
+
+        ''') + '🌱'
+"
+        );
+        assert_eq!(tree(&once), tree(code));
+        assert_eq!(formatted(&once), once);
+    }
+
+    #[test]
+    fn test_groups() {
+        let code = "lets
+    Main: Int
+        1 + (2 * 3) - (4 - 5) * (6 + 7) - -(8 - 9)
+";
+
+        assert_eq!(
+            formatted(code),
+            "lets
+    Main: Int
+        1 + 2 * 3 - (4 - 5) * (6 + 7) - -(8 - 9)
+"
+        );
+    }
+
+    #[test]
+    fn test_comments() {
+        let code = "# Constants.
+lets
+    # The answer.
+    Main: Int
+        Half(of: 84) # Twice the answer.
+
+    Half: (of: Int) -> Int
+        of / 2
+# The end.
+";
+
+        assert_eq!(formatted(code), code);
+    }
+
+    #[test]
+    fn test_idempotence() {
+        let code = "lets
+    Main: Int
+      1 +   2
+          * 3 # Multiplied.
+    Other: Bool
+        Main.Eq(rhs:
+            7)
+";
+
+        let once = formatted(code);
+        assert_eq!(formatted(&once), once);
+
+        let code = "lets
+    F: (n: Int) -> Int
+        n
+    Operator: Int
+        1 + # After plus.
+            2
+    Operand: Int
+        1 # After one.
+            + 2
+    Chain: Bool
+        1 = 1 & # After and.
+            (2 = 2 | 3 = 3) & true
+    Input: Int
+        F(n: 1 + # In the input.
+            2)
+    Branches: Int
+        if true then 1 # One.
+            else 2 # Two.
+    Group: Int
+        F(n: 1 # One.
+            + 2
+            # On its own line.
+            + 3)
+";
+
+        let once = formatted(code);
+        assert_eq!(formatted(&once), once);
+        assert_eq!(tree(&once), tree(code));
+        assert!(once.contains("        1 + 2 # After plus.\n"), "{}", once);
+        assert!(
+            once.contains("        F(n: 1 + 2) # In the input.\n"),
+            "{}",
+            once
+        );
+    }
+
+    #[test]
+    fn test_same_tree() {
+        let chain = "F(n: 1111111111) = F(n: 1111111111) & F(n: 2222222222) = F(n: 2222222222)";
+        let code = format!(
+            "lets
+    F: (n: Int) -> Int
+        n
+    Condition: Bool
+        if {chain} & F(n: 3) = F(n: 3) then true else false
+    Branches: Bool
+        if false then {chain} else if {chain} then false else {chain}
+    Input: Int
+        F(n: F(n: 1111111111) + F(n: 2222222222) + F(n: 3333333333) + F(n: 4444444444) + 5)
+    Nested: Bool
+        false | F(n: 1111111111) = F(n: 1111111111) & F(n: 2222222222) = F(n: 2222222222) & true
+    Subject: Int
+        match n: F(n: 1111111111) + F(n: 2222222222) + F(n: 3333333333) + F(n: 4444444444)
+            Int: n
+"
+        );
+
+        let once = formatted(&code);
+        assert_eq!(tree(&once), tree(&code), "{}", once);
+        assert_eq!(formatted(&once), once);
+
+        for entry in fs::read_dir("examples").unwrap() {
+            let path = entry.unwrap().path();
+            if path
+                .extension()
+                .is_some_and(|extension| extension == "compost")
+            {
+                let code = fs::read_to_string(&path).unwrap();
+                assert_eq!(tree(&formatted(&code)), tree(&code), "{:?}", path);
+            }
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use crate::lex::token::{Level, Next, Token};
+use crate::lex::tokenizer::LeveledToken;
+use crate::lex::tokens::{Placement, Tokens};
+use crate::source::Span;
+use std::fmt::{Display, Formatter};
+
+/// The code of a file as the tokens it is made of, each with the trivia around it, so that the
+/// code can be written back exactly as it was. The tokens which the abstract syntax tree is parsed
+/// from are derived from it.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ConcreteSyntaxTree {
+    /// The tokens of the code, ending with the end of the file.
+    pub tokens: Vec<SyntaxToken>,
+}
+
+/// A token with the trivia which belongs to it. Parentheses, colons and commas are tokens here,
+/// although they only set the levels of the tokens around them.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SyntaxToken {
+    pub token: Token,
+    pub text: String,
+    pub span: Span,
+    /// The level of the token, as the parser sees it.
+    pub level: usize,
+    /// The number of parentheses which are open at the token.
+    pub depth: usize,
+    /// The trivia on the lines before the token, and on its own line before it.
+    pub leading: Vec<Trivia>,
+    /// The trivia after the token on its line, up to the line break.
+    pub trailing: Vec<Trivia>,
+}
+
+/// Code which doesn't change the meaning of the tokens, other than their levels.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Trivia {
+    pub kind: TriviaKind,
+    pub text: String,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum TriviaKind {
+    /// One or more spaces.
+    Space,
+    Newline,
+    Comment,
+}
+
+impl Trivia {
+    /// The trivia of a token, if it is one.
+    pub fn of(token: &Token) -> Option<TriviaKind> {
+        match token {
+            Token::Space => Some(TriviaKind::Space),
+            Token::Next(Next::Line) => Some(TriviaKind::Newline),
+            Token::Comment(_) => Some(TriviaKind::Comment),
+            _ => None,
+        }
+    }
+}
+
+/// Writes the code the tree was read from, exactly as it was written.
+impl Display for ConcreteSyntaxTree {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for token in &self.tokens {
+            token
+                .leading
+                .iter()
+                .try_for_each(|trivia| f.write_str(&trivia.text))?;
+            f.write_str(&token.text)?;
+            token
+                .trailing
+                .iter()
+                .try_for_each(|trivia| f.write_str(&trivia.text))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl ConcreteSyntaxTree {
+    /// The comments in the code, with whether each follows a token on its line.
+    pub fn comments(&self) -> impl Iterator<Item = (&Trivia, bool)> {
+        self.tokens
+            .iter()
+            .flat_map(|token| {
+                let leading = token.leading.iter().map(|trivia| (trivia, false));
+                let trailing = token.trailing.iter().map(|trivia| (trivia, true));
+
+                leading.chain(trailing)
+            })
+            .filter(|(trivia, _)| trivia.kind == TriviaKind::Comment)
+    }
+
+    /// The tokens which the parser reads, with their levels. The trivia and the tokens which only
+    /// set levels are left out.
+    pub fn tokens(&self) -> Tokens {
+        let mut leveled_tokens: Vec<LeveledToken> = vec![];
+        let mut placements: Vec<Placement> = vec![];
+        // Whether the closing parentheses found now directly follow the last leveled token.
+        let mut is_after_token = false;
+
+        for token in &self.tokens {
+            let is_same_line = !token
+                .leading
+                .iter()
+                .any(|trivia| trivia.kind == TriviaKind::Newline);
+
+            match &token.token {
+                Token::Down(_) | Token::Next(_) => is_after_token = false,
+                Token::Up(Level::Paren) if is_after_token && is_same_line => {
+                    if let Some(placement) = placements.last_mut() {
+                        placement.closing.push(token.span.end);
+                    }
+                }
+                Token::Up(_) => is_after_token = false,
+                Token::Eof => {
+                    leveled_tokens.push((Token::Eof, 0));
+                    placements.push(Placement {
+                        span: token.span,
+                        depth: 0,
+                        closing: vec![],
+                    });
+                }
+                _ => {
+                    leveled_tokens.push((token.token.clone(), token.level));
+                    placements.push(Placement {
+                        span: token.span,
+                        depth: token.depth,
+                        closing: vec![],
+                    });
+                    is_after_token = true;
+                }
+            }
+        }
+
+        Tokens::new(leveled_tokens, placements)
+    }
+}
@@ -1,3 +1,4 @@
+pub(crate) mod concrete_syntax_tree;
 pub(crate) mod token;
 pub(crate) mod tokenizer;
 pub(crate) mod tokens;
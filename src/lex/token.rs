@@ -1,4 +1,5 @@
 use crate::error::ErrorMessage;
+use std::ops::Range;
 
 /// Represents a single token.
 #[derive(Eq, PartialEq, Debug, Clone)]
@@ -13,6 +14,8 @@ pub enum Token {
     Op(Op),
     Lit(Lit),
     Space,
+    /// A comment, from its '#' to the end of its line.
+    Comment(String),
 }
 
 /// Keywords
@@ -27,6 +30,8 @@ pub enum Kw {
     Zelf,
     Match,
     Using,
+    Import,
+    Private,
     If,
     Then,
     Else,
@@ -43,6 +48,10 @@ pub enum Op {
     Eq,
     Lt,
     Gt,
+    Ne,
+    Lte,
+    Gte,
+    Not,
     And,
     Or,
     Question,
@@ -53,7 +62,7 @@ pub enum Op {
 #[derive(Eq, PartialEq, Debug, Clone)]
 pub enum Lit {
     String(String),
-    Number(usize),
+    Number(i64),
     Boolean(bool),
 }
 
@@ -71,50 +80,71 @@ pub enum Next {
     Line,
 }
 
-type SizedToken = (Option<Token>, usize);
+type SizedToken = (Token, usize);
 
-/// Gets the next sized token at the beginning of the given code.
-pub fn next_token(code: &str) -> Result<SizedToken, ErrorMessage> {
+/// An error in the code of a token, with the part of the code it points at relative to the start
+/// of the token. An empty range points at the first character.
+#[derive(Debug, PartialEq)]
+pub struct LexError {
+    pub message: ErrorMessage,
+    pub range: Range<usize>,
+}
+
+impl From<ErrorMessage> for LexError {
+    fn from(message: ErrorMessage) -> Self {
+        LexError {
+            message,
+            range: 0..0,
+        }
+    }
+}
+
+/// Gets the next sized token at the beginning of the given code, on a line with the given
+/// indentation.
+pub fn next_token(code: &str, indentation: usize) -> Result<SizedToken, LexError> {
     let char = match code.chars().next() {
         Some(c) => c,
-        None => return Ok((Some(Token::Eof), 1)),
+        None => return Ok((Token::Eof, 1)),
     };
 
     let token = match char {
-        ' ' => (Some(Token::Space), 1),
-        '#' => (None, comment_size(code)),
-        '(' => (Some(Token::Down(Level::Paren)), 1),
-        ')' => (Some(Token::Up(Level::Paren)), 1),
-        ':' => (Some(Token::Down(Level::Colon)), 1),
-        '\n' | '\r' => (Some(Token::Next(Next::Line)), 1),
-        ',' => (Some(Token::Next(Next::Comma)), 1),
-        '+' => (Some(Token::Op(Op::Add)), 1),
-        '-' => (Some(Token::Op(Op::Sub)), 1),
-        '*' => (Some(Token::Op(Op::Mul)), 1),
-        '/' => (Some(Token::Op(Op::Div)), 1),
-        '<' => (Some(Token::Op(Op::Lt)), 1),
-        '>' => (Some(Token::Op(Op::Gt)), 1),
-        '=' => (Some(Token::Op(Op::Eq)), 1),
-        '&' => (Some(Token::Op(Op::And)), 1),
-        '|' => (Some(Token::Op(Op::Or)), 1),
-        '?' => (Some(Token::Op(Op::Question)), 1),
-        '.' => (Some(Token::Op(Op::Dot)), 1),
-        '@' => (Some(Token::Op(Op::At)), 1),
+        ' ' => (Token::Space, 1),
+        '#' => next_comment_token(code),
+        '(' => (Token::Down(Level::Paren), 1),
+        ')' => (Token::Up(Level::Paren), 1),
+        ':' => (Token::Down(Level::Colon), 1),
+        '\n' | '\r' => (Token::Next(Next::Line), 1),
+        ',' => (Token::Next(Next::Comma), 1),
+        '+' => (Token::Op(Op::Add), 1),
+        '-' => (Token::Op(Op::Sub), 1),
+        '*' => (Token::Op(Op::Mul), 1),
+        '/' => (Token::Op(Op::Div), 1),
+        '<' if code.starts_with("<=") => (Token::Op(Op::Lte), 2),
+        '>' if code.starts_with(">=") => (Token::Op(Op::Gte), 2),
+        '!' if code.starts_with("!=") => (Token::Op(Op::Ne), 2),
+        '<' => (Token::Op(Op::Lt), 1),
+        '>' => (Token::Op(Op::Gt), 1),
+        '!' => (Token::Op(Op::Not), 1),
+        '=' => (Token::Op(Op::Eq), 1),
+        '&' => (Token::Op(Op::And), 1),
+        '|' => (Token::Op(Op::Or), 1),
+        '?' => (Token::Op(Op::Question), 1),
+        '.' => (Token::Op(Op::Dot), 1),
+        '@' => (Token::Op(Op::At), 1),
         'a'..='z' => next_local_token(code),
         'A'..='Z' | '\\' => next_global_token(code),
-        '0'..='9' => next_number_token(code),
-        '\'' => next_string_token(code),
-        _ => return Err(ErrorMessage::UnexpectedChar(char.to_string())),
+        '0'..='9' => next_number_token(code)?,
+        '\'' => next_string_token(code, indentation)?,
+        _ => return Err(ErrorMessage::UnexpectedChar(char.to_string()).into()),
     };
 
     Ok(token)
 }
 
-fn comment_size(code: &str) -> usize {
-    match code.find('\n') {
-        Some(position) => position,
-        None => code.len(),
-    }
+fn next_comment_token(code: &str) -> SizedToken {
+    let size = code.find('\n').unwrap_or(code.len());
+
+    (Token::Comment(code[..size].to_string()), size)
 }
 
 fn next_local_token(code: &str) -> SizedToken {
@@ -138,15 +168,18 @@ fn next_local_token(code: &str) -> SizedToken {
         "lets" => Token::Kw(Kw::Lets),
         "match" => Token::Kw(Kw::Match),
         "using" => Token::Kw(Kw::Using),
+        "import" => Token::Kw(Kw::Import),
+        "private" => Token::Kw(Kw::Private),
         "if" => Token::Kw(Kw::If),
         "then" => Token::Kw(Kw::Then),
         "else" => Token::Kw(Kw::Else),
+        "not" => Token::Op(Op::Not),
         "false" => Token::Lit(Lit::Boolean(false)),
         "true" => Token::Lit(Lit::Boolean(true)),
         _ => Token::Local(str.to_string()),
     };
 
-    (Some(token), size)
+    (token, size)
 }
 
 fn next_global_token(code: &str) -> SizedToken {
@@ -166,10 +199,10 @@ fn next_global_token(code: &str) -> SizedToken {
         _ => Token::Global(str.to_string()),
     };
 
-    (Some(token), size)
+    (token, size)
 }
 
-fn next_number_token(code: &str) -> SizedToken {
+fn next_number_token(code: &str) -> Result<SizedToken, ErrorMessage> {
     let mut size = 0;
 
     for char in code.chars() {
@@ -179,127 +212,335 @@ fn next_number_token(code: &str) -> SizedToken {
         size += 1;
     }
 
-    let number = code[..size].to_string().parse().unwrap();
+    let digits = &code[..size];
+    let number = digits
+        .parse()
+        .map_err(|_| ErrorMessage::IntegerTooLarge(digits.to_string()))?;
 
-    (Some(Token::Lit(Lit::Number(number))), size)
+    Ok((Token::Lit(Lit::Number(number)), size))
 }
 
-fn next_string_token(code: &str) -> SizedToken {
-    let mut size = 2;
+fn next_string_token(code: &str, indentation: usize) -> Result<SizedToken, LexError> {
+    let (string, size) = if code.starts_with("'''") {
+        let end = closing_quotes(code, "'''")?;
+        // The escapes are checked before the indentation is stripped, so that an invalid one is
+        // pointed at where it is in the code.
+        unescape(&code[3..end], 3)?;
 
-    for char in code.chars().skip(1) {
-        if char == '\'' {
-            break;
+        (
+            unescape(&strip_indentation(&code[3..end], indentation), 3)?,
+            end + 3,
+        )
+    } else {
+        let end = closing_quotes(code, "'")?;
+
+        (unescape(&code[1..end], 1)?, end + 1)
+    };
+
+    Ok((Token::Lit(Lit::String(string)), size))
+}
+
+/// The position of the quotes which close the string literal at the beginning of the code. A
+/// string in single quotes ends at the end of its line.
+fn closing_quotes(code: &str, quotes: &str) -> Result<usize, ErrorMessage> {
+    let mut chars = code.char_indices().skip(quotes.len());
+
+    while let Some((position, char)) = chars.next() {
+        match char {
+            '\\' => {
+                chars.next();
+            }
+            '\n' | '\r' if quotes.len() == 1 => break,
+            _ if code[position..].starts_with(quotes) => return Ok(position),
+            _ => {}
         }
-        size += 1;
     }
 
-    let string = code[1..(size - 1)].to_string();
+    Err(ErrorMessage::UnterminatedString)
+}
+
+/// The lines of a multi-line string without the indentation of the line the string starts on.
+/// Opening and closing quotes on lines of their own don't add a line to the string.
+fn strip_indentation(string: &str, indentation: usize) -> String {
+    let is_blank = |line: &str| line.trim_start_matches(' ').is_empty();
+
+    let mut lines: Vec<&str> = string
+        .split('\n')
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+        .collect();
+
+    if lines.len() > 1 && lines.last().is_some_and(|line| is_blank(line)) {
+        lines.pop();
+    }
+    // The first line directly follows the opening quotes, so it isn't indented.
+    let skip = usize::from(lines.len() > 1 && is_blank(lines[0]));
+
+    lines
+        .iter()
+        .enumerate()
+        .skip(skip)
+        .map(|(number, line)| {
+            let spaces = line.len() - line.trim_start_matches(' ').len();
+
+            if number == 0 {
+                line
+            } else {
+                &line[spaces.min(indentation)..]
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Replaces the escapes in a string literal by the characters they stand for. The string is at the
+/// given offset from the start of its token, which errors are relative to.
+fn unescape(string: &str, offset: usize) -> Result<String, LexError> {
+    let mut unescaped = String::new();
+    let mut chars = string.chars();
+
+    while let Some(char) = chars.next() {
+        if char != '\\' {
+            unescaped.push(char);
+            continue;
+        }
 
-    (Some(Token::Lit(Lit::String(string))), size)
+        let start = string.len() - chars.as_str().len() - 1;
+        let escaped = match chars.next() {
+            Some('\'') => Ok('\''),
+            Some('\\') => Ok('\\'),
+            Some('n') => Ok('\n'),
+            Some('t') => Ok('\t'),
+            Some('u') => unicode_escape(&mut chars),
+            Some(char) => Err(ErrorMessage::InvalidEscape(format!("\\{}", char))),
+            None => Err(ErrorMessage::InvalidEscape("\\".to_string())),
+        };
+
+        match escaped {
+            Ok(escaped) => unescaped.push(escaped),
+            Err(message) => {
+                let end = string.len() - chars.as_str().len();
+
+                return Err(LexError {
+                    message,
+                    range: offset + start..offset + end,
+                });
+            }
+        }
+    }
+
+    Ok(unescaped)
+}
+
+/// The character of a `\u{…}` escape, with up to six hexadecimal digits between the braces. C
+/// strings end at a NUL character, so there's no escape for it.
+fn unicode_escape(chars: &mut std::str::Chars) -> Result<char, ErrorMessage> {
+    let mut escape = "\\u".to_string();
+    let mut digits = String::new();
+
+    for char in chars.by_ref() {
+        escape.push(char);
+
+        match char {
+            '{' if escape.len() == 3 => {}
+            '}' if !digits.is_empty() => break,
+            _ if char.is_ascii_hexdigit() && escape.len() > 3 && digits.len() < 6 => {
+                digits.push(char)
+            }
+            _ => return Err(ErrorMessage::InvalidEscape(escape)),
+        }
+    }
+
+    u32::from_str_radix(&digits, 16)
+        .ok()
+        .filter(|_| escape.ends_with('}'))
+        .and_then(char::from_u32)
+        .filter(|char| *char != '\0')
+        .ok_or(ErrorMessage::InvalidEscape(escape))
 }
 
 #[cfg(test)]
 mod test {
     use crate::error::ErrorMessage;
-    use crate::lex::token::{next_token, Kw, Level, Lit, Next, Op, Token};
+    use crate::lex::token::{Kw, Level, Lit, Next, Op, SizedToken, Token};
+
+    fn next_token(code: &str) -> Result<SizedToken, ErrorMessage> {
+        super::next_token(code, 0).map_err(|error| error.message)
+    }
+
+    fn string(code: &str, indentation: usize) -> Result<String, ErrorMessage> {
+        match super::next_token(code, indentation).map_err(|error| error.message)? {
+            (Token::Lit(Lit::String(string)), _) => Ok(string),
+            (token, _) => panic!("{:?} is not a string", token),
+        }
+    }
 
     #[test]
     fn test_operators() {
-        assert_eq!(next_token("+ 1"), Ok((Some(Token::Op(Op::Add)), 1)));
-        assert_eq!(next_token("- 1"), Ok((Some(Token::Op(Op::Sub)), 1)));
-        assert_eq!(next_token("/ 1"), Ok((Some(Token::Op(Op::Div)), 1)));
-        assert_eq!(next_token("* 1"), Ok((Some(Token::Op(Op::Mul)), 1)));
-        assert_eq!(next_token(".Add"), Ok((Some(Token::Op(Op::Dot)), 1)));
-        assert_eq!(next_token("< 1"), Ok((Some(Token::Op(Op::Lt)), 1)));
-        assert_eq!(next_token("> 1"), Ok((Some(Token::Op(Op::Gt)), 1)));
-        assert_eq!(next_token("= 1"), Ok((Some(Token::Op(Op::Eq)), 1)));
-        assert_eq!(next_token("& 1"), Ok((Some(Token::Op(Op::And)), 1)));
-        assert_eq!(next_token("| 1"), Ok((Some(Token::Op(Op::Or)), 1)));
-        assert_eq!(next_token("? 1"), Ok((Some(Token::Op(Op::Question)), 1)));
+        assert_eq!(next_token("+ 1"), Ok((Token::Op(Op::Add), 1)));
+        assert_eq!(next_token("- 1"), Ok((Token::Op(Op::Sub), 1)));
+        assert_eq!(next_token("/ 1"), Ok((Token::Op(Op::Div), 1)));
+        assert_eq!(next_token("* 1"), Ok((Token::Op(Op::Mul), 1)));
+        assert_eq!(next_token(".Add"), Ok((Token::Op(Op::Dot), 1)));
+        assert_eq!(next_token("< 1"), Ok((Token::Op(Op::Lt), 1)));
+        assert_eq!(next_token("> 1"), Ok((Token::Op(Op::Gt), 1)));
+        assert_eq!(next_token("= 1"), Ok((Token::Op(Op::Eq), 1)));
+        assert_eq!(next_token("& 1"), Ok((Token::Op(Op::And), 1)));
+        assert_eq!(next_token("| 1"), Ok((Token::Op(Op::Or), 1)));
+        assert_eq!(next_token("? 1"), Ok((Token::Op(Op::Question), 1)));
+        assert_eq!(next_token("!= 1"), Ok((Token::Op(Op::Ne), 2)));
+        assert_eq!(next_token("<= 1"), Ok((Token::Op(Op::Lte), 2)));
+        assert_eq!(next_token(">= 1"), Ok((Token::Op(Op::Gte), 2)));
+        assert_eq!(next_token("!a"), Ok((Token::Op(Op::Not), 1)));
+        assert_eq!(next_token("not a"), Ok((Token::Op(Op::Not), 3)));
+        assert_eq!(next_token("nota"), Ok((Token::Local("nota".into()), 4)));
     }
 
     #[test]
     fn test_comments() {
-        assert_eq!(next_token("# A comment\n1 + 1"), Ok((None, 11)));
+        assert_eq!(
+            next_token("# A comment\n1 + 1"),
+            Ok((Token::Comment("# A comment".into()), 11))
+        );
         assert_eq!(
             next_token("# A comment\n# Next comment\n1 + 1"),
-            Ok((None, 11))
+            Ok((Token::Comment("# A comment".into()), 11))
+        );
+        assert_eq!(
+            next_token("# The end"),
+            Ok((Token::Comment("# The end".into()), 9))
         );
     }
 
     #[test]
     fn test_whitespace() {
-        assert_eq!(next_token(" 1 + 1"), Ok((Some(Token::Space), 1)));
-        assert_eq!(next_token(""), Ok((Some(Token::Eof), 1)));
+        assert_eq!(next_token(" 1 + 1"), Ok((Token::Space, 1)));
+        assert_eq!(next_token(""), Ok((Token::Eof, 1)));
     }
 
     #[test]
     fn test_levels() {
+        assert_eq!(next_token("\n1 + 1"), Ok((Token::Next(Next::Line), 1)));
+        assert_eq!(next_token(",1 + 1"), Ok((Token::Next(Next::Comma), 1)));
+        assert_eq!(next_token(": 1 + 1"), Ok((Token::Down(Level::Colon), 1)));
+        assert_eq!(next_token("(1 + 1)"), Ok((Token::Down(Level::Paren), 1)));
+        assert_eq!(next_token(")1 + 1"), Ok((Token::Up(Level::Paren), 1)));
+    }
+
+    #[test]
+    fn test_literals() {
+        assert_eq!(next_token("123 "), Ok((Token::Lit(Lit::Number(123)), 3)));
+        assert_eq!(
+            next_token("9223372036854775807 "),
+            Ok((Token::Lit(Lit::Number(i64::MAX)), 19))
+        );
         assert_eq!(
-            next_token("\n1 + 1"),
-            Ok((Some(Token::Next(Next::Line)), 1))
+            next_token("9223372036854775808 "),
+            Err(ErrorMessage::IntegerTooLarge("9223372036854775808".into()))
         );
         assert_eq!(
-            next_token(",1 + 1"),
-            Ok((Some(Token::Next(Next::Comma)), 1))
+            next_token("'Bla' "),
+            Ok((Token::Lit(Lit::String("Bla".into())), 5))
+        );
+    }
+
+    #[test]
+    fn test_escapes() {
+        assert_eq!(string(r"'It\'s'", 0), Ok("It's".into()));
+        assert_eq!(string(r"'a\\b'", 0), Ok("a\\b".into()));
+        assert_eq!(string(r"'a\nb\tc'", 0), Ok("a\nb\tc".into()));
+        assert_eq!(string(r"'\u{1F331}\u{e9}'", 0), Ok("🌱é".into()));
+        assert_eq!(
+            next_token(r"'\'' "),
+            Ok((Token::Lit(Lit::String("'".into())), 4))
+        );
+
+        assert_eq!(
+            string(r"'\q'", 0),
+            Err(ErrorMessage::InvalidEscape(r"\q".into()))
         );
         assert_eq!(
-            next_token(": 1 + 1"),
-            Ok((Some(Token::Down(Level::Colon)), 1))
+            string(r"'\u{110000}'", 0),
+            Err(ErrorMessage::InvalidEscape(r"\u{110000}".into()))
         );
         assert_eq!(
-            next_token("(1 + 1)"),
-            Ok((Some(Token::Down(Level::Paren)), 1))
+            string(r"'\u{1234567}'", 0),
+            Err(ErrorMessage::InvalidEscape(r"\u{1234567".into()))
+        );
+        assert_eq!(
+            string(r"'\u41'", 0),
+            Err(ErrorMessage::InvalidEscape(r"\u4".into()))
+        );
+        assert_eq!(
+            string(r"'a\u{0}b'", 0),
+            Err(ErrorMessage::InvalidEscape(r"\u{0}".into()))
         );
-        assert_eq!(next_token(")1 + 1"), Ok((Some(Token::Up(Level::Paren)), 1)));
     }
 
     #[test]
-    fn test_literals() {
+    fn test_escape_errors_point_at_the_escape() {
+        let range = |code, indentation| super::next_token(code, indentation).unwrap_err().range;
+
+        assert_eq!(range(r"'It\'s \q'", 0), 7..9);
+        assert_eq!(range(r"'\\\u{0}'", 0), 3..8);
+        assert_eq!(range("'''\n    a\n    b\\q\n    '''", 4), 15..17);
+    }
+
+    #[test]
+    fn test_multi_line_strings() {
+        let code = "'''\n        Hello\n          world\n        '''\n";
+        assert_eq!(string(code, 8), Ok("Hello\n  world".into()));
+        assert_eq!(next_token(code).unwrap().1, code.len() - 1);
+
         assert_eq!(
-            next_token("123 "),
-            Ok((Some(Token::Lit(Lit::Number(123))), 3))
+            string("'''It's\n    'quoted\\''''", 4),
+            Ok("It's\n'quoted'".into())
         );
         assert_eq!(
-            next_token("'Bla' "),
-            Ok((Some(Token::Lit(Lit::String("Bla".into()))), 5))
+            string("'''\n    a\n\n    b\n    '''", 4),
+            Ok("a\n\nb".into())
         );
+        assert_eq!(string("''''''", 4), Ok("".into()));
     }
 
     #[test]
-    fn test_keywords() {
-        assert_eq!(next_token("mod "), Ok((Some(Token::Kw(Kw::Mod)), 3)));
-        assert_eq!(next_token("class "), Ok((Some(Token::Kw(Kw::Class)), 5)));
-        assert_eq!(next_token("struct "), Ok((Some(Token::Kw(Kw::Struct)), 6)));
-        assert_eq!(next_token("traits "), Ok((Some(Token::Kw(Kw::Traits)), 6)));
-        assert_eq!(next_token("defs "), Ok((Some(Token::Kw(Kw::Defs)), 4)));
-        assert_eq!(next_token("lets "), Ok((Some(Token::Kw(Kw::Lets)), 4)));
-        assert_eq!(next_token("Self "), Ok((Some(Token::Kw(Kw::Zelf)), 4)));
-        assert_eq!(next_token("match "), Ok((Some(Token::Kw(Kw::Match)), 5)));
-        assert_eq!(next_token("using "), Ok((Some(Token::Kw(Kw::Using)), 5)));
-        assert_eq!(next_token("if "), Ok((Some(Token::Kw(Kw::If)), 2)));
-        assert_eq!(next_token("then "), Ok((Some(Token::Kw(Kw::Then)), 4)));
-        assert_eq!(next_token("else "), Ok((Some(Token::Kw(Kw::Else)), 4)));
+    fn test_unterminated_strings() {
+        assert_eq!(next_token("'Bla"), Err(ErrorMessage::UnterminatedString));
+        assert_eq!(next_token("'Bla\n'"), Err(ErrorMessage::UnterminatedString));
+        assert_eq!(next_token(r"'Bla\'"), Err(ErrorMessage::UnterminatedString));
         assert_eq!(
-            next_token("true "),
-            Ok((Some(Token::Lit(Lit::Boolean(true))), 4))
+            next_token("'''Bla\n''"),
+            Err(ErrorMessage::UnterminatedString)
         );
+    }
+
+    #[test]
+    fn test_keywords() {
+        assert_eq!(next_token("mod "), Ok((Token::Kw(Kw::Mod), 3)));
+        assert_eq!(next_token("class "), Ok((Token::Kw(Kw::Class), 5)));
+        assert_eq!(next_token("struct "), Ok((Token::Kw(Kw::Struct), 6)));
+        assert_eq!(next_token("traits "), Ok((Token::Kw(Kw::Traits), 6)));
+        assert_eq!(next_token("defs "), Ok((Token::Kw(Kw::Defs), 4)));
+        assert_eq!(next_token("lets "), Ok((Token::Kw(Kw::Lets), 4)));
+        assert_eq!(next_token("Self "), Ok((Token::Kw(Kw::Zelf), 4)));
+        assert_eq!(next_token("match "), Ok((Token::Kw(Kw::Match), 5)));
+        assert_eq!(next_token("using "), Ok((Token::Kw(Kw::Using), 5)));
+        assert_eq!(next_token("private "), Ok((Token::Kw(Kw::Private), 7)));
+        assert_eq!(next_token("if "), Ok((Token::Kw(Kw::If), 2)));
+        assert_eq!(next_token("then "), Ok((Token::Kw(Kw::Then), 4)));
+        assert_eq!(next_token("else "), Ok((Token::Kw(Kw::Else), 4)));
+        assert_eq!(next_token("true "), Ok((Token::Lit(Lit::Boolean(true)), 4)));
         assert_eq!(
             next_token("false "),
-            Ok((Some(Token::Lit(Lit::Boolean(false))), 5))
+            Ok((Token::Lit(Lit::Boolean(false)), 5))
         );
     }
 
     #[test]
     fn test_local() {
-        assert_eq!(
-            next_token("local "),
-            Ok((Some(Token::Local("local".into())), 5))
-        );
+        assert_eq!(next_token("local "), Ok((Token::Local("local".into()), 5)));
         assert_eq!(
             next_token("aLocalField "),
-            Ok((Some(Token::Local("aLocalField".into())), 11))
+            Ok((Token::Local("aLocalField".into()), 11))
         );
     }
 
@@ -307,19 +548,19 @@ mod test {
     fn test_global() {
         assert_eq!(
             next_token("Global "),
-            Ok((Some(Token::Global("Global".into())), 6))
+            Ok((Token::Global("Global".into()), 6))
         );
         assert_eq!(
             next_token("AGlobalField "),
-            Ok((Some(Token::Global("AGlobalField".into())), 12))
+            Ok((Token::Global("AGlobalField".into()), 12))
         );
         assert_eq!(
             next_token("\\Global "),
-            Ok((Some(Token::Global("\\Global".into())), 7))
+            Ok((Token::Global("\\Global".into()), 7))
         );
         assert_eq!(
             next_token("Module\\Global.Trait"),
-            Ok((Some(Token::Global("Module\\Global".into())), 13))
+            Ok((Token::Global("Module\\Global".into()), 13))
         );
     }
 
@@ -1,85 +1,150 @@
-use crate::error::{CResult, CompilationError, ErrorContext};
-use crate::lex::token::{next_token, Level, Next, Token};
+use crate::error::{CResult, CompilationError, ErrorMessage};
+use crate::lex::concrete_syntax_tree::{ConcreteSyntaxTree, SyntaxToken, Trivia, TriviaKind};
+use crate::lex::token::{next_token, Level, LexError, Next, Token};
 use crate::lex::tokens::Tokens;
+use crate::source::{FileId, SourceMap};
+use std::cmp::max;
 
 pub type LeveledToken = (Token, usize);
 
-// Turns a string of raw code into a vector of tokens with levels.
-pub fn tokenize(code: &str) -> CResult<Tokens> {
-    let mut position: usize = 0;
+// Turns the code of a source file into a vector of tokens with levels.
+pub fn tokenize(sources: &SourceMap, file: FileId) -> CResult<Tokens> {
+    concrete_syntax_tree(sources, file).map(|tree| tree.tokens())
+}
+
+/// Reads the code of a source file into its tokens, keeping the trivia around them.
+pub fn concrete_syntax_tree(sources: &SourceMap, file: FileId) -> CResult<ConcreteSyntaxTree> {
+    let range = sources.range(file);
+    let code = &sources.code()[..range.end];
+    let mut position = range.start;
     let mut level_stack = LevelStack::new();
-    let mut leveled_tokens: Vec<LeveledToken> = vec![];
+    let mut tokens: Vec<SyntaxToken> = vec![];
+    // The trivia which goes before the next token.
+    let mut leading: Vec<Trivia> = vec![];
     let mut is_beginning_of_line = true;
 
     while position <= code.len() {
-        let sized_token = match next_token(&code[position..]) {
+        let (token, size) = match next_token(&code[position..], level_stack.indentation) {
             Ok(sized_token) => sized_token,
-            Err(message) => {
+            Err(LexError { message, range }) => {
+                let start = position + range.start;
+                let size = code[start..].chars().next().map_or(1, char::len_utf8);
+
                 return Err(CompilationError {
                     message,
-                    context: Some(ErrorContext::Character(position)),
-                })
+                    context: Some(sources.span(start..max(position + range.end, start + size))),
+                    annotations: vec![],
+                });
             }
         };
 
         assert!(
-            sized_token.1 > 0,
+            size > 0,
             "Size must be larger than 0 to prevent an infinite loop"
         );
-        position += sized_token.1;
+        let start = position;
+        position += size;
+
+        let text = code[start..position.min(code.len())].to_string();
+        let span = sources.span(start..position);
 
-        if let Some(token) = sized_token.0 {
+        if !matches!(token, Token::Comment(_)) {
             is_beginning_of_line = is_beginning_of_line && token == Token::Space;
+        }
 
+        if let Some(kind) = Trivia::of(&token) {
             match token {
-                Token::Space => {
-                    if is_beginning_of_line {
-                        level_stack.indent()
-                    }
-                }
-                Token::Down(level) => level_stack.push(level),
-                Token::Up(level) => level_stack.pop(&level),
+                Token::Space if is_beginning_of_line => level_stack.indent(),
                 Token::Next(next) => {
                     level_stack.next(&next);
+                    is_beginning_of_line = true;
+                }
+                _ => {}
+            }
 
-                    if next == Next::Line {
-                        is_beginning_of_line = true;
-                    }
+            // Trivia on the line of a token is its trailing trivia.
+            let is_trailing = kind != TriviaKind::Newline && leading.is_empty();
+            let trivia = match tokens.last_mut() {
+                Some(last) if is_trailing && last.token != Token::Eof => &mut last.trailing,
+                _ => &mut leading,
+            };
+
+            // Spaces which follow each other are one piece of trivia.
+            match trivia.last_mut() {
+                Some(last) if kind == TriviaKind::Space && last.kind == TriviaKind::Space => {
+                    last.text += &text;
+                    last.span.end = span.end;
                 }
-                Token::Eof => leveled_tokens.push((Token::Eof, 0)),
-                _ => leveled_tokens.push((token, level_stack.level())),
+                _ => trivia.push(Trivia { kind, text, span }),
             }
+
+            continue;
+        }
+
+        let (level, depth) = match token {
+            Token::Eof => (0, 0),
+            _ => (level_stack.level(), level_stack.parens()),
+        };
+
+        match &token {
+            Token::Down(level) => level_stack.push(level.clone()),
+            Token::Up(level) => level_stack.pop(level),
+            Token::Next(next) => level_stack.next(next),
+            _ => {}
         }
+
+        tokens.push(SyntaxToken {
+            token,
+            text,
+            span,
+            level,
+            depth,
+            leading: std::mem::take(&mut leading),
+            trailing: vec![],
+        });
     }
 
-    Ok(leveled_tokens.into())
+    Ok(ConcreteSyntaxTree { tokens })
 }
 
-/// Purely for showing line numbers in error messages
-pub fn get_position_of_token(code: &str, token_number: usize) -> usize {
+/// Whether more lines are needed to finish the code, because a parenthesis is still open or
+/// because the last line is indented, so that it may be followed by more lines on its level.
+pub fn is_incomplete(code: &str) -> bool {
     let mut position: usize = 0;
-    let mut leveled_tokens = 0;
+    let mut level_stack = LevelStack::new();
     let mut is_beginning_of_line = true;
-    let mut sized_token = (None, 0);
 
-    while leveled_tokens <= token_number {
-        sized_token = next_token(&code[position..]).unwrap();
+    while position < code.len() {
+        let (token, size) = match next_token(&code[position..], level_stack.indentation) {
+            Ok(sized_token) => sized_token,
+            // A multi-line string may be closed on a later line.
+            Err(LexError {
+                message: ErrorMessage::UnterminatedString,
+                ..
+            }) => return code[position..].starts_with("'''"),
+            // Let the tokenizer report the error.
+            Err(_) => return false,
+        };
+        position += size;
 
-        position += sized_token.1;
+        is_beginning_of_line = is_beginning_of_line && token == Token::Space;
 
-        if let Some(token) = sized_token.0 {
-            is_beginning_of_line = is_beginning_of_line && token == Token::Space;
+        match token {
+            Token::Space if is_beginning_of_line => level_stack.indent(),
+            Token::Down(level) => level_stack.push(level),
+            Token::Up(level) => level_stack.pop(&level),
+            Token::Next(next) => {
+                level_stack.next(&next);
 
-            match token {
-                Token::Next(Next::Line) => is_beginning_of_line = true,
-                Token::Space | Token::Down(_) | Token::Up(_) | Token::Next(_) => {}
-                _ => leveled_tokens += 1,
+                if next == Next::Line {
+                    is_beginning_of_line = true;
+                }
             }
+            _ => {}
         }
     }
 
-    // Return the beginning position of the token
-    position - sized_token.1
+    level_stack.levels.contains(&Level::Paren) || level_stack.indentation > 0
 }
 
 /// Utility to keep track of the depth level of our code.
@@ -138,6 +203,14 @@ impl LevelStack {
         }
     }
 
+    /// The number of open parentheses.
+    fn parens(&self) -> usize {
+        self.levels
+            .iter()
+            .filter(|level| **level == Level::Paren)
+            .count()
+    }
+
     /// Gets the current level.
     fn level(&self) -> usize {
         self.levels.len() + self.indentation
@@ -146,8 +219,11 @@ impl LevelStack {
 
 #[cfg(test)]
 mod test {
-    use crate::lex::token::{Kw, Token};
-    use crate::lex::tokenizer::tokenize;
+    use crate::lex::concrete_syntax_tree::{Trivia, TriviaKind};
+    use crate::lex::token::{Kw, Level, Lit, Token};
+    use crate::lex::tokenizer::{concrete_syntax_tree, is_incomplete, tokenize};
+    use crate::run::STD_CODE;
+    use crate::source::{SourceMap, Span};
 
     #[test]
     fn test_levels() {
@@ -159,7 +235,7 @@ mod test {
                     Value: Int
         "#;
 
-        let tokens = tokenize(code).unwrap();
+        let tokens = tokenize(&SourceMap::single("test.compost", code), 0).unwrap();
 
         let expected = vec![
             (Token::Kw(Kw::Mod), 12),
@@ -175,4 +251,145 @@ mod test {
 
         assert_eq!(tokens.remaining(), &expected)
     }
+
+    #[test]
+    fn test_spans() {
+        let mut sources = SourceMap::new();
+        sources.add("std.compost", "lets\n    One: 1\n");
+        sources.add("user.compost", "lets\n    Two: 2\n");
+
+        let tokens = tokenize(&sources, 1).unwrap();
+
+        assert_eq!(
+            tokens.span(0),
+            Span {
+                file: 1,
+                start: 0,
+                end: 4
+            }
+        );
+        assert_eq!(
+            tokens.span(1),
+            Span {
+                file: 1,
+                start: 9,
+                end: 12
+            }
+        );
+        assert_eq!(
+            tokens.span(3),
+            Span {
+                file: 1,
+                start: 16,
+                end: 16
+            }
+        );
+    }
+
+    #[test]
+    fn test_is_incomplete() {
+        assert!(!is_incomplete("1 + 2"));
+        assert!(is_incomplete("Int(value: 1"));
+        assert!(!is_incomplete("Int(value: 1)"));
+        assert!(is_incomplete("lets\n    Two: Int"));
+        assert!(!is_incomplete("lets\n    Two: Int\n"));
+        assert!(is_incomplete("Point(\n    x: 1\n    y: 2"));
+        assert!(is_incomplete("'''\nHello"));
+        assert!(!is_incomplete("'''\nHello\n'''"));
+        assert!(!is_incomplete("'Hello"));
+    }
+
+    #[test]
+    fn test_span_from() {
+        let sources = SourceMap::single("test.compost", "Point(x: 1) + 2");
+        let mut tokens = tokenize(&sources, 0).unwrap();
+        let span = |start, end| Span {
+            file: 0,
+            start,
+            end,
+        };
+
+        assert_eq!(tokens.span_from(0), span(0, 5), "Nothing stepped over");
+
+        tokens.step();
+        tokens.step();
+        tokens.step();
+        assert_eq!(tokens.span_from(0), span(0, 11), "Closing parenthesis");
+        assert_eq!(tokens.span_from(1), span(6, 10), "Within parentheses");
+
+        tokens.step();
+        tokens.step();
+        assert_eq!(tokens.span_from(3), span(12, 15), "Multiple tokens");
+        assert_eq!(tokens.span_from(4), span(14, 15), "Single token");
+
+        tokens.step();
+        assert_eq!(tokens.span_from(4), span(14, 15), "End of the code");
+    }
+
+    #[test]
+    fn test_lossless() {
+        let codes = [
+            STD_CODE,
+            include_str!("../../examples/binary_tree.compost"),
+            "lets\r\n    Main: 'a # b' # Not a string.\r\n",
+            "  (1 +  2 ) ,\n# The end",
+        ];
+
+        for code in codes {
+            let tree = concrete_syntax_tree(&SourceMap::single("test.compost", code), 0).unwrap();
+
+            assert_eq!(tree.to_string(), code);
+        }
+    }
+
+    #[test]
+    fn test_trivia() {
+        let code =
+            "# Constants.\nlets\n    One: 1 # The first.\n\n    # The second.\n    Two: (2)\n";
+        let tree = concrete_syntax_tree(&SourceMap::single("test.compost", code), 0).unwrap();
+
+        let texts = |trivia: &[Trivia]| -> Vec<String> {
+            trivia.iter().map(|trivia| trivia.text.clone()).collect()
+        };
+
+        let lets = &tree.tokens[0];
+        assert_eq!(lets.token, Token::Kw(Kw::Lets));
+        assert_eq!(texts(&lets.leading), vec!["# Constants.", "\n"]);
+        assert!(lets.trailing.is_empty());
+
+        let one = &tree.tokens[3];
+        assert_eq!(one.text, "1");
+        assert_eq!(texts(&one.trailing), vec![" ", "# The first."]);
+        assert_eq!(one.trailing[1].kind, TriviaKind::Comment);
+
+        let two = &tree.tokens[4];
+        assert_eq!(two.text, "Two");
+        assert_eq!(
+            texts(&two.leading),
+            vec!["\n", "\n", "    ", "# The second.", "\n", "    "]
+        );
+        assert_eq!(two.level, 4);
+
+        let paren = &tree.tokens[6];
+        assert_eq!(paren.token, Token::Down(Level::Paren));
+        assert_eq!(tree.tokens[7].depth, 1);
+
+        let comments: Vec<_> = tree
+            .comments()
+            .map(|(comment, trailing)| (comment.text.as_str(), trailing))
+            .collect();
+        assert_eq!(
+            comments,
+            vec![
+                ("# Constants.", false),
+                ("# The first.", true),
+                ("# The second.", false)
+            ]
+        );
+
+        // The parser reads the same tokens without the trivia and punctuation.
+        let tokens = tokenize(&SourceMap::single("test.compost", code), 0).unwrap();
+        assert_eq!(tokens.remaining().len(), 6);
+        assert_eq!(tokens.remaining()[4], (Token::Lit(Lit::Number(2)), 6));
+    }
 }
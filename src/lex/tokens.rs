@@ -1,15 +1,40 @@
-use crate::error::{CResult, CompilationError, ErrorContext, ErrorMessage};
+use crate::error::{CResult, CompilationError, ErrorMessage};
 use crate::lex::token::Token;
 use crate::lex::tokenizer::LeveledToken;
+use crate::source::Span;
+use std::cmp::{max, min};
+
+/// Where a token is in the source files.
+#[derive(Debug, Clone)]
+pub struct Placement {
+    pub span: Span,
+    /// The number of parentheses which are open at the token.
+    pub depth: usize,
+    /// The ends of the closing parentheses which directly follow the token.
+    pub closing: Vec<usize>,
+}
 
 /// Provides utility functions that help traversing the tokens.
 pub struct Tokens {
     tokens: Vec<LeveledToken>,
+    /// Where each of the tokens is in the source files.
+    placements: Vec<Placement>,
     position: usize,
     expecting: Vec<&'static str>,
+    errors: Vec<CompilationError>,
 }
 
 impl Tokens {
+    pub fn new(tokens: Vec<LeveledToken>, placements: Vec<Placement>) -> Self {
+        Tokens {
+            tokens,
+            placements,
+            position: 0,
+            expecting: vec![],
+            errors: vec![],
+        }
+    }
+
     /// Advance to the next token.
     pub fn step(&mut self) {
         self.position += 1;
@@ -32,24 +57,62 @@ impl Tokens {
 
     /// The remaining tokens.
     pub fn remaining(&self) -> &[LeveledToken] {
-        &self.tokens[self.position..]
+        &self.tokens[min(self.position, self.tokens.len())..]
+    }
+
+    /// Where the token at the given position is in the source files.
+    pub fn span(&self, position: usize) -> Span {
+        self.placement(position).span
+    }
+
+    /// The span from the start of the token at the given position to the end of the last token
+    /// stepped over, or of the token itself if there is none. Parentheses aren't tokens, so those
+    /// which close the ones opened within the span are taken in.
+    pub fn span_from(&self, start: usize) -> Span {
+        let first = self.placement(start);
+        let last = self.placement(max(start, self.position.saturating_sub(1)));
+
+        if first.span.file != last.span.file {
+            return first.span;
+        }
+
+        let unclosed = min(last.depth.saturating_sub(first.depth), last.closing.len());
+        let end = match unclosed {
+            0 => last.span.end,
+            _ => last.closing[unclosed - 1],
+        };
+
+        Span {
+            end: max(end, first.span.start),
+            ..first.span
+        }
+    }
+
+    /// The placement of the token at the given position, or of the last token if it's beyond.
+    fn placement(&self, position: usize) -> &Placement {
+        &self.placements[min(position, self.placements.len() - 1)]
+    }
+
+    /// The token at the given position, or the last one, which ends the file, if it's beyond.
+    fn leveled_token(&self, position: usize) -> &LeveledToken {
+        &self.tokens[min(position, self.tokens.len() - 1)]
     }
 
     /// The current token.
     pub fn token(&self) -> &Token {
-        &self.tokens[self.position].0
+        &self.leveled_token(self.position).0
     }
 
     /// Returns the current token and step to the next.
     pub fn token_and_step(&mut self) -> &Token {
         self.position += 1;
         self.expecting.clear();
-        &self.tokens[self.position - 1].0
+        &self.leveled_token(self.position - 1).0
     }
 
     /// The current level.
     pub fn level(&self) -> usize {
-        self.tokens[self.position].1
+        self.leveled_token(self.position).1
     }
 
     /// The current position in the array of tokens.
@@ -67,10 +130,30 @@ impl Tokens {
     pub fn error<T>(&self, message: ErrorMessage) -> CResult<T> {
         Err(CompilationError {
             message,
-            context: Some(ErrorContext::Token(self.position)),
+            context: Some(self.span(self.position)),
+            annotations: vec![],
         })
     }
 
+    /// Records an error and skips the rest of the statement which started at the given position
+    /// and level, so that parsing can continue with the next statement.
+    pub fn recover(&mut self, error: CompilationError, start: usize, level: usize) {
+        self.errors.push(error);
+
+        if self.position == start {
+            self.step();
+        }
+
+        while self.deeper_than(level) {
+            self.step();
+        }
+    }
+
+    /// Takes the errors that were recovered from.
+    pub fn take_errors(&mut self) -> Vec<CompilationError> {
+        std::mem::take(&mut self.errors)
+    }
+
     /// Create an unexpected token error at the current position
     pub fn unexpected_token_error<T>(&self) -> CResult<T> {
         let expectation = if self.expecting.is_empty() {
@@ -81,17 +164,8 @@ impl Tokens {
 
         Err(CompilationError {
             message: ErrorMessage::UnexpectedToken(self.token().clone(), expectation),
-            context: Some(ErrorContext::Token(self.position)),
+            context: Some(self.span(self.position)),
+            annotations: vec![],
         })
     }
 }
-
-impl From<Vec<LeveledToken>> for Tokens {
-    fn from(tokens: Vec<LeveledToken>) -> Self {
-        Tokens {
-            tokens,
-            position: 0,
-            expecting: vec![],
-        }
-    }
-}
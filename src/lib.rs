@@ -1,6 +1,15 @@
 mod ast;
+mod codegen;
+pub(crate) mod diagnostic;
 pub(crate) mod error;
+pub(crate) mod explanations;
+pub(crate) mod formatter;
 mod lex;
+pub(crate) mod loader;
+pub mod lsp;
+pub(crate) mod package;
+pub mod repl;
 pub mod run;
 mod runtime;
 mod sem;
+pub(crate) mod source;
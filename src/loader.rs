@@ -0,0 +1,150 @@
+use crate::ast::abstract_syntax_tree::AbstractSyntaxTree;
+use crate::ast::import_statement::ImportStatement;
+use crate::ast::parser::Parse;
+use crate::ast::Statement;
+use crate::error::{CResult, CompilationError, ErrorMessage};
+use crate::lex::tokenizer::tokenize;
+use crate::run::RunError;
+use crate::source::{FileId, SourceMap};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Parses the files of the source map, together with the files they import, into a single syntax
+/// tree. Imported files are added to the source map, after the ones which were already in it.
+///
+/// An import is resolved relative to the directory of the importing file, and the directories on
+/// the way are added to the namespace of the importing file to form the namespace of what the
+/// imported file declares. So `import Geometry\Point` loads `Geometry/Point.compost`, in which
+/// `mod Point` is the module `Geometry\Point`.
+pub(crate) fn load(sources: &mut SourceMap) -> Result<AbstractSyntaxTree, RunError> {
+    let files = sources.file_count();
+
+    let mut loader = Loader {
+        imported: (0..files).map(|file| path_of(sources, file)).collect(),
+        sources,
+        loading: vec![],
+        ast: AbstractSyntaxTree::new(),
+        errors: vec![],
+    };
+
+    for file in 0..files {
+        loader.load_file(file)?;
+    }
+
+    if loader.errors.is_empty() {
+        Ok(loader.ast)
+    } else {
+        Err(RunError::Parsing(loader.errors))
+    }
+}
+
+/// Parses a single file, without loading its imports.
+pub(crate) fn parse_file(
+    sources: &SourceMap,
+    file: FileId,
+) -> Result<AbstractSyntaxTree, RunError> {
+    let mut tokens = tokenize(sources, file).map_err(RunError::Lexing)?;
+
+    let ast = AbstractSyntaxTree::parse(&mut tokens);
+    let mut errors = tokens.take_errors();
+
+    match ast {
+        Ok(ast) if errors.is_empty() => Ok(ast),
+        Ok(_) => Err(RunError::Parsing(errors)),
+        Err(error) => {
+            errors.push(error);
+            Err(RunError::Parsing(errors))
+        }
+    }
+}
+
+struct Loader<'a> {
+    sources: &'a mut SourceMap,
+    /// The paths of the files which have been loaded or are being loaded, to load each file once.
+    imported: HashSet<PathBuf>,
+    /// The files which are being loaded, each imported by the one before.
+    loading: Vec<FileId>,
+    ast: AbstractSyntaxTree,
+    errors: Vec<CompilationError>,
+}
+
+impl Loader<'_> {
+    /// Loads the file and the files it imports, and adds what it declares to the syntax tree.
+    fn load_file(&mut self, file: FileId) -> Result<(), RunError> {
+        let ast = match parse_file(self.sources, file) {
+            Ok(ast) => ast,
+            Err(RunError::Parsing(mut errors)) => {
+                self.errors.append(&mut errors);
+                return Ok(());
+            }
+            Err(error) => return Err(error),
+        };
+
+        self.loading.push(file);
+
+        for import in &ast.imports {
+            match self.import(file, import) {
+                Ok(Some(imported)) => self.load_file(imported)?,
+                Ok(None) => {}
+                Err(error) => self.errors.push(error),
+            }
+        }
+
+        self.loading.pop();
+
+        self.ast.append(ast, self.sources.namespace(file));
+
+        Ok(())
+    }
+
+    /// Reads the imported file into the source map, unless it has been loaded already.
+    fn import(&mut self, importer: FileId, import: &ImportStatement) -> CResult<Option<FileId>> {
+        let segments = import.segments();
+
+        let mut path = path_of(self.sources, importer)
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        path.extend(&segments);
+        path.set_extension("compost");
+
+        let cycle_start = self
+            .loading
+            .iter()
+            .position(|file| path_of(self.sources, *file) == path);
+        if let Some(start) = cycle_start {
+            let files: Vec<&str> = self.loading[start..]
+                .iter()
+                .map(|file| self.sources.name(*file))
+                .chain([self.sources.name(self.loading[start])])
+                .collect();
+
+            return import.error(ErrorMessage::ImportCycle(files.join(" -> ")));
+        }
+
+        if !self.imported.insert(path.clone()) {
+            return Ok(None);
+        }
+
+        let name = path.to_string_lossy().to_string();
+        let Ok(code) = fs::read_to_string(&path) else {
+            return import.error(ErrorMessage::NoImport(import.path.clone(), name));
+        };
+
+        let directories = &segments[..segments.len().saturating_sub(1)];
+        let namespace = [self.sources.namespace(importer)]
+            .into_iter()
+            .chain(directories.iter().copied())
+            .filter(|segment| !segment.is_empty())
+            .collect::<Vec<_>>()
+            .join("\\");
+
+        Ok(Some(self.sources.add_namespaced(&name, &code, &namespace)))
+    }
+}
+
+/// The path of a file, as given by its name.
+fn path_of(sources: &SourceMap, file: FileId) -> PathBuf {
+    PathBuf::from(sources.name(file))
+}
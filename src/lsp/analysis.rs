@@ -0,0 +1,455 @@
+use crate::ast::abstract_syntax_tree::AbstractSyntaxTree;
+use crate::ast::Statement;
+use crate::lex::token::{Kw, Op, Token};
+use crate::lex::tokenizer::tokenize;
+use crate::loader::load;
+use crate::run::{signature, STD_CODE, STD_FILE};
+use crate::sem::evaluation::Evaluation;
+use crate::sem::semantic_analyser::{analyse_ast, SemanticContext, SemanticScope};
+use crate::sem::table::parent;
+use crate::sem::trayt::{interface_type, Trait};
+use crate::sem::typ::Type;
+use crate::source::{FileId, SourceMap, Span};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// The file of the document in its source map, which comes after the standard library.
+pub(crate) const DOCUMENT: FileId = 1;
+
+/// The syntax tree and semantic context of a document, with the files it imports.
+pub(crate) struct Analysis {
+    pub sources: SourceMap,
+    ast: AbstractSyntaxTree,
+    context: SemanticContext,
+}
+
+/// A name in the code, and where it is.
+struct Name {
+    text: String,
+    span: Span,
+    /// Whether the name is called on a subject, as in '.Name'.
+    after_dot: bool,
+}
+
+/// The module path, locals and type of 'Self' at a position in the document.
+struct Enclosing {
+    path: String,
+    locals: HashMap<String, Type>,
+    zelf: Option<Type>,
+}
+
+/// A completion of a trait call.
+pub(crate) struct Completion {
+    pub label: String,
+    /// The full name and signature of the trait.
+    pub detail: String,
+}
+
+impl Analysis {
+    /// Analyses the code of the document, or returns nothing if it has errors.
+    pub(crate) fn new(path: &str, code: &str) -> Option<Analysis> {
+        let document_sources = || {
+            let mut sources = SourceMap::single(STD_FILE, STD_CODE);
+            sources.add(path, code);
+            sources
+        };
+
+        // The context takes the syntax tree it is analysed from, so the files are loaded twice.
+        let mut sources = document_sources();
+        let ast = load(&mut sources).ok()?;
+        let context = analyse_ast(load(&mut document_sources()).ok()?).ok()?;
+
+        Some(Analysis {
+            sources,
+            ast,
+            context,
+        })
+    }
+
+    /// The type or signature of what the name at the position refers to.
+    pub(crate) fn hover(&self, offset: usize) -> Option<String> {
+        let name = self.name_at(offset)?;
+
+        if name.after_dot {
+            let trayt = self.called_trait(&name)?;
+            let trayt = trayt.borrow();
+
+            return Some(format!(
+                "{}{}",
+                trayt.full_name,
+                signature(&trayt.inputs, &trayt.output)
+            ));
+        }
+
+        let enclosing = self.enclosing(offset);
+
+        if name.text.starts_with(|char: char| char.is_lowercase()) {
+            let typ = enclosing.locals.get(&name.text)?;
+
+            return Some(format!("{}: {}", name.text, typ));
+        }
+
+        let context = &self.context;
+        if let Ok(lett) = context.lets.resolve(&name.text, &enclosing.path) {
+            let lett = lett.borrow();
+
+            Some(format!(
+                "{}{}",
+                lett.name,
+                signature(&lett.inputs, &lett.output)
+            ))
+        } else if let Ok(interface) = context.interfaces.resolve(&name.text, &enclosing.path) {
+            let (full_name, _) = context
+                .interfaces
+                .iter()
+                .find(|(_, other)| Rc::ptr_eq(other, &interface))?;
+
+            Some(format!(
+                "mod {}: {}",
+                full_name,
+                interface_type(interface.borrow().as_ref())
+            ))
+        } else {
+            let trayt = context.traits.resolve(&name.text, &enclosing.path).ok()?;
+            let trayt = trayt.borrow();
+
+            Some(format!(
+                "{}{}",
+                trayt.full_name,
+                signature(&trayt.inputs, &trayt.output)
+            ))
+        }
+    }
+
+    /// Where the let, trait or module that the name at the position refers to is declared.
+    pub(crate) fn definition(&self, offset: usize) -> Option<Span> {
+        let name = self.name_at(offset)?;
+
+        let full_name = if name.after_dot {
+            self.called_trait(&name)?.borrow().full_name.clone()
+        } else {
+            let path = self.enclosing(offset).path;
+            let context = &self.context;
+
+            if let Ok(lett) = context.lets.resolve(&name.text, &path) {
+                lett.borrow().name.clone()
+            } else if let Ok(interface) = context.interfaces.resolve(&name.text, &path) {
+                context
+                    .interfaces
+                    .iter()
+                    .find(|(_, other)| Rc::ptr_eq(other, &interface))?
+                    .0
+            } else {
+                let trayt = context.traits.resolve(&name.text, &path).ok()?;
+                let full_name = trayt.borrow().full_name.clone();
+                full_name
+            }
+        };
+
+        self.declaration(&full_name)
+    }
+
+    /// The traits that can be called on the subject before the dot at the position, after which
+    /// the name of the trait may be partly typed. The subject is analysed without the dot.
+    pub(crate) fn completions(path: &str, code: &str, offset: usize) -> Vec<Completion> {
+        let is_name = |char: char| char.is_alphanumeric() || char == '\\';
+
+        let offset = offset.min(code.len());
+        let start = code[..offset].trim_end_matches(is_name).len();
+        let mut end =
+            offset + code[offset..].len() - code[offset..].trim_start_matches(is_name).len();
+        let Some(dot) = code[..start].strip_suffix('.').map(str::len) else {
+            return vec![];
+        };
+
+        // Leave out the inputs of the call as well, up to the matching parenthesis.
+        if code[end..].starts_with('(') {
+            let mut depth = 0;
+            for (index, char) in code[end..].char_indices() {
+                match char {
+                    '(' => depth += 1,
+                    ')' => depth -= 1,
+                    _ => continue,
+                }
+                if depth == 0 {
+                    end += index + 1;
+                    break;
+                }
+            }
+        }
+
+        let without_call = format!("{}{}", &code[..dot], &code[end..]);
+        let Some(analysis) = Analysis::new(path, &without_call) else {
+            return vec![];
+        };
+
+        let Some(traits) = analysis.callable_traits(dot) else {
+            return vec![];
+        };
+
+        // Use the last segment of the names where it is unambiguous.
+        let short_name = |name: &str| name.rsplit('\\').next().unwrap_or(name).to_string();
+        let mut counts = HashMap::new();
+        for name in &traits {
+            *counts.entry(short_name(name)).or_insert(0) += 1;
+        }
+
+        traits
+            .iter()
+            .filter_map(|name| {
+                let trayt = analysis.context.traits.resolve(name, "").ok()?;
+                let trayt = trayt.borrow();
+                let label = match counts[&short_name(name)] {
+                    1 => short_name(name),
+                    _ => name.clone(),
+                };
+
+                Some(Completion {
+                    label,
+                    detail: format!(
+                        "{}{}",
+                        trayt.full_name,
+                        signature(&trayt.inputs, &trayt.output)
+                    ),
+                })
+            })
+            .collect()
+    }
+
+    /// The names of the traits callable on the expression which ends at the position.
+    fn callable_traits(&self, end: usize) -> Option<Vec<String>> {
+        let enclosing = self.enclosing(end);
+        let scope = SemanticScope {
+            context: &self.context,
+            path: &enclosing.path,
+            locals: enclosing.locals.clone(),
+            zelf: enclosing.zelf.clone(),
+        };
+
+        // The innermost call which ends at the position is the subject.
+        let mut subject: Option<(Span, Type)> = None;
+        self.visit_calls(&mut |evaluation| {
+            let Some(span) = evaluation.span() else {
+                return;
+            };
+            let smaller = subject
+                .as_ref()
+                .is_none_or(|(other, _)| span.end - span.start < other.end - other.start);
+
+            if span.file == DOCUMENT && span.end == end && smaller && has_locals(evaluation, &scope)
+            {
+                if let Ok(typ) = evaluation.typ(&scope) {
+                    subject = Some((span, typ));
+                }
+            }
+        });
+
+        let typ = match subject {
+            Some((_, typ)) => typ,
+            // Locals and 'Self' aren't calls, so they are found by their name.
+            None => {
+                let name = self.name_at(end)?;
+                match name.text.as_str() {
+                    "Self" => enclosing.zelf.clone()?,
+                    local => enclosing.locals.get(local)?.clone(),
+                }
+            }
+        };
+
+        Some(typ.callable_traits(&scope).into_iter().collect())
+    }
+
+    /// The name at the position, if there is one.
+    fn name_at(&self, offset: usize) -> Option<Name> {
+        let tokens = tokenize(&self.sources, DOCUMENT).ok()?;
+        let remaining = tokens.remaining();
+
+        let index = (0..remaining.len()).find(|index| {
+            let span = tokens.span(*index);
+            span.start <= offset && offset <= span.end
+        })?;
+
+        let text = match &remaining[index].0 {
+            Token::Global(name) | Token::Local(name) => name.clone(),
+            Token::Kw(Kw::Zelf) => "Self".to_string(),
+            _ => return None,
+        };
+        let after_dot = index > 0 && remaining[index - 1].0 == Token::Op(Op::Dot);
+
+        Some(Name {
+            text,
+            span: tokens.span(index),
+            after_dot,
+        })
+    }
+
+    /// The trait of the innermost trait call at the name.
+    fn called_trait(&self, name: &Name) -> Option<Rc<RefCell<Trait>>> {
+        let mut called: Option<(Span, Rc<RefCell<Trait>>)> = None;
+
+        self.visit_calls(&mut |evaluation| {
+            let Evaluation::Trait(call) = evaluation else {
+                return;
+            };
+            let span = call.span;
+            let contains =
+                span.file == DOCUMENT && span.start <= name.span.start && name.span.end <= span.end;
+            let matches = call.trayt.borrow().full_name.ends_with(&name.text);
+            let smaller = called
+                .as_ref()
+                .is_none_or(|(other, _)| span.end - span.start < other.end - other.start);
+
+            if contains && matches && smaller {
+                called = Some((span, Rc::clone(&call.trayt)));
+            }
+        });
+
+        called.map(|(_, trayt)| trayt)
+    }
+
+    /// The module path, locals and type of 'Self' at the position.
+    fn enclosing(&self, offset: usize) -> Enclosing {
+        let contains =
+            |span: Span| span.file == DOCUMENT && span.start <= offset && offset <= span.end;
+        let inputs = |name: &str| {
+            self.context
+                .lets
+                .resolve(name, "")
+                .map(|lett| lett.borrow().inputs.clone())
+                .unwrap_or_default()
+        };
+
+        if let Some(module) = self.ast.mods.iter().find(|module| contains(module.span())) {
+            let mut locals = vec![];
+
+            if let Some(lett) = module.lets.iter().find(|lett| contains(lett.span())) {
+                locals = inputs(&format!("{}\\{}", module.name, lett.name));
+            } else if let Some(def) = module.defs.iter().find(|def| contains(def.span())) {
+                // Definitions have the dependencies of the class or the fields of the struct, and
+                // the inputs of the trait.
+                if module.class.is_some() || module.strukt.is_some() {
+                    locals = inputs(&module.name);
+                }
+                if let Ok(trayt) = self.context.traits.resolve(&def.name, &module.name) {
+                    locals.extend(trayt.borrow().inputs.clone());
+                }
+            }
+
+            let zelf = self
+                .context
+                .interfaces
+                .resolve(&module.name, "")
+                .ok()
+                .map(|interface| interface_type(interface.borrow().as_ref()));
+
+            return Enclosing {
+                path: module.name.clone(),
+                locals: locals.into_iter().collect(),
+                zelf,
+            };
+        }
+
+        match self.ast.lets.iter().find(|lett| contains(lett.span())) {
+            Some(lett) => Enclosing {
+                path: parent(&lett.name).to_string(),
+                locals: inputs(&lett.name).into_iter().collect(),
+                zelf: None,
+            },
+            None => Enclosing {
+                path: self.sources.namespace(DOCUMENT).to_string(),
+                locals: HashMap::new(),
+                zelf: None,
+            },
+        }
+    }
+
+    /// Where the let, trait or module with the full name is declared.
+    fn declaration(&self, full_name: &str) -> Option<Span> {
+        if let Some(lett) = self.ast.lets.iter().find(|lett| lett.name == full_name) {
+            return Some(lett.span());
+        }
+
+        for module in &self.ast.mods {
+            if module.name == full_name {
+                return Some(module.span());
+            }
+
+            let in_module = |name: &str| format!("{}\\{}", module.name, name) == full_name;
+            if let Some(trayt) = module.traits.iter().find(|trayt| in_module(&trayt.name)) {
+                return Some(trayt.span());
+            }
+            if let Some(lett) = module.lets.iter().find(|lett| in_module(&lett.name)) {
+                return Some(lett.span());
+            }
+        }
+
+        None
+    }
+
+    /// Visits every evaluation in the lets, classes and structs of the program.
+    fn visit_calls(&self, visitor: &mut impl FnMut(&Evaluation)) {
+        for (_, lett) in self.context.lets.iter() {
+            visit(&lett.borrow().evaluation, visitor);
+        }
+    }
+}
+
+fn visit(evaluation: &Evaluation, visitor: &mut impl FnMut(&Evaluation)) {
+    visitor(evaluation);
+
+    match evaluation {
+        Evaluation::Let(call) => {
+            for (_, input) in &call.inputs {
+                visit(input, visitor);
+            }
+        }
+        Evaluation::Trait(call) => {
+            visit(&call.subject, visitor);
+            for (_, input) in &call.inputs {
+                visit(input, visitor);
+            }
+        }
+        Evaluation::Match(call) => {
+            visit(&call.subject, visitor);
+            for (_, branch) in &call.branches {
+                visit(branch, visitor);
+            }
+        }
+        Evaluation::IfElse(call) => {
+            visit(&call.condition, visitor);
+            visit(&call.iff, visitor);
+            visit(&call.els, visitor);
+        }
+        Evaluation::ClassConstructor(class) => {
+            for (_, definition) in &class.definitions {
+                visit(definition, visitor);
+            }
+        }
+        Evaluation::StructConstructor(strukt) => {
+            for (_, definition) in &strukt.definitions {
+                visit(definition, visitor);
+            }
+        }
+        Evaluation::Literal(_)
+        | Evaluation::Local(_)
+        | Evaluation::FriendlyField(_)
+        | Evaluation::Zelf
+        | Evaluation::Void => {}
+    }
+}
+
+/// Whether the locals which the type of the evaluation depends on are in the scope.
+fn has_locals(evaluation: &Evaluation, scope: &SemanticScope) -> bool {
+    match evaluation {
+        Evaluation::Local(name) => scope.locals.contains_key(name),
+        Evaluation::FriendlyField(field) => scope
+            .locals
+            .contains_key(&format!("{}.{}", field.local_name, field.field_name)),
+        Evaluation::Trait(call) => has_locals(&call.subject, scope),
+        Evaluation::IfElse(call) => has_locals(&call.iff, scope) && has_locals(&call.els, scope),
+        // The branches of a match are typed with the matched local added to the scope.
+        Evaluation::Match(_) => false,
+        _ => true,
+    }
+}
@@ -0,0 +1,355 @@
+use std::fmt::{Display, Formatter};
+
+/// A JSON value, as sent between the language server and its client.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    /// The members of an object, in order.
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// Parses a complete JSON text.
+    pub fn parse(text: &str) -> Result<Json, String> {
+        let mut parser = Parser {
+            chars: text.chars().collect(),
+            position: 0,
+        };
+
+        let value = parser.value()?;
+        parser.skip_whitespace();
+
+        match parser.peek() {
+            None => Ok(value),
+            Some(char) => Err(format!("Unexpected '{}' after the value", char)),
+        }
+    }
+
+    /// An object with the given members.
+    pub fn object<const N: usize>(members: [(&str, Json); N]) -> Json {
+        Json::Object(
+            members
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+
+    /// The member of an object with the given key, or null.
+    pub fn get(&self, key: &str) -> &Json {
+        match self {
+            Json::Object(members) => members
+                .iter()
+                .find(|(member, _)| member == key)
+                .map_or(&Json::Null, |(_, value)| value),
+            _ => &Json::Null,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(string) => Some(string),
+            _ => None,
+        }
+    }
+
+    pub fn as_usize(&self) -> Option<usize> {
+        match self {
+            Json::Number(number) if *number >= 0.0 && number.fract() == 0.0 => {
+                Some(*number as usize)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl From<&str> for Json {
+    fn from(string: &str) -> Self {
+        Json::String(string.to_string())
+    }
+}
+
+impl From<String> for Json {
+    fn from(string: String) -> Self {
+        Json::String(string)
+    }
+}
+
+impl From<usize> for Json {
+    fn from(number: usize) -> Self {
+        Json::Number(number as f64)
+    }
+}
+
+impl From<bool> for Json {
+    fn from(bool: bool) -> Self {
+        Json::Bool(bool)
+    }
+}
+
+impl Display for Json {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(bool) => write!(f, "{}", bool),
+            Json::Number(number) => write!(f, "{}", number),
+            Json::String(string) => write_string(f, string),
+            Json::Array(values) => {
+                write!(f, "[")?;
+                for (index, value) in values.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(members) => {
+                write!(f, "{{")?;
+                for (index, (key, value)) in members.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+fn write_string(f: &mut Formatter<'_>, string: &str) -> std::fmt::Result {
+    write!(f, "\"")?;
+
+    for char in string.chars() {
+        match char {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            char if (char as u32) < 0x20 => write!(f, "\\u{:04x}", char as u32)?,
+            char => write!(f, "{}", char)?,
+        }
+    }
+
+    write!(f, "\"")
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let char = self.peek();
+        self.position += 1;
+        char
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.position += 1;
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        self.skip_whitespace();
+
+        match self.next() {
+            Some(char) if char == expected => Ok(()),
+            Some(char) => Err(format!("Expected '{}', found '{}'", expected, char)),
+            None => Err(format!("Expected '{}', found the end", expected)),
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        self.skip_whitespace();
+
+        match self.peek() {
+            Some('n') => self.keyword("null", Json::Null),
+            Some('t') => self.keyword("true", Json::Bool(true)),
+            Some('f') => self.keyword("false", Json::Bool(false)),
+            Some('"') => Ok(Json::String(self.string()?)),
+            Some('[') => self.array(),
+            Some('{') => self.object(),
+            Some(char) if char == '-' || char.is_ascii_digit() => self.number(),
+            Some(char) => Err(format!("Unexpected '{}'", char)),
+            None => Err("Expected a value, found the end".to_string()),
+        }
+    }
+
+    fn keyword(&mut self, keyword: &str, value: Json) -> Result<Json, String> {
+        for expected in keyword.chars() {
+            if self.next() != Some(expected) {
+                return Err(format!("Expected '{}'", keyword));
+            }
+        }
+
+        Ok(value)
+    }
+
+    fn number(&mut self) -> Result<Json, String> {
+        let start = self.position;
+
+        while self
+            .peek()
+            .is_some_and(|char| char.is_ascii_digit() || "+-.eE".contains(char))
+        {
+            self.position += 1;
+        }
+
+        let number: String = self.chars[start..self.position].iter().collect();
+        number
+            .parse()
+            .map(Json::Number)
+            .map_err(|_| format!("Invalid number '{}'", number))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+
+        let mut string = String::new();
+        loop {
+            match self.next() {
+                Some('"') => return Ok(string),
+                Some('\\') => match self.next() {
+                    Some('n') => string.push('\n'),
+                    Some('r') => string.push('\r'),
+                    Some('t') => string.push('\t'),
+                    Some('b') => string.push('\u{8}'),
+                    Some('f') => string.push('\u{c}'),
+                    Some('u') => string.push(self.unicode_escape()?),
+                    Some(char @ ('"' | '\\' | '/')) => string.push(char),
+                    _ => return Err("Invalid escape in string".to_string()),
+                },
+                Some(char) => string.push(char),
+                None => return Err("Unterminated string".to_string()),
+            }
+        }
+    }
+
+    /// Reads the digits of a '\u' escape, combining surrogate pairs into one character.
+    fn unicode_escape(&mut self) -> Result<char, String> {
+        let high = self.hex()?;
+
+        let code = if (0xD800..0xDC00).contains(&high) {
+            if self.next() != Some('\\') || self.next() != Some('u') {
+                return Err("Expected the second half of a surrogate pair".to_string());
+            }
+            let low = self.hex()?;
+
+            0x10000 + ((high - 0xD800) << 10) + (low.wrapping_sub(0xDC00) & 0x3FF)
+        } else {
+            high
+        };
+
+        char::from_u32(code).ok_or_else(|| "Invalid unicode escape".to_string())
+    }
+
+    fn hex(&mut self) -> Result<u32, String> {
+        let digits: String = (0..4).filter_map(|_| self.next()).collect();
+
+        u32::from_str_radix(&digits, 16).map_err(|_| format!("Invalid unicode escape '{}'", digits))
+    }
+
+    fn array(&mut self) -> Result<Json, String> {
+        self.expect('[')?;
+
+        let mut values = vec![];
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.position += 1;
+            return Ok(Json::Array(values));
+        }
+
+        loop {
+            values.push(self.value()?);
+
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some(']') => return Ok(Json::Array(values)),
+                _ => return Err("Expected ',' or ']' in array".to_string()),
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<Json, String> {
+        self.expect('{')?;
+
+        let mut members = vec![];
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.position += 1;
+            return Ok(Json::Object(members));
+        }
+
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.expect(':')?;
+            members.push((key, self.value()?));
+
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some('}') => return Ok(Json::Object(members)),
+                _ => return Err("Expected ',' or '}' in object".to_string()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::lsp::json::Json;
+
+    #[test]
+    fn test_parse() {
+        let json = Json::parse(
+            r#"{"id": 1, "params": {"text": "a\n\"b\" é 😀", "list": [true, null, -2.5e1]}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(json.get("id").as_usize(), Some(1));
+        assert_eq!(
+            json.get("params").get("text").as_str(),
+            Some("a\n\"b\" é 😀")
+        );
+        assert_eq!(
+            json.get("params").get("list"),
+            &Json::Array(vec![Json::Bool(true), Json::Null, Json::Number(-25.0)])
+        );
+        assert_eq!(json.get("missing"), &Json::Null);
+
+        assert!(Json::parse("{\"a\": }").is_err());
+        assert!(Json::parse("[1, 2").is_err());
+        assert!(Json::parse("1 2").is_err());
+    }
+
+    #[test]
+    fn test_display() {
+        let json = Json::object([
+            ("id", 3.into()),
+            ("text", "say \"hi\"\n".into()),
+            ("items", Json::Array(vec![Json::Null, false.into()])),
+        ]);
+
+        assert_eq!(
+            json.to_string(),
+            r#"{"id":3,"text":"say \"hi\"\n","items":[null,false]}"#
+        );
+        assert_eq!(Json::parse(&json.to_string()), Ok(json));
+    }
+}
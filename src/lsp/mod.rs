@@ -0,0 +1,477 @@
+use crate::diagnostic::Diagnostic;
+use crate::lsp::analysis::{Analysis, DOCUMENT};
+use crate::lsp::json::Json;
+use crate::run::{check_code, Source, STD_FILE};
+use crate::source::Span;
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+
+mod analysis;
+pub(crate) mod json;
+
+/// The error code of the protocol for requests of an unknown method.
+const METHOD_NOT_FOUND: i32 = -32601;
+/// The error code of the protocol for messages which aren't valid JSON.
+const PARSE_ERROR: i32 = -32700;
+
+/// Runs a language server which speaks the Language Server Protocol over stdin and stdout, until
+/// the client asks it to exit.
+pub fn run_lsp() -> io::Result<()> {
+    serve(&mut io::stdin().lock(), &mut io::stdout().lock())
+}
+
+/// Handles the messages from the input and writes the responses and notifications to the output,
+/// until an exit notification or the end of the input.
+pub fn serve(input: &mut impl BufRead, output: &mut impl Write) -> io::Result<()> {
+    let mut server = Server {
+        documents: HashMap::new(),
+        output,
+    };
+
+    while let Some(message) = read_message(input)? {
+        let message = match Json::parse(&message) {
+            Ok(message) => message,
+            Err(error) => {
+                server.respond_error(&Json::Null, PARSE_ERROR, &error)?;
+                continue;
+            }
+        };
+
+        if message.get("method").as_str() == Some("exit") {
+            break;
+        }
+
+        server.handle(&message)?;
+    }
+
+    Ok(())
+}
+
+/// Reads the content of the next message, after its headers.
+fn read_message(input: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut length = None;
+
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                length = value.trim().parse().ok();
+            }
+        }
+    }
+
+    let Some(length) = length else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Message without a Content-Length header",
+        ));
+    };
+
+    let mut content = vec![0; length];
+    input.read_exact(&mut content)?;
+
+    String::from_utf8(content)
+        .map(Some)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+}
+
+struct Server<'a, W: Write> {
+    /// The code of the open documents, by their URIs.
+    documents: HashMap<String, String>,
+    output: &'a mut W,
+}
+
+impl<W: Write> Server<'_, W> {
+    fn handle(&mut self, message: &Json) -> io::Result<()> {
+        let id = message.get("id");
+        let params = message.get("params");
+        let document = params.get("textDocument");
+        let uri = document.get("uri").as_str().unwrap_or_default();
+
+        match message.get("method").as_str().unwrap_or_default() {
+            "initialize" => self.respond(id, capabilities()),
+            "shutdown" => self.respond(id, Json::Null),
+            "textDocument/didOpen" => {
+                let code = document.get("text").as_str().unwrap_or_default();
+                self.documents.insert(uri.to_string(), code.to_string());
+                self.publish_diagnostics(uri)
+            }
+            "textDocument/didChange" => {
+                // The whole document is sent on every change.
+                if let Json::Array(changes) = params.get("contentChanges") {
+                    if let Some(code) = changes
+                        .last()
+                        .and_then(|change| change.get("text").as_str())
+                    {
+                        self.documents.insert(uri.to_string(), code.to_string());
+                    }
+                }
+                self.publish_diagnostics(uri)
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(uri);
+                self.notify(
+                    "textDocument/publishDiagnostics",
+                    Json::object([("uri", uri.into()), ("diagnostics", Json::Array(vec![]))]),
+                )
+            }
+            "textDocument/hover" => {
+                let result = self.hover(uri, params.get("position"));
+                self.respond(id, result.unwrap_or(Json::Null))
+            }
+            "textDocument/definition" => {
+                let result = self.definition(uri, params.get("position"));
+                self.respond(id, result.unwrap_or(Json::Null))
+            }
+            "textDocument/completion" => {
+                let result = self.completion(uri, params.get("position"));
+                self.respond(id, result.unwrap_or(Json::Array(vec![])))
+            }
+            // Notifications without a handler, such as 'initialized', are ignored.
+            _ if *id == Json::Null => Ok(()),
+            method => self.respond_error(
+                id,
+                METHOD_NOT_FOUND,
+                &format!("Unknown method '{}'", method),
+            ),
+        }
+    }
+
+    fn publish_diagnostics(&mut self, uri: &str) -> io::Result<()> {
+        let code = self.documents.get(uri).map_or("", String::as_str);
+        let path = uri_to_path(uri);
+
+        let diagnostics = match check_code(Source::new(&path, code)) {
+            Ok(()) => vec![],
+            Err(diagnostics) => diagnostics
+                .iter()
+                .map(|diagnostic| lsp_diagnostic(diagnostic, &path))
+                .collect(),
+        };
+
+        self.notify(
+            "textDocument/publishDiagnostics",
+            Json::object([
+                ("uri", uri.into()),
+                ("diagnostics", Json::Array(diagnostics)),
+            ]),
+        )
+    }
+
+    fn hover(&self, uri: &str, position: &Json) -> Option<Json> {
+        let code = self.documents.get(uri)?;
+        let analysis = Analysis::new(&uri_to_path(uri), code)?;
+
+        let hover = analysis.hover(offset(code, position)?)?;
+
+        Some(Json::object([(
+            "contents",
+            Json::object([
+                ("kind", "markdown".into()),
+                ("value", format!("```compost\n{}\n```", hover).into()),
+            ]),
+        )]))
+    }
+
+    fn definition(&self, uri: &str, position: &Json) -> Option<Json> {
+        let code = self.documents.get(uri)?;
+        let analysis = Analysis::new(&uri_to_path(uri), code)?;
+
+        let span = analysis.definition(offset(code, position)?)?;
+        let name = analysis.sources.name(span.file);
+
+        // The standard library isn't a file that can be opened.
+        let uri = match name {
+            STD_FILE => return None,
+            _ if span.file == DOCUMENT => uri.to_string(),
+            name => path_to_uri(name),
+        };
+
+        Some(Json::object([
+            ("uri", uri.into()),
+            ("range", range(analysis.sources.file_code(span.file), span)),
+        ]))
+    }
+
+    fn completion(&self, uri: &str, position: &Json) -> Option<Json> {
+        let code = self.documents.get(uri)?;
+
+        let completions = Analysis::completions(&uri_to_path(uri), code, offset(code, position)?);
+
+        // Trait calls are completed as methods.
+        let items = completions
+            .into_iter()
+            .map(|completion| {
+                Json::object([
+                    ("label", completion.label.into()),
+                    ("kind", 2.into()),
+                    ("detail", completion.detail.into()),
+                ])
+            })
+            .collect();
+
+        Some(Json::Array(items))
+    }
+
+    fn respond(&mut self, id: &Json, result: Json) -> io::Result<()> {
+        self.send(Json::object([
+            ("jsonrpc", "2.0".into()),
+            ("id", id.clone()),
+            ("result", result),
+        ]))
+    }
+
+    fn respond_error(&mut self, id: &Json, code: i32, message: &str) -> io::Result<()> {
+        self.send(Json::object([
+            ("jsonrpc", "2.0".into()),
+            ("id", id.clone()),
+            (
+                "error",
+                Json::object([
+                    ("code", Json::Number(code.into())),
+                    ("message", message.into()),
+                ]),
+            ),
+        ]))
+    }
+
+    fn notify(&mut self, method: &str, params: Json) -> io::Result<()> {
+        self.send(Json::object([
+            ("jsonrpc", "2.0".into()),
+            ("method", method.into()),
+            ("params", params),
+        ]))
+    }
+
+    fn send(&mut self, message: Json) -> io::Result<()> {
+        let content = message.to_string();
+
+        write!(
+            self.output,
+            "Content-Length: {}\r\n\r\n{}",
+            content.len(),
+            content
+        )?;
+        self.output.flush()
+    }
+}
+
+/// What the server can do, in answer to the initialize request.
+fn capabilities() -> Json {
+    Json::object([
+        (
+            "capabilities",
+            Json::object([
+                // The whole document is synchronised on every change.
+                ("textDocumentSync", 1.into()),
+                ("hoverProvider", true.into()),
+                ("definitionProvider", true.into()),
+                (
+                    "completionProvider",
+                    Json::object([("triggerCharacters", Json::Array(vec![".".into()]))]),
+                ),
+            ]),
+        ),
+        (
+            "serverInfo",
+            Json::object([
+                ("name", "compost".into()),
+                ("version", env!("CARGO_PKG_VERSION").into()),
+            ]),
+        ),
+    ])
+}
+
+/// The diagnostic of the protocol for a diagnostic of the document. Diagnostics in other files
+/// are shown at the start of the document, with their location in the message.
+fn lsp_diagnostic(diagnostic: &Diagnostic, path: &str) -> Json {
+    let mut message = diagnostic.message.clone();
+    for note in &diagnostic.notes {
+        message += &format!("\nnote: {}", note);
+    }
+    for help in &diagnostic.help {
+        message += &format!("\nhelp: {}", help);
+    }
+
+    let range = match &diagnostic.location {
+        Some(location) if location.file == path => {
+            let start = utf16_len(location.source.chars().take(location.col - 1));
+            let end = utf16_len(
+                location
+                    .source
+                    .chars()
+                    .take(location.col - 1 + location.len),
+            );
+
+            Json::object([
+                ("start", position(location.line - 1, start)),
+                ("end", position(location.line - 1, end)),
+            ])
+        }
+        location => {
+            if let Some(location) = location {
+                message = format!(
+                    "{}:{}:{}: {}",
+                    location.file, location.line, location.col, message
+                );
+            }
+
+            Json::object([("start", position(0, 0)), ("end", position(0, 0))])
+        }
+    };
+
+    let mut members = vec![
+        ("range".to_string(), range),
+        // Every diagnostic is an error.
+        ("severity".to_string(), 1.into()),
+        ("source".to_string(), "compost".into()),
+        ("message".to_string(), message.into()),
+    ];
+    if let Some(code) = diagnostic.code {
+        members.push(("code".to_string(), code.into()));
+    }
+
+    Json::Object(members)
+}
+
+fn position(line: usize, character: usize) -> Json {
+    Json::object([("line", line.into()), ("character", character.into())])
+}
+
+/// The range of the first line of the span in the code of its file.
+fn range(code: &str, span: Span) -> Json {
+    let line_end = code[span.start..]
+        .find('\n')
+        .map_or(code.len(), |index| span.start + index);
+
+    let to_position = |offset: usize| {
+        let before = &code[..offset];
+        let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+
+        position(
+            before.matches('\n').count(),
+            utf16_len(before[line_start..].chars()),
+        )
+    };
+
+    Json::object([
+        ("start", to_position(span.start)),
+        ("end", to_position(span.end.min(line_end))),
+    ])
+}
+
+/// The byte offset in the code of a position of the protocol, of which the character counts
+/// UTF-16 code units.
+fn offset(code: &str, position: &Json) -> Option<usize> {
+    let line = position.get("line").as_usize()?;
+    let character = position.get("character").as_usize()?;
+
+    let line_start = if line == 0 {
+        0
+    } else {
+        code.match_indices('\n').nth(line - 1)?.0 + 1
+    };
+
+    let mut units = 0;
+    for (index, char) in code[line_start..].char_indices() {
+        if units >= character || char == '\n' {
+            return Some(line_start + index);
+        }
+        units += char.len_utf16();
+    }
+
+    Some(code.len())
+}
+
+fn utf16_len(chars: impl Iterator<Item = char>) -> usize {
+    chars.map(char::len_utf16).sum()
+}
+
+/// The path of a file URI, as in 'file:///home/compost/main.compost'. Other URIs are kept as
+/// they are.
+fn uri_to_path(uri: &str) -> String {
+    let Some(path) = uri.strip_prefix("file://") else {
+        return uri.to_string();
+    };
+
+    // Decode escaped bytes, as in '%20'.
+    let mut bytes = vec![];
+    let mut rest = path.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        let escaped = (byte == b'%')
+            .then(|| tail.get(..2))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+
+        match escaped {
+            Some(decoded) => {
+                bytes.push(decoded);
+                rest = &tail[2..];
+            }
+            None => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&bytes).to_string()
+}
+
+fn path_to_uri(path: &str) -> String {
+    let mut uri = "file://".to_string();
+
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-_.~".contains(&byte) {
+            uri.push(byte as char);
+        } else {
+            uri += &format!("%{:02X}", byte);
+        }
+    }
+
+    uri
+}
+
+#[cfg(test)]
+mod test {
+    use crate::lsp::json::Json;
+    use crate::lsp::{offset, path_to_uri, position, uri_to_path};
+
+    #[test]
+    fn test_offset() {
+        let code = "lets\n    Main: 'é😀x'\n";
+
+        assert_eq!(offset(code, &position(0, 2)), Some(2));
+        assert_eq!(offset(code, &position(1, 4)), Some(9));
+        // The emoji is two UTF-16 code units long.
+        assert_eq!(
+            offset(code, &position(1, 14)),
+            Some(code.find('x').unwrap())
+        );
+        // Positions past the end of a line are at its end.
+        assert_eq!(offset(code, &position(0, 10)), Some(4));
+        assert_eq!(offset(code, &position(5, 0)), None);
+        assert_eq!(offset(code, &Json::Null), None);
+    }
+
+    #[test]
+    fn test_uris() {
+        assert_eq!(
+            uri_to_path("file:///home/my%20code/main.compost"),
+            "/home/my code/main.compost"
+        );
+        assert_eq!(
+            path_to_uri("/home/my code/main.compost"),
+            "file:///home/my%20code/main.compost"
+        );
+    }
+}
@@ -1,12 +1,172 @@
-use compost::run::run_file;
+use compost::lsp::run_lsp;
+use compost::repl::run_repl;
+use compost::run::{
+    check_code, check_package, compile_code_to_c, compile_code_to_wat, dump_ast, dump_tokens,
+    dump_types, explain, format_code, run_code, run_package, Diagnostic, Source,
+};
 use std::env;
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::Path;
+use std::process::ExitCode;
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+const USAGE: &str = "Usage: compost <command> <file>
 
-    if let Some(file_path) = args.get(1) {
-        println!("{}", run_file(file_path))
+Commands:
+  run <file>         Run a program and print its result
+  run <package>      Run a package, given by its directory or compost.toml
+  check <file>       Parse and analyse a program without running it
+  check <package>    Parse and analyse a package without running it
+  fmt <file>         Rewrite a file in the canonical layout
+  fmt --check <file> Check that a file is in the canonical layout, without rewriting it
+  tokens <file>      Print the tokens of a file with their positions and levels
+  ast <file>         Print the abstract syntax tree of a file
+  types <file>       Print the types of the interfaces, traits and lets in a file
+  repl               Start an interactive session
+  lsp                Start a language server on stdin and stdout
+  explain <code>     Explain an error code, as in C0004
+  --emit-c <file>    Compile a program to C
+  --emit-wat <file>  Compile a program to a WebAssembly text module";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    match args.as_slice() {
+        ["run", path] if is_package(path) => report(run_package(path)),
+        ["run", file_path] => with_file(file_path, |source| run_code(source)),
+        ["check", path] if is_package(path) => report(check_package(path).map(|_| String::new())),
+        ["check", file_path] => {
+            with_file(file_path, |code| check_code(code).map(|_| String::new()))
+        }
+        ["fmt", file_path] if !file_path.starts_with('-') => format_file(file_path, false),
+        ["fmt", "--check", file_path] => format_file(file_path, true),
+        ["tokens", file_path] => with_file(file_path, |source| dump_tokens(source)),
+        ["ast", file_path] => with_file(file_path, |source| dump_ast(source)),
+        ["types", file_path] => with_file(file_path, |source| dump_types(source)),
+        ["repl"] => {
+            run_repl();
+            ExitCode::SUCCESS
+        }
+        ["lsp"] => match run_lsp() {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(error) => {
+                eprintln!("Language server failed: {}", error);
+                ExitCode::FAILURE
+            }
+        },
+        ["explain", code] => match explain(code) {
+            Some(explanation) => {
+                print_output(explanation);
+                ExitCode::SUCCESS
+            }
+            None => {
+                eprintln!("Unknown error code '{}'", code);
+                ExitCode::from(2)
+            }
+        },
+        ["--emit-c", file_path] => with_file(file_path, |source| compile_code_to_c(source)),
+        ["--emit-wat", file_path] => with_file(file_path, |source| compile_code_to_wat(source)),
+        // Running a file or package is the default.
+        [path] if is_package(path) => report(run_package(path)),
+        [file_path] if !file_path.starts_with('-') => {
+            with_file(file_path, |source| run_code(source))
+        }
+        _ => {
+            eprintln!("{}", USAGE);
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Whether the path is the directory or the manifest of a package, rather than a file of code.
+fn is_package(path: &str) -> bool {
+    let path = Path::new(path);
+
+    path.is_dir() || path.file_name().is_some_and(|name| name == "compost.toml")
+}
+
+/// Reads the file and passes its code, named by its path, to the command, and reports the result.
+fn with_file(
+    file_path: &str,
+    command: impl Fn(Source) -> Result<String, Vec<Diagnostic>>,
+) -> ExitCode {
+    let code = match fs::read_to_string(file_path) {
+        Ok(code) => code,
+        Err(error) => {
+            eprintln!("Unable to read file '{}': {}", file_path, error);
+            return ExitCode::from(2);
+        }
+    };
+
+    report(command(Source::new(file_path, &code)))
+}
+
+/// Rewrites the file in the canonical layout, or only checks that it already is. A file which
+/// isn't exits with 1 when checking.
+fn format_file(file_path: &str, check: bool) -> ExitCode {
+    let code = match fs::read_to_string(file_path) {
+        Ok(code) => code,
+        Err(error) => {
+            eprintln!("Unable to read file '{}': {}", file_path, error);
+            return ExitCode::from(2);
+        }
+    };
+
+    let formatted = match format_code(Source::new(file_path, &code)) {
+        Ok(formatted) => formatted,
+        Err(diagnostics) => return report(Err(diagnostics)),
+    };
+
+    if formatted == code {
+        ExitCode::SUCCESS
+    } else if check {
+        eprintln!("'{}' is not formatted", file_path);
+        ExitCode::FAILURE
+    } else {
+        match fs::write(file_path, formatted) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(error) => {
+                eprintln!("Unable to write file '{}': {}", file_path, error);
+                ExitCode::from(2)
+            }
+        }
+    }
+}
+
+/// Prints the output, or the diagnostics to stderr with an exit code for the stage that failed.
+fn report(result: Result<String, Vec<Diagnostic>>) -> ExitCode {
+    match result {
+        Ok(output) => {
+            print_output(&output);
+            ExitCode::SUCCESS
+        }
+        Err(diagnostics) => {
+            // Colour the output only when a person is likely to read it.
+            let colour = io::stderr().is_terminal() && env::var_os("NO_COLOR").is_none();
+
+            for diagnostic in &diagnostics {
+                eprintln!("{}\n", diagnostic.render(colour));
+            }
+
+            if let Some(code) = diagnostics.iter().find_map(|diagnostic| diagnostic.code) {
+                eprintln!(
+                    "For more information about an error, try `compost explain {}`.",
+                    code
+                );
+            }
+
+            // All diagnostics come from the same stage.
+            ExitCode::from(diagnostics[0].kind.exit_code())
+        }
+    }
+}
+
+/// Prints the output, ending it with a newline if it doesn't already have one.
+fn print_output(output: &str) {
+    if output.is_empty() || output.ends_with('\n') {
+        print!("{}", output);
     } else {
-        println!("Specify a source file to run")
+        println!("{}", output);
     }
 }
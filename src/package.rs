@@ -0,0 +1,550 @@
+use crate::diagnostic::{Diagnostic, DiagnosticKind, Snippet};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// The name of the manifest file in the directory of a package.
+pub(crate) const MANIFEST_FILE: &str = "compost.toml";
+
+/// The entry file of a package which doesn't name one.
+const DEFAULT_ENTRY: &str = "main.compost";
+
+/// The description of a package, as read from its compost.toml.
+///
+/// ```toml
+/// [package]
+/// name = "App"
+/// version = "0.1.0"
+/// entry = "main.compost"
+///
+/// [dependencies]
+/// Shapes = { path = "../shapes" }
+/// ```
+#[derive(Debug, PartialEq)]
+pub(crate) struct Manifest {
+    /// The name of the package, which is the namespace of everything it declares.
+    pub name: String,
+    pub version: String,
+    /// The path of the file that is loaded first, relative to the package directory.
+    pub entry: String,
+    /// The line of the entry, if it is given.
+    pub entry_line: Option<usize>,
+    pub dependencies: Vec<Dependency>,
+}
+
+/// A package that another package depends on.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Dependency {
+    pub name: String,
+    /// The directory of the package, relative to the directory of the depending package.
+    pub path: String,
+    /// The line on which the dependency is declared.
+    pub line: usize,
+}
+
+/// An error in a manifest, on the given line.
+#[derive(Debug, PartialEq)]
+pub(crate) struct ManifestError {
+    pub line: usize,
+    pub message: String,
+}
+
+/// A value in a manifest, which is a string or an inline table of strings.
+enum Value {
+    String(String),
+    Table(Vec<(String, String)>),
+}
+
+impl Manifest {
+    /// Parses the supported subset of TOML: sections, comments, and keys with a string or an
+    /// inline table of strings as their value.
+    pub(crate) fn parse(text: &str) -> Result<Manifest, ManifestError> {
+        let mut section = None;
+        let mut package_line = 1;
+        let mut package = HashMap::new();
+        let mut dependencies: Vec<Dependency> = vec![];
+
+        for (index, line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let error = |message: String| ManifestError {
+                line: line_number,
+                message,
+            };
+
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix('[') {
+                let Some(name) = name.strip_suffix(']') else {
+                    return Err(error("Expected ']' at the end of the section".to_string()));
+                };
+
+                section = match name.trim() {
+                    "package" => {
+                        package_line = line_number;
+                        Some("package")
+                    }
+                    "dependencies" => Some("dependencies"),
+                    name => return Err(error(format!("Unknown section '{}'", name))),
+                };
+                continue;
+            }
+
+            let (key, value) = parse_entry(line).map_err(error)?;
+
+            match (section, value) {
+                (None, _) => {
+                    return Err(error(format!(
+                        "'{}' must be in the [package] or [dependencies] section",
+                        key
+                    )))
+                }
+                (Some("package"), Value::String(value)) => {
+                    if !["name", "version", "entry"].contains(&key.as_str()) {
+                        return Err(error(format!("Unknown package key '{}'", key)));
+                    }
+                    if package.insert(key.clone(), (value, line_number)).is_some() {
+                        return Err(error(format!("'{}' is declared twice", key)));
+                    }
+                }
+                (Some("package"), Value::Table(_)) => {
+                    return Err(error(format!("'{}' must be a string", key)))
+                }
+                (Some(_), Value::Table(entries)) => {
+                    if dependencies.iter().any(|dependency| dependency.name == key) {
+                        return Err(error(format!("'{}' is declared twice", key)));
+                    }
+
+                    let Some((_, path)) = entries.into_iter().find(|(key, _)| key == "path") else {
+                        return Err(error(format!(
+                            "Dependency '{}' needs a path, as in {{ path = \"../{}\" }}",
+                            key,
+                            key.to_lowercase()
+                        )));
+                    };
+
+                    dependencies.push(Dependency {
+                        name: key,
+                        path,
+                        line: line_number,
+                    });
+                }
+                (Some(_), Value::String(_)) => {
+                    return Err(error(format!(
+                        "Dependency '{}' must be a table, as in {{ path = \"../{}\" }}",
+                        key,
+                        key.to_lowercase()
+                    )))
+                }
+            }
+        }
+
+        let missing = |key: &str| ManifestError {
+            line: package_line,
+            message: format!("The package has no {}", key),
+        };
+
+        let (name, name_line) = package.remove("name").ok_or_else(|| missing("name"))?;
+        let (version, _) = package
+            .remove("version")
+            .ok_or_else(|| missing("version"))?;
+        let entry = package.remove("entry");
+
+        if !is_global(&name) {
+            return Err(ManifestError {
+                line: name_line,
+                message: format!(
+                    "The package name '{}' must start with a capital letter and contain only letters and digits",
+                    name
+                ),
+            });
+        }
+
+        Ok(Manifest {
+            name,
+            version,
+            entry_line: entry.as_ref().map(|(_, line)| *line),
+            entry: entry.map_or(DEFAULT_ENTRY.to_string(), |(entry, _)| entry),
+            dependencies,
+        })
+    }
+}
+
+/// Removes a comment from the end of the line, if it isn't within a string.
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (index, char) in line.char_indices() {
+        match char {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..index],
+            _ => {}
+        }
+    }
+
+    line
+}
+
+/// Parses a line of the form 'key = value'.
+fn parse_entry(line: &str) -> Result<(String, Value), String> {
+    let Some((key, value)) = line.split_once('=') else {
+        return Err(format!("Expected 'key = value', found '{}'", line));
+    };
+
+    let key = parse_key(key)?;
+    let value = value.trim();
+
+    if let Some(entries) = value.strip_prefix('{') {
+        let Some(entries) = entries.strip_suffix('}') else {
+            return Err("Expected '}' at the end of the table".to_string());
+        };
+
+        let mut table = vec![];
+        for entry in entries.split(',').filter(|entry| !entry.trim().is_empty()) {
+            let Some((key, value)) = entry.split_once('=') else {
+                return Err(format!("Expected 'key = value', found '{}'", entry.trim()));
+            };
+
+            table.push((parse_key(key)?, parse_string(value.trim())?));
+        }
+
+        Ok((key, Value::Table(table)))
+    } else {
+        Ok((key, Value::String(parse_string(value)?)))
+    }
+}
+
+fn parse_key(key: &str) -> Result<String, String> {
+    let key = key.trim();
+
+    let valid = !key.is_empty()
+        && key
+            .chars()
+            .all(|char| char.is_ascii_alphanumeric() || char == '_' || char == '-');
+
+    if valid {
+        Ok(key.to_string())
+    } else {
+        Err(format!("Invalid key '{}'", key))
+    }
+}
+
+/// Parses a string in double quotes, in which quotes and backslashes are escaped by a backslash.
+fn parse_string(value: &str) -> Result<String, String> {
+    let error = || format!("Expected a string in double quotes, found '{}'", value);
+
+    let inner = value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .ok_or_else(error)?;
+
+    let mut string = String::new();
+    let mut chars = inner.chars();
+    while let Some(char) = chars.next() {
+        match char {
+            '\\' => match chars.next() {
+                Some(char @ ('"' | '\\')) => string.push(char),
+                _ => return Err(error()),
+            },
+            '"' => return Err(error()),
+            char => string.push(char),
+        }
+    }
+
+    Ok(string)
+}
+
+/// Whether the name can be used as a namespace, as in "Shapes".
+fn is_global(name: &str) -> bool {
+    name.starts_with(|char: char| char.is_ascii_uppercase())
+        && name.chars().all(|char| char.is_ascii_alphanumeric())
+}
+
+/// A package with the code of its entry file.
+pub(crate) struct Package {
+    pub name: String,
+    /// The path of the entry file, as shown in diagnostics.
+    pub entry: String,
+    pub code: String,
+}
+
+/// Reads the package in the directory, or of the given manifest file, and the packages it depends
+/// on. Every package comes after the packages it depends on, so the given package comes last.
+pub(crate) fn resolve_packages(path: &str) -> Result<Vec<Package>, Vec<Diagnostic>> {
+    let path = Path::new(path);
+    let dir = if path.file_name().is_some_and(|name| name == MANIFEST_FILE) {
+        path.parent().unwrap_or(Path::new(""))
+    } else {
+        path
+    };
+
+    let mut resolver = Resolver {
+        packages: vec![],
+        resolved: HashMap::new(),
+        resolving: vec![],
+    };
+    resolver.resolve(&normalize(dir), None)?;
+
+    Ok(resolver.packages)
+}
+
+struct Resolver {
+    packages: Vec<Package>,
+    /// The directories of the resolved packages, by their names.
+    resolved: HashMap<String, PathBuf>,
+    /// The names and directories of the packages which are being resolved, each depending on the
+    /// one before.
+    resolving: Vec<(String, PathBuf)>,
+}
+
+/// A dependency as declared in a manifest, to point errors at.
+struct Declaration<'a> {
+    dependency: &'a Dependency,
+    manifest: &'a str,
+    text: &'a str,
+}
+
+impl Resolver {
+    /// Resolves the package in the directory after the packages it depends on, unless it has
+    /// been resolved already.
+    fn resolve(
+        &mut self,
+        dir: &Path,
+        declaration: Option<Declaration>,
+    ) -> Result<(), Vec<Diagnostic>> {
+        let manifest_path = dir.join(MANIFEST_FILE);
+        let manifest_name = manifest_path.to_string_lossy().to_string();
+
+        let Ok(text) = fs::read_to_string(&manifest_path) else {
+            let message = format!("Unable to read the manifest '{}'", manifest_name);
+
+            return Err(vec![match declaration {
+                Some(declaration) => declaration.error(message),
+                None => package_error(message, None),
+            }]);
+        };
+
+        let manifest = Manifest::parse(&text).map_err(|error| {
+            vec![manifest_error(
+                &manifest_name,
+                &text,
+                error.line,
+                error.message,
+            )]
+        })?;
+
+        if let Some(declaration) = &declaration {
+            if declaration.dependency.name != manifest.name {
+                return Err(vec![declaration.error(format!(
+                    "The package at '{}' is named '{}', not '{}'",
+                    dir.display(),
+                    manifest.name,
+                    declaration.dependency.name
+                ))]);
+            }
+        }
+
+        let identity = fs::canonicalize(dir).unwrap_or(dir.to_path_buf());
+
+        if let Some(start) = self
+            .resolving
+            .iter()
+            .position(|(_, resolving)| *resolving == identity)
+        {
+            let names: Vec<&str> = self.resolving[start..]
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .chain([manifest.name.as_str()])
+                .collect();
+            let message = format!("Dependency cycle: {}", names.join(" -> "));
+
+            return Err(vec![match declaration {
+                Some(declaration) => declaration.error(message),
+                None => package_error(message, None),
+            }]);
+        }
+
+        match self.resolved.get(&manifest.name) {
+            Some(resolved) if *resolved == identity => return Ok(()),
+            Some(resolved) => {
+                let message = format!(
+                    "Two different packages are named '{}', at '{}' and '{}'",
+                    manifest.name,
+                    resolved.display(),
+                    identity.display()
+                );
+
+                return Err(vec![match declaration {
+                    Some(declaration) => declaration.error(message),
+                    None => package_error(message, None),
+                }]);
+            }
+            None => {}
+        }
+
+        self.resolving
+            .push((manifest.name.clone(), identity.clone()));
+
+        for dependency in &manifest.dependencies {
+            let declaration = Declaration {
+                dependency,
+                manifest: &manifest_name,
+                text: &text,
+            };
+
+            self.resolve(&normalize(&dir.join(&dependency.path)), Some(declaration))?;
+        }
+
+        self.resolving.pop();
+
+        let entry = normalize(&dir.join(&manifest.entry))
+            .to_string_lossy()
+            .to_string();
+        let Ok(code) = fs::read_to_string(&entry) else {
+            let message = format!("Unable to read the entry file '{}'", entry);
+            let line = manifest.entry_line.unwrap_or(1);
+
+            return Err(vec![manifest_error(&manifest_name, &text, line, message)]);
+        };
+
+        self.resolved.insert(manifest.name.clone(), identity);
+        self.packages.push(Package {
+            name: manifest.name,
+            entry,
+            code,
+        });
+
+        Ok(())
+    }
+}
+
+impl Declaration<'_> {
+    /// An error pointing at the declaration of the dependency.
+    fn error(&self, message: String) -> Diagnostic {
+        manifest_error(self.manifest, self.text, self.dependency.line, message)
+    }
+}
+
+/// A diagnostic pointing at a line of a manifest.
+fn manifest_error(file: &str, text: &str, line: usize, message: String) -> Diagnostic {
+    let source = text.lines().nth(line - 1).unwrap_or_default();
+    let content = source.trim();
+
+    let snippet = Snippet {
+        file: file.to_string(),
+        line,
+        col: source.len() - source.trim_start().len() + 1,
+        len: content.chars().count().max(1),
+        source: source.to_string(),
+    };
+
+    package_error(message, Some(snippet))
+}
+
+pub(crate) fn package_error(message: String, location: Option<Snippet>) -> Diagnostic {
+    Diagnostic {
+        kind: DiagnosticKind::Package,
+        code: None,
+        message,
+        location,
+        labels: vec![],
+        notes: vec![],
+        help: vec![],
+        trace: vec![],
+    }
+}
+
+/// Removes the '.' and '..' parts of the path where possible, without reading the file system.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir
+                if matches!(
+                    normalized.components().next_back(),
+                    Some(Component::Normal(_))
+                ) =>
+            {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+
+    normalized
+}
+
+#[cfg(test)]
+mod test {
+    use crate::package::{normalize, Dependency, Manifest, ManifestError};
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn test_parse_manifest() {
+        let text = "# The application.
+[package]
+name = \"App\"
+version = \"0.1.0\" # Not used yet.
+
+[dependencies]
+Shapes = { path = \"../shapes\" }
+";
+
+        assert_eq!(
+            Manifest::parse(text),
+            Ok(Manifest {
+                name: "App".to_string(),
+                version: "0.1.0".to_string(),
+                entry: "main.compost".to_string(),
+                entry_line: None,
+                dependencies: vec![Dependency {
+                    name: "Shapes".to_string(),
+                    path: "../shapes".to_string(),
+                    line: 7,
+                }],
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_manifest_errors() {
+        let cases = [
+            ("[package]\nname = \"App\"\n", 1, "The package has no version"),
+            ("[package]\nname = \"app\"\nversion = \"1\"\n", 2, "The package name 'app' must start with a capital letter and contain only letters and digits"),
+            ("[package]\nname = App\n", 2, "Expected a string in double quotes, found 'App'"),
+            ("[packages]\n", 1, "Unknown section 'packages'"),
+            ("[dependencies]\nShapes = \"../shapes\"\n", 2, "Dependency 'Shapes' must be a table, as in { path = \"../shapes\" }"),
+        ];
+
+        for (text, line, message) in cases {
+            assert_eq!(
+                Manifest::parse(text),
+                Err(ManifestError {
+                    line,
+                    message: message.to_string()
+                }),
+                "{}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(
+            normalize(Path::new("packages/app/../shapes/./main.compost")),
+            PathBuf::from("packages/shapes/main.compost")
+        );
+        assert_eq!(
+            normalize(Path::new("../shapes")),
+            PathBuf::from("../shapes")
+        );
+    }
+}
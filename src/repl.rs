@@ -0,0 +1,239 @@
+use crate::ast::expression::ExpressionStatement;
+use crate::ast::parser::Parse;
+use crate::error::CResult;
+use crate::lex::token::{Kw, Token};
+use crate::lex::tokenizer::{is_incomplete, tokenize};
+use crate::run::{analyse, Diagnostic, DiagnosticKind, Limits, STD_CODE, STD_FILE};
+use crate::runtime::compiler::compile;
+use crate::runtime::vm::Vm;
+use crate::sem::evaluation::Evaluation;
+use crate::sem::lett::Let;
+use crate::sem::semantic_analyser::{SemanticContext, SemanticScope};
+use crate::sem::typ::Type;
+use crate::source::{FileId, SourceMap};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::rc::Rc;
+
+const HELP: &str =
+    "Enter an 'import', or a 'mod' or 'lets' block to declare it, or an expression to
+evaluate it.
+Blocks and indented lines continue until an empty line.
+  :type <expression>  Show the type of an expression
+  :help               Show this message
+  :quit               Exit";
+
+/// The names of the declarations so far and of the current input in diagnostics.
+const DECLARATIONS_FILE: &str = "<declarations>";
+const INPUT_FILE: &str = "<input>";
+
+/// The keywords which start a declaration that continues until an empty line.
+const DECLARATION_KEYWORDS: [Kw; 3] = [Kw::Private, Kw::Mod, Kw::Lets];
+
+/// An interactive session. Declared modules and lets accumulate, and expressions are evaluated
+/// against everything declared so far.
+pub struct Repl {
+    declarations: String,
+    limits: Limits,
+}
+
+impl Repl {
+    pub fn new() -> Self {
+        Self::with_limits(Limits::default())
+    }
+
+    pub fn with_limits(limits: Limits) -> Self {
+        Repl {
+            declarations: String::new(),
+            limits,
+        }
+    }
+
+    /// Handles a complete input and returns the text to show, which is empty for a successful
+    /// declaration.
+    pub fn input(&mut self, input: &str) -> String {
+        let input = input.trim_end();
+
+        if let Some(expression) = input.strip_prefix(":type") {
+            return match self.analyse_expression(expression.trim(), &mut self.sources()) {
+                Ok((_, typ)) => typ.to_string(),
+                Err(error) => error,
+            };
+        }
+
+        match input {
+            "" => String::new(),
+            ":help" => HELP.to_string(),
+            _ if input.starts_with(':') => format!("Unknown command '{}', try :help", input),
+            _ if starts_block(input, &[Kw::Import])
+                || starts_block(input, &DECLARATION_KEYWORDS) =>
+            {
+                self.declare(input)
+            }
+            _ => self.evaluate(input),
+        }
+    }
+
+    /// Whether the input needs more lines, following the same levels as the tokenizer.
+    /// Blocks which start with a keyword always continue until an empty line.
+    pub fn needs_more(input: &str) -> bool {
+        let input = input.strip_prefix(":type").map_or(input, str::trim_start);
+
+        let input = input.trim();
+
+        is_incomplete(input)
+            || starts_block(input, &DECLARATION_KEYWORDS)
+            || starts_block(input, &[Kw::Match])
+    }
+
+    fn declare(&mut self, input: &str) -> String {
+        let declarations = format!("{}{}\n", self.declarations, input);
+        let mut sources = self.sources();
+        sources.add(INPUT_FILE, input);
+
+        match analyse(&mut sources) {
+            Ok(_) => {
+                self.declarations = declarations;
+                String::new()
+            }
+            Err(error) => render(&error.to_diagnostics(&sources)),
+        }
+    }
+
+    fn evaluate(&self, expression: &str) -> String {
+        let mut sources = self.sources();
+
+        let (evaluation, typ) = match self.analyse_expression(expression, &mut sources) {
+            Ok(analysed) => analysed,
+            Err(error) => return error,
+        };
+
+        let lett = Rc::new(RefCell::new(Let {
+            name: "Input".into(),
+            inputs: vec![],
+            input_contexts: vec![],
+            output: typ,
+            evaluation,
+        }));
+
+        let program = compile(&lett);
+        let mut vm = Vm::new(&program, self.limits);
+
+        match vm.run().and_then(|result| vm.stringify(result)) {
+            Ok(string) => string,
+            Err(error) => error.to_diagnostic(&sources).to_string(),
+        }
+    }
+
+    /// Analyses an expression in the global scope, adding it to the sources of the declarations.
+    /// Errors are returned as strings.
+    fn analyse_expression(
+        &self,
+        expression: &str,
+        sources: &mut SourceMap,
+    ) -> Result<(Evaluation, Type), String> {
+        let context = analyse(sources).map_err(|error| render(&error.to_diagnostics(sources)))?;
+
+        let input = sources.add(INPUT_FILE, expression);
+
+        analyse_input(&context, sources, input).map_err(|error| {
+            error
+                .to_diagnostic(DiagnosticKind::Semantic, sources)
+                .to_string()
+        })
+    }
+
+    /// The standard library and the declarations so far.
+    fn sources(&self) -> SourceMap {
+        let mut sources = SourceMap::single(STD_FILE, STD_CODE);
+        sources.add(DECLARATIONS_FILE, &self.declarations);
+        sources
+    }
+}
+
+/// Parses and analyses the expression in the input file.
+fn analyse_input(
+    context: &SemanticContext,
+    sources: &SourceMap,
+    input: FileId,
+) -> CResult<(Evaluation, Type)> {
+    let mut tokens = tokenize(sources, input)?;
+
+    let statement = ExpressionStatement::parse(&mut tokens)?;
+    if !matches!(tokens.token(), Token::Eof) {
+        return tokens.unexpected_token_error();
+    }
+
+    let scope = SemanticScope {
+        context,
+        path: "",
+        locals: HashMap::new(),
+        zelf: None,
+    };
+
+    let evaluation = Evaluation::analyse(statement, &scope)?;
+    let typ = evaluation.typ(&scope)?;
+
+    Ok((evaluation, typ))
+}
+
+/// Renders diagnostics without colours, one after the other.
+fn render(diagnostics: &[Diagnostic]) -> String {
+    diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+impl Default for Repl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whether the first token of the input is one of the given keywords.
+fn starts_block(input: &str, keywords: &[Kw]) -> bool {
+    match tokenize(&SourceMap::single(INPUT_FILE, input), 0) {
+        Ok(tokens) => matches!(tokens.token(), Token::Kw(kw) if keywords.contains(kw)),
+        Err(_) => false,
+    }
+}
+
+/// Reads inputs from stdin and prints their results, until the end of the input or ':quit'.
+pub fn run_repl() {
+    let mut repl = Repl::new();
+    let mut lines = io::stdin().lock().lines();
+
+    println!("Compost REPL, enter :help for help");
+
+    loop {
+        prompt("> ");
+        let Some(Ok(mut input)) = lines.next() else {
+            break;
+        };
+
+        while Repl::needs_more(&input) {
+            prompt(". ");
+            match lines.next() {
+                Some(Ok(line)) if !line.trim().is_empty() => input = input + "\n" + &line,
+                _ => break,
+            }
+        }
+
+        if input.trim() == ":quit" {
+            break;
+        }
+
+        let output = repl.input(&input);
+        if !output.is_empty() {
+            println!("{}", output);
+        }
+    }
+}
+
+fn prompt(prompt: &str) {
+    print!("{}", prompt);
+    io::stdout().flush().expect("Unable to write to stdout");
+}
@@ -0,0 +1,426 @@
+#include <pthread.h>
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+/* The runtime of a compiled Compost program. Instances are never freed, as programs only live
+ * for as long as it takes to produce their output. */
+
+/* Not every program uses every part of the runtime. */
+#define RUNTIME static __attribute__((unused))
+
+typedef enum { VOID, INT, STRING, BOOL, OBJECT } Kind;
+
+typedef struct Value Value;
+
+/* A let or a trait definition. 'self' is NULL for lets. */
+typedef Value *(*Function)(Value *self, Value **args);
+
+struct Value {
+    Kind kind;
+    union {
+        int64_t integer;
+        const char *string;
+        bool boolean;
+        struct {
+            int class;
+            Value **slots;
+        } object;
+    } as;
+};
+
+/* The operation performed when a trait is called on a raw value. */
+typedef enum {
+    OP_NONE,
+    OP_ADD,
+    OP_SUB,
+    OP_MUL,
+    OP_DIV,
+    OP_NEG,
+    OP_EQ,
+    OP_LT,
+    OP_GT,
+    OP_AND,
+    OP_OR,
+    OP_STRING,
+    OP_NE,
+    OP_LTE,
+    OP_GTE,
+    OP_NOT
+} RawOp;
+
+/* Provided by the generated program. */
+extern const int num_traits;
+extern const int string_trait;
+extern const char *const trait_names[];
+extern const RawOp raw_ops[];
+extern const char *const class_names[];
+extern const int class_sizes[];
+extern const bool class_is_struct[];
+extern const bool class_is_string[];
+extern const int class_value_slots[];
+extern const Function vtables[];
+extern const int max_depth;
+extern const size_t stack_size;
+extern Value *tail_args[];
+
+RUNTIME Value void_value = {VOID, {0}};
+
+/* What a function returns when it ends with a tail call, which its caller makes instead, so that
+ * the stack doesn't grow. Functions copy their arguments before anything else, so one tail call
+ * can reuse the arguments of the one before. */
+RUNTIME Value tail_call_value = {VOID, {0}};
+RUNTIME Function tail_function;
+RUNTIME Value *tail_self;
+
+/* The number of nested calls. */
+RUNTIME int depth = 0;
+
+RUNTIME void fail(const char *format, ...) {
+    va_list args;
+    va_start(args, format);
+    vprintf(format, args);
+    va_end(args);
+    printf("\n");
+    exit(1);
+}
+
+RUNTIME void *allocate(size_t size) {
+    void *memory = malloc(size);
+    if (memory == NULL) {
+        fail("Out of memory");
+    }
+    return memory;
+}
+
+RUNTIME Value *make_int(int64_t integer) {
+    Value *value = allocate(sizeof(Value));
+    value->kind = INT;
+    value->as.integer = integer;
+    return value;
+}
+
+RUNTIME Value *make_string(const char *string) {
+    Value *value = allocate(sizeof(Value));
+    value->kind = STRING;
+    value->as.string = string;
+    return value;
+}
+
+RUNTIME Value *make_bool(bool boolean) {
+    Value *value = allocate(sizeof(Value));
+    value->kind = BOOL;
+    value->as.boolean = boolean;
+    return value;
+}
+
+RUNTIME const char *type_name(Value *value) {
+    switch (value->kind) {
+    case INT:
+        return "int";
+    case STRING:
+        return "string";
+    case BOOL:
+        return "bool";
+    case OBJECT:
+        return class_names[value->as.object.class];
+    default:
+        return "?";
+    }
+}
+
+RUNTIME const char *concat(const char *a, const char *b) {
+    size_t a_length = strlen(a);
+    size_t b_length = strlen(b);
+    char *string = allocate(a_length + b_length + 1);
+    memcpy(string, a, a_length);
+    memcpy(string + a_length, b, b_length + 1);
+    return string;
+}
+
+RUNTIME const char *raw_to_string(Value *value) {
+    char *string;
+
+    switch (value->kind) {
+    case INT:
+        string = allocate(21);
+        snprintf(string, 21, "%lld", (long long)value->as.integer);
+        return string;
+    case STRING:
+        return value->as.string;
+    case BOOL:
+        return value->as.boolean ? "true" : "false";
+    default:
+        return "?";
+    }
+}
+
+RUNTIME void unsupported(int trait, Value *value) {
+    fail("Operation '%s' is not supported by %s", trait_names[trait], type_name(value));
+}
+
+/* The right-hand side of a raw operation, which must have the same raw type as the subject. */
+RUNTIME Value *rhs_value(int trait, Value **args, Kind kind) {
+    Value *rhs = args[0];
+
+    if (rhs->kind == OBJECT || rhs->kind == VOID) {
+        fail("Operation '%s' is not supported by a non-raw right-hand side", trait_names[trait]);
+    }
+    if (rhs->kind != kind) {
+        unsupported(trait, rhs);
+    }
+
+    return rhs;
+}
+
+RUNTIME Value *raw_operation(int trait, Value *value, Value **args) {
+    int64_t a, b, result;
+
+    if (raw_ops[trait] == OP_STRING) {
+        return make_string(raw_to_string(value));
+    }
+    if (raw_ops[trait] == OP_NEG) {
+        if (value->kind != INT) {
+            unsupported(trait, value);
+        }
+        if (value->as.integer == INT64_MIN) {
+            fail("Integer overflow");
+        }
+        return make_int(-value->as.integer);
+    }
+    if (raw_ops[trait] == OP_NOT) {
+        if (value->kind != BOOL) {
+            unsupported(trait, value);
+        }
+        return make_bool(!value->as.boolean);
+    }
+
+    switch (value->kind) {
+    case INT:
+        if (raw_ops[trait] == OP_AND || raw_ops[trait] == OP_OR || raw_ops[trait] == OP_NONE) {
+            break;
+        }
+        a = value->as.integer;
+        b = rhs_value(trait, args, INT)->as.integer;
+
+        switch (raw_ops[trait]) {
+        case OP_ADD:
+            if (__builtin_add_overflow(a, b, &result)) {
+                fail("Integer overflow");
+            }
+            return make_int(result);
+        case OP_SUB:
+            if (__builtin_sub_overflow(a, b, &result)) {
+                fail("Integer overflow");
+            }
+            return make_int(result);
+        case OP_MUL:
+            if (__builtin_mul_overflow(a, b, &result)) {
+                fail("Integer overflow");
+            }
+            return make_int(result);
+        case OP_DIV:
+            if (b == 0) {
+                fail("Division by zero");
+            }
+            if (a == INT64_MIN && b == -1) {
+                fail("Integer overflow");
+            }
+            return make_int(a / b);
+        case OP_EQ:
+            return make_bool(a == b);
+        case OP_LT:
+            return make_bool(a < b);
+        case OP_GT:
+            return make_bool(a > b);
+        case OP_NE:
+            return make_bool(a != b);
+        case OP_LTE:
+            return make_bool(a <= b);
+        case OP_GTE:
+            return make_bool(a >= b);
+        default:
+            break;
+        }
+        break;
+    case STRING:
+        switch (raw_ops[trait]) {
+        case OP_ADD:
+            return make_string(concat(value->as.string, rhs_value(trait, args, STRING)->as.string));
+        case OP_EQ:
+            return make_bool(strcmp(value->as.string, rhs_value(trait, args, STRING)->as.string) == 0);
+        case OP_LT:
+            return make_bool(strlen(value->as.string) < strlen(rhs_value(trait, args, STRING)->as.string));
+        case OP_GT:
+            return make_bool(strlen(value->as.string) > strlen(rhs_value(trait, args, STRING)->as.string));
+        case OP_NE:
+            return make_bool(strcmp(value->as.string, rhs_value(trait, args, STRING)->as.string) != 0);
+        case OP_LTE:
+            return make_bool(strlen(value->as.string) <= strlen(rhs_value(trait, args, STRING)->as.string));
+        case OP_GTE:
+            return make_bool(strlen(value->as.string) >= strlen(rhs_value(trait, args, STRING)->as.string));
+        default:
+            break;
+        }
+        break;
+    case BOOL:
+        switch (raw_ops[trait]) {
+        case OP_EQ:
+            return make_bool(value->as.boolean == rhs_value(trait, args, BOOL)->as.boolean);
+        case OP_NE:
+            return make_bool(value->as.boolean != rhs_value(trait, args, BOOL)->as.boolean);
+        case OP_AND:
+            return make_bool(value->as.boolean && rhs_value(trait, args, BOOL)->as.boolean);
+        case OP_OR:
+            return make_bool(value->as.boolean || rhs_value(trait, args, BOOL)->as.boolean);
+        default:
+            break;
+        }
+        break;
+    default:
+        break;
+    }
+
+    unsupported(trait, value);
+    return NULL;
+}
+
+RUNTIME void enter(void) {
+    if (++depth > max_depth) {
+        fail("Recursion limit exceeded (max depth: %d)", max_depth);
+    }
+}
+
+RUNTIME Value *leave(Value *result) {
+    depth--;
+    return result;
+}
+
+RUNTIME Value *tail_call(Function function, Value *self, Value **args, int count) {
+    tail_function = function;
+    tail_self = self;
+    memcpy(tail_args, args, sizeof(Value *) * count);
+    return &tail_call_value;
+}
+
+/* Makes the tail calls that the result of a call stands for, until there is an actual result. */
+RUNTIME Value *finish(Value *result) {
+    while (result == &tail_call_value) {
+        result = tail_function(tail_self, tail_args);
+    }
+    return result;
+}
+
+/* Calls a trait on the subject, through the vtable of its class or as a raw operation. */
+RUNTIME Value *call_trait(int trait, Value *subject, Value **args) {
+    Function function;
+
+    switch (subject->kind) {
+    case OBJECT:
+        function = vtables[subject->as.object.class * num_traits + trait];
+        if (function == NULL) {
+            fail("Trait '%s' is not defined on this instance", trait_names[trait]);
+        }
+        return finish(function(subject, args));
+    case VOID:
+        fail("Can't call trait '%s' on void", trait_names[trait]);
+        return NULL;
+    default:
+        return raw_operation(trait, subject, args);
+    }
+}
+
+/* Like call_trait, but leaves a call through the vtable to the caller. */
+RUNTIME Value *tail_call_trait(int trait, Value *subject, Value **args, int count) {
+    Function function;
+
+    if (subject->kind == OBJECT) {
+        function = vtables[subject->as.object.class * num_traits + trait];
+        if (function != NULL) {
+            return tail_call(function, subject, args, count);
+        }
+    }
+    return call_trait(trait, subject, args);
+}
+
+RUNTIME Value *new_object(int class, Value **slots) {
+    int size = class_sizes[class];
+    Value *value = allocate(sizeof(Value));
+
+    if (class_is_struct[class]) {
+        for (int i = 0; i < size; i++) {
+            if (slots[i]->kind == OBJECT || slots[i]->kind == VOID) {
+                fail("Operation '%s' is not supported by a non-raw value", class_names[class]);
+            }
+        }
+    }
+
+    value->kind = OBJECT;
+    value->as.object.class = class;
+    value->as.object.slots = allocate(sizeof(Value *) * (size > 0 ? size : 1));
+    memcpy(value->as.object.slots, slots, sizeof(Value *) * size);
+    return value;
+}
+
+RUNTIME Value *friendly_field(Value *value, int class, int slot, const char *name) {
+    if (value->kind != OBJECT || value->as.object.class != class) {
+        fail("Field '%s' does not exist", name);
+    }
+    return value->as.object.slots[slot];
+}
+
+RUNTIME bool to_bool(Value *value) {
+    if (value->kind == OBJECT && class_value_slots[value->as.object.class] >= 0) {
+        value = value->as.object.slots[class_value_slots[value->as.object.class]];
+    }
+    if (value->kind != BOOL) {
+        fail("Value can't be resolved to a bool");
+    }
+    return value->as.boolean;
+}
+
+RUNTIME bool has_trait(Value *value, int trait) {
+    return value->kind == OBJECT && vtables[value->as.object.class * num_traits + trait] != NULL;
+}
+
+RUNTIME bool same_type(Value *a, Value *b) {
+    if (a->kind != b->kind) {
+        return false;
+    }
+    return a->kind != OBJECT || a->as.object.class == b->as.object.class;
+}
+
+/* Calls the String trait until the value is turned into an actual string. */
+RUNTIME const char *to_string(Value *value) {
+    for (;;) {
+        if (value->kind != OBJECT && value->kind != VOID) {
+            return raw_to_string(value);
+        }
+        if (value->kind == OBJECT && class_is_string[value->as.object.class]
+            && value->as.object.slots[0]->kind == STRING) {
+            return value->as.object.slots[0]->as.string;
+        }
+        value = call_trait(string_trait, value, NULL);
+    }
+}
+
+RUNTIME void *run_main(void *main) {
+    printf("%s\n", to_string(finish(((Function)main)(NULL, NULL))));
+    return NULL;
+}
+
+/* Runs the Main let on a thread with a stack that has room for the deepest calls. */
+RUNTIME int run(Function main) {
+    pthread_attr_t attributes;
+    pthread_t thread;
+
+    if (pthread_attr_init(&attributes) != 0 || pthread_attr_setstacksize(&attributes, stack_size) != 0
+        || pthread_create(&thread, &attributes, run_main, (void *)main) != 0) {
+        fail("Unable to start the program");
+    }
+    pthread_join(thread, NULL);
+    return 0;
+}
@@ -1,39 +1,352 @@
-use crate::ast::abstract_syntax_tree::AbstractSyntaxTree;
-use crate::ast::parser::Parse;
-use crate::error::CResult;
+use crate::codegen::c::generate_c;
+use crate::codegen::wat::generate_wat;
+pub use crate::diagnostic::{Diagnostic, DiagnosticKind, Label, Snippet};
+use crate::error::{CompilationError, RuntimeError};
+pub use crate::explanations::{error_codes, explain};
+use crate::formatter::format;
 use crate::lex::tokenizer::tokenize;
-use crate::runtime::evaluate::evaluate;
-use crate::sem::semantic_analyser::analyse_ast;
+use crate::loader::{load, parse_file};
+use crate::package::{package_error, resolve_packages};
+pub use crate::runtime::call_stack::Limits;
+use crate::runtime::compiler::compile;
+use crate::runtime::vm::Vm;
+use crate::sem::semantic_analyser::{analyse_ast, SemanticContext};
+use crate::sem::trayt::interface_type;
+use crate::sem::typ::Type;
+use crate::source::SourceMap;
+use std::collections::HashSet;
 use std::fs;
 
-pub fn run_file(file_path: &str) -> String {
-    let code = fs::read_to_string(file_path).expect("Unable to read file");
+pub(crate) const STD_CODE: &str = include_str!("resources/lib/std.compost");
 
-    run_code(&code)
+/// The name of the standard library file in diagnostics.
+pub(crate) const STD_FILE: &str = "std.compost";
+
+/// The name in diagnostics of code that isn't read from a file.
+const USER_FILE: &str = "user.compost";
+
+/// The code of a program, with the name of its file as shown in diagnostics.
+#[derive(Clone, Copy, Debug)]
+pub struct Source<'a> {
+    pub name: &'a str,
+    pub code: &'a str,
+}
+
+impl<'a> Source<'a> {
+    pub fn new(name: &'a str, code: &'a str) -> Self {
+        Source { name, code }
+    }
+
+    /// The source map of the standard library, followed by this code.
+    fn with_std(self) -> SourceMap {
+        let mut sources = SourceMap::single(STD_FILE, STD_CODE);
+        sources.add(self.name, self.code);
+        sources
+    }
+
+    /// The source map of just this code.
+    fn alone(self) -> SourceMap {
+        SourceMap::single(self.name, self.code)
+    }
+}
+
+impl<'a> From<&'a str> for Source<'a> {
+    fn from(code: &'a str) -> Self {
+        Source::new(USER_FILE, code)
+    }
+}
+
+impl<'a> From<&'a String> for Source<'a> {
+    fn from(code: &'a String) -> Self {
+        Source::new(USER_FILE, code)
+    }
+}
+
+pub fn run_file(file_path: &str) -> Result<String, Vec<Diagnostic>> {
+    run_file_with_limits(file_path, Limits::default())
+}
+
+pub fn run_file_with_limits(file_path: &str, limits: Limits) -> Result<String, Vec<Diagnostic>> {
+    let code = read_file(file_path)?;
+
+    run_code_with_limits(Source::new(file_path, &code), limits)
+}
+
+/// Reads the code of a file, failing with a diagnostic when it can't be read.
+fn read_file(file_path: &str) -> Result<String, Vec<Diagnostic>> {
+    fs::read_to_string(file_path).map_err(|error| {
+        let message = format!("Unable to read file '{}': {}", file_path, error);
+
+        vec![package_error(message, None)]
+    })
+}
+
+pub fn run_code<'a>(source: impl Into<Source<'a>>) -> Result<String, Vec<Diagnostic>> {
+    run_code_with_limits(source, Limits::default())
+}
+
+/// Runs the code, stopping with an error when the evaluation exceeds the given limits.
+pub fn run_code_with_limits<'a>(
+    source: impl Into<Source<'a>>,
+    limits: Limits,
+) -> Result<String, Vec<Diagnostic>> {
+    let mut sources = source.into().with_std();
+
+    run(&mut sources, limits).map_err(|error| error.to_diagnostics(&sources))
+}
+
+/// Runs the package in the directory, or of the given compost.toml, with its dependencies.
+pub fn run_package(path: &str) -> Result<String, Vec<Diagnostic>> {
+    run_package_with_limits(path, Limits::default())
+}
+
+pub fn run_package_with_limits(path: &str, limits: Limits) -> Result<String, Vec<Diagnostic>> {
+    let mut sources = package_sources(path)?;
+
+    run(&mut sources, limits).map_err(|error| error.to_diagnostics(&sources))
+}
+
+/// Parses and analyses the package with its dependencies, without running it.
+pub fn check_package(path: &str) -> Result<(), Vec<Diagnostic>> {
+    let mut sources = package_sources(path)?;
+
+    analyse(&mut sources)
+        .map(|_| ())
+        .map_err(|error| error.to_diagnostics(&sources))
 }
 
-pub fn run_code(code: &str) -> String {
-    let std_code = include_str!("resources/lib/std.compost");
-    let all_code = std_code.to_string() + code;
+/// The source map of the standard library, followed by the entry files of the package and its
+/// dependencies, each in the namespace of its package. The package itself comes last.
+fn package_sources(path: &str) -> Result<SourceMap, Vec<Diagnostic>> {
+    let packages = resolve_packages(path)?;
 
-    match run(&all_code) {
-        Ok(result) => result,
-        Err(error) => error.to_string(std_code, &all_code),
+    let mut sources = SourceMap::single(STD_FILE, STD_CODE);
+    for package in packages {
+        sources.add_namespaced(&package.entry, &package.code, &package.name);
     }
+
+    Ok(sources)
+}
+
+/// Parses and analyses the code without running it.
+pub fn check_code<'a>(source: impl Into<Source<'a>>) -> Result<(), Vec<Diagnostic>> {
+    let mut sources = source.into().with_std();
+
+    analyse(&mut sources)
+        .map(|_| ())
+        .map_err(|error| error.to_diagnostics(&sources))
+}
+
+/// Lists the tokens of the code, one per line, with their line and column and their level.
+pub fn dump_tokens<'a>(source: impl Into<Source<'a>>) -> Result<String, Vec<Diagnostic>> {
+    let sources = source.into().alone();
+
+    let tokens = tokenize(&sources, 0)
+        .map_err(RunError::Lexing)
+        .map_err(|error| error.to_diagnostics(&sources))?;
+
+    let mut dump = String::new();
+    for (index, (token, level)) in tokens.remaining().iter().enumerate() {
+        let location = sources.location(&tokens.span(index));
+
+        dump += &format!(
+            "{}:{}\t{}\t{:?}\n",
+            location.line, location.col, level, token
+        );
+    }
+
+    Ok(dump)
 }
 
-fn run(code: &str) -> CResult<String> {
-    let mut tokens = tokenize(code)?;
+/// Pretty-prints the abstract syntax tree of the code, leaving out the files it imports.
+pub fn dump_ast<'a>(source: impl Into<Source<'a>>) -> Result<String, Vec<Diagnostic>> {
+    let sources = source.into().alone();
+
+    let ast = parse_file(&sources, 0).map_err(|error| error.to_diagnostics(&sources))?;
+
+    Ok(format!("{:#?}\n", ast))
+}
+
+/// Writes the code in the canonical layout, keeping its comments.
+pub fn format_code<'a>(source: impl Into<Source<'a>>) -> Result<String, Vec<Diagnostic>> {
+    let sources = source.into().alone();
+
+    parse_file(&sources, 0)
+        .and_then(|ast| format(&sources, 0, &ast).map_err(RunError::Lexing))
+        .map_err(|error| error.to_diagnostics(&sources))
+}
+
+/// Lists every interface, trait and let declared in the code, with their resolved types.
+pub fn dump_types<'a>(source: impl Into<Source<'a>>) -> Result<String, Vec<Diagnostic>> {
+    let mut sources = source.into().with_std();
+
+    let context = analyse(&mut sources).map_err(|error| error.to_diagnostics(&sources))?;
+
+    // Leave out everything declared by the standard library.
+    let mut std_sources = Source::new(STD_FILE, STD_CODE).alone();
+    let std_context =
+        analyse(&mut std_sources).map_err(|error| error.to_diagnostics(&std_sources))?;
+    let std_names: HashSet<String> = std_context
+        .interfaces
+        .iter()
+        .map(|(name, _)| format!("interface {}", name))
+        .chain(
+            std_context
+                .traits
+                .iter()
+                .map(|(name, _)| format!("trait {}", name)),
+        )
+        .chain(
+            std_context
+                .lets
+                .iter()
+                .map(|(name, _)| format!("let {}", name)),
+        )
+        .collect();
+
+    let interfaces = context.interfaces.iter().map(|(name, interface)| {
+        let typ = interface_type(interface.borrow().as_ref());
+
+        (format!("interface {}", name), format!(": {}", typ))
+    });
+    let traits = context.traits.iter().map(|(name, trayt)| {
+        let trayt = trayt.borrow();
+
+        (
+            format!("trait {}", name),
+            signature(&trayt.inputs, &trayt.output),
+        )
+    });
+    let lets = context.lets.iter().map(|(name, lett)| {
+        let lett = lett.borrow();
+
+        (
+            format!("let {}", name),
+            signature(&lett.inputs, &lett.output),
+        )
+    });
+
+    let dump = interfaces
+        .chain(traits)
+        .chain(lets)
+        .filter(|(name, _)| !std_names.contains(name))
+        .map(|(name, signature)| name + &signature + "\n")
+        .collect();
+
+    Ok(dump)
+}
+
+/// The inputs and output of a let or trait, as in "(rhs: Int): Bool".
+pub(crate) fn signature(inputs: &[(String, Type)], output: &Type) -> String {
+    if inputs.is_empty() {
+        return format!(": {}", output);
+    }
+
+    let inputs: Vec<String> = inputs
+        .iter()
+        .map(|(name, typ)| format!("{}: {}", name, typ))
+        .collect();
+
+    format!("({}): {}", inputs.join(", "), output)
+}
+
+/// Compiles the file into the source of a C program that prints the result of the code.
+pub fn compile_file_to_c(file_path: &str) -> Result<String, Vec<Diagnostic>> {
+    let code = read_file(file_path)?;
+
+    compile_code_to_c(Source::new(file_path, &code))
+}
+
+/// Compiles the code into the source of a C program that prints its result.
+pub fn compile_code_to_c<'a>(source: impl Into<Source<'a>>) -> Result<String, Vec<Diagnostic>> {
+    let mut sources = source.into().with_std();
+
+    analyse(&mut sources)
+        .and_then(|context| generate_c(&context).map_err(|error| RunError::Semantic(vec![error])))
+        .map_err(|error| error.to_diagnostics(&sources))
+}
+
+/// Compiles the file into a WebAssembly text module that produces the result of the code.
+pub fn compile_file_to_wat(file_path: &str) -> Result<String, Vec<Diagnostic>> {
+    let code = read_file(file_path)?;
+
+    compile_code_to_wat(Source::new(file_path, &code))
+}
+
+/// Compiles the code into a WebAssembly text module that produces its result.
+pub fn compile_code_to_wat<'a>(source: impl Into<Source<'a>>) -> Result<String, Vec<Diagnostic>> {
+    let mut sources = source.into().with_std();
+
+    analyse(&mut sources)
+        .and_then(|context| generate_wat(&context).map_err(|error| RunError::Semantic(vec![error])))
+        .map_err(|error| error.to_diagnostics(&sources))
+}
+
+/// Any errors that stop a program from producing its output, by the stage they occurred in.
+pub(crate) enum RunError {
+    Lexing(CompilationError),
+    Parsing(Vec<CompilationError>),
+    Semantic(Vec<CompilationError>),
+    Runtime(RuntimeError),
+}
+
+impl RunError {
+    /// Turns the errors into diagnostics, sorted by their position in the code.
+    pub(crate) fn to_diagnostics(&self, sources: &SourceMap) -> Vec<Diagnostic> {
+        let (kind, mut errors): (_, Vec<_>) = match self {
+            RunError::Lexing(error) => (DiagnosticKind::Lexing, vec![error]),
+            RunError::Parsing(errors) => (DiagnosticKind::Parsing, errors.iter().collect()),
+            RunError::Semantic(errors) => (DiagnosticKind::Semantic, errors.iter().collect()),
+            RunError::Runtime(error) => {
+                return vec![error.to_diagnostic(sources)];
+            }
+        };
+
+        // Files are in order of their position in the code, and errors without a span come last.
+        errors.sort_by_key(|error| {
+            error
+                .context
+                .map_or((usize::MAX, 0), |span| (span.file, span.start))
+        });
+
+        errors
+            .into_iter()
+            .map(|error| error.to_diagnostic(kind, sources))
+            .collect()
+    }
+}
+
+impl From<RuntimeError> for RunError {
+    fn from(error: RuntimeError) -> Self {
+        RunError::Runtime(error)
+    }
+}
+
+/// Loads the files in the source map with the files they import, and analyses them.
+pub(crate) fn analyse(sources: &mut SourceMap) -> Result<SemanticContext, RunError> {
+    let ast = load(sources)?;
+
+    analyse_ast(ast).map_err(RunError::Semantic)
+}
+
+/// Runs the Main let, as seen from the namespace of the last file in the source map.
+fn run(sources: &mut SourceMap, limits: Limits) -> Result<String, RunError> {
+    let namespace = sources.namespace(sources.file_count() - 1).to_string();
+
+    let context = analyse(sources)?;
 
-    let ast = AbstractSyntaxTree::parse(&mut tokens)?;
+    let main_let = context
+        .lets
+        .resolve("Main", &namespace)
+        .map_err(|error| RunError::Semantic(vec![error]))?;
 
-    let context = analyse_ast(ast)?;
+    let program = compile(&main_let);
 
-    let main_let = context.lets.resolve("Main", "")?;
+    let mut vm = Vm::new(&program, limits);
 
-    let result = evaluate(&main_let.borrow().evaluation, &[].into(), &None);
+    let result = vm.run()?;
 
-    let string = result.to_string(&context)?;
+    let string = vm.stringify(result)?;
 
     Ok(string)
 }
@@ -0,0 +1,133 @@
+use crate::ast::type_statement::RawType;
+use crate::error::RuntimeErrorMessage;
+use crate::runtime::instance::Instance;
+use crate::runtime::raw_operation::RawOp;
+use crate::source::Span;
+use std::rc::Rc;
+
+pub type FunctionId = usize;
+pub type ClassId = usize;
+pub type TraitId = usize;
+
+/// A single instruction of the stack machine.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Instruction {
+    /// Push a constant from the program's constant pool.
+    Constant(usize),
+    /// Push a void instance.
+    Void,
+    /// Push the local in the given slot of the current frame.
+    LoadLocal(usize),
+    /// Pop into the given slot of the current frame.
+    StoreLocal(usize),
+    /// Push the instance the current trait was called on.
+    LoadSelf,
+    /// Push a dependency or field of the instance the current trait was called on.
+    LoadSlot(usize),
+    /// Pop a struct instance of the given struct and push one of its fields.
+    FriendlyField {
+        class: ClassId,
+        slot: usize,
+    },
+    /// Pop the parameters and call a let function.
+    CallLet {
+        function: FunctionId,
+        site: usize,
+    },
+    /// Pop the subject and the inputs and call a trait on the subject.
+    CallTrait {
+        trayt: TraitId,
+        site: usize,
+    },
+    /// Like `CallLet`, but replaces the current frame, as its result is returned right away.
+    TailCallLet {
+        function: FunctionId,
+        site: usize,
+    },
+    /// Like `CallTrait`, but replaces the current frame, as its result is returned right away.
+    TailCallTrait {
+        trayt: TraitId,
+        site: usize,
+    },
+    /// Pop the dependencies or fields and push a new instance of the class or struct.
+    New(ClassId),
+    /// Pop a condition and jump if it is false.
+    JumpIfFalse(usize),
+    Jump(usize),
+    /// Jump unless the instance on top of the stack matches the pattern. Does not pop.
+    JumpUnlessMatches {
+        pattern: usize,
+        target: usize,
+    },
+    /// Stop with a runtime error.
+    Fail(RuntimeErrorMessage),
+    /// Return the instance on top of the stack.
+    Return,
+}
+
+/// A compiled let or trait definition.
+#[derive(Debug)]
+pub struct Function {
+    pub name: String,
+    /// The number of parameters, which occupy the first local slots.
+    pub params: usize,
+    /// The total number of local slots.
+    pub locals: usize,
+    pub code: Vec<Instruction>,
+}
+
+/// The runtime layout of a class or struct.
+#[derive(Debug)]
+pub struct ClassLayout {
+    pub name: String,
+    /// The names of the dependencies or fields, in slot order.
+    pub slots: Vec<String>,
+    /// The raw types of the fields, if this is a struct.
+    pub fields: Option<Vec<RawType>>,
+    /// The definition of each trait, indexed by trait id.
+    pub vtable: Vec<Option<FunctionId>>,
+}
+
+impl ClassLayout {
+    /// Whether instances represent a string through their first field.
+    pub fn is_string(&self) -> bool {
+        self.fields.as_ref().and_then(|fields| fields.first()) == Some(&RawType::String)
+            && self.slots.first().map(String::as_str) == Some("value")
+    }
+}
+
+/// A trait as far as the runtime is concerned.
+#[derive(Debug)]
+pub struct TraitInfo {
+    pub name: String,
+    pub inputs: Vec<String>,
+    /// The operation performed when the trait is called on a raw value.
+    pub raw_op: Option<RawOp>,
+}
+
+/// A type to match against at runtime.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Pattern {
+    Trait(TraitId),
+    Raw(RawType),
+    And(Box<Pattern>, Box<Pattern>),
+    Or(Box<Pattern>, Box<Pattern>),
+    Zelf,
+    Void,
+}
+
+/// A whole program lowered into bytecode.
+#[derive(Debug)]
+pub struct Program {
+    pub functions: Vec<Function>,
+    pub classes: Vec<ClassLayout>,
+    pub traits: Vec<TraitInfo>,
+    pub constants: Vec<Rc<Instance>>,
+    pub patterns: Vec<Pattern>,
+    /// Where the let and trait calls are, used for tracebacks.
+    pub call_sites: Vec<Span>,
+    /// The function of the Main let.
+    pub main: FunctionId,
+    /// The eponymous trait of the String module, used to output the result.
+    pub string_trait: TraitId,
+}
@@ -0,0 +1,42 @@
+use crate::source::Span;
+use std::fmt::{Display, Formatter};
+
+/// A single let or trait call on the Compost call stack.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame {
+    /// The full name of the called let or trait.
+    pub name: String,
+    /// The class or struct the trait was called on, if this is a trait call.
+    pub receiver: Option<String>,
+    /// Where the call site is.
+    pub span: Span,
+    /// The number of tail calls leading up to this call that were left out of the trace.
+    pub omitted_tail_calls: usize,
+}
+
+impl Display for Frame {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.receiver {
+            Some(receiver) => write!(f, "{} on {}", self.name, receiver),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+/// Limits on the evaluation of a program, to stop programs that don't terminate.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Limits {
+    /// The maximum number of nested let and trait calls.
+    pub max_depth: usize,
+    /// The maximum number of evaluation steps, or no maximum if `None`.
+    pub fuel: Option<u64>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_depth: 100_000,
+            fuel: None,
+        }
+    }
+}
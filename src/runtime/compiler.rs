@@ -0,0 +1,567 @@
+use crate::ast::type_statement::RawType;
+use crate::error::RuntimeErrorMessage;
+use crate::runtime::bytecode::{
+    ClassId, ClassLayout, Function, FunctionId, Instruction, Pattern, Program, TraitId, TraitInfo,
+};
+use crate::runtime::instance::Instance;
+use crate::runtime::raw_operation::RawOp;
+use crate::sem::evaluation::Evaluation;
+use crate::sem::lett::Let;
+use crate::sem::trayt::Trait;
+use crate::sem::typ::Type;
+use crate::source::Span;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Lowers the Main let, and everything it depends on, into bytecode.
+pub fn compile(main: &Rc<RefCell<Let>>) -> Program {
+    let mut compiler = Compiler::new();
+
+    let main = compiler.let_function(main);
+    let string_trait = compiler.trait_id("String", vec![]);
+
+    while let Some(job) = compiler.jobs.pop() {
+        compiler.compile_job(job);
+    }
+
+    // Now that all traits are known, fill the vtables.
+    let num_traits = compiler.traits.len();
+    for class in compiler.classes.iter_mut() {
+        class.vtable.resize(num_traits, None);
+    }
+    for (class, trayt, function) in compiler.definitions {
+        compiler.classes[class].vtable[trayt] = Some(function);
+    }
+
+    Program {
+        functions: compiler.functions,
+        classes: compiler.classes,
+        traits: compiler.traits,
+        constants: compiler.constants,
+        patterns: compiler.patterns,
+        call_sites: compiler.call_sites,
+        main,
+        string_trait,
+    }
+}
+
+/// A function that still has to be compiled.
+enum Job {
+    Let(FunctionId, Rc<RefCell<Let>>),
+    Definition {
+        function: FunctionId,
+        class: ClassId,
+        inputs: Vec<String>,
+        evaluation: Evaluation,
+    },
+}
+
+/// Where a local variable lives.
+#[derive(Clone, Copy)]
+enum Local {
+    /// A slot of the current frame.
+    Frame(usize),
+    /// A dependency or field of the instance the trait was called on.
+    Zelf(usize),
+}
+
+/// The state of the function currently being compiled.
+struct FunctionBuilder {
+    code: Vec<Instruction>,
+    /// Names in scope, searched from the end so later names shadow earlier ones.
+    scope: Vec<(String, Local)>,
+    locals: usize,
+    /// The class or struct the trait definition belongs to, if this is a definition.
+    class: Option<ClassId>,
+}
+
+impl FunctionBuilder {
+    fn new(params: &[String], class: Option<(ClassId, &[String])>) -> Self {
+        let mut scope: Vec<_> = params
+            .iter()
+            .enumerate()
+            .map(|(slot, name)| (name.clone(), Local::Frame(slot)))
+            .collect();
+
+        // Dependencies and fields take precedence over inputs of the same name.
+        if let Some((_, slots)) = class {
+            scope.extend(
+                slots
+                    .iter()
+                    .enumerate()
+                    .map(|(slot, name)| (name.clone(), Local::Zelf(slot))),
+            );
+        }
+
+        FunctionBuilder {
+            code: vec![],
+            scope,
+            locals: params.len(),
+            class: class.map(|(class, _)| class),
+        }
+    }
+
+    fn emit(&mut self, instruction: Instruction) -> usize {
+        self.code.push(instruction);
+        self.code.len() - 1
+    }
+
+    /// Points the jump at the given index to the next instruction.
+    fn patch(&mut self, index: usize) {
+        let target = self.code.len();
+
+        match &mut self.code[index] {
+            Instruction::Jump(t)
+            | Instruction::JumpIfFalse(t)
+            | Instruction::JumpUnlessMatches { target: t, .. } => *t = target,
+            _ => unreachable!("Only jumps can be patched"),
+        }
+    }
+
+    /// Turns calls whose result is returned right away into tail calls.
+    fn mark_tail_calls(&mut self) {
+        for index in 0..self.code.len() {
+            if !self.returns_from(index + 1) {
+                continue;
+            }
+
+            self.code[index] = match self.code[index] {
+                Instruction::CallLet { function, site } => {
+                    Instruction::TailCallLet { function, site }
+                }
+                Instruction::CallTrait { trayt, site } => {
+                    Instruction::TailCallTrait { trayt, site }
+                }
+                _ => continue,
+            };
+        }
+    }
+
+    /// Whether continuing at the given index returns without doing anything else.
+    fn returns_from(&self, mut index: usize) -> bool {
+        loop {
+            match self.code.get(index) {
+                Some(Instruction::Return) => return true,
+                Some(Instruction::Jump(target)) => index = *target,
+                _ => return false,
+            }
+        }
+    }
+
+    fn local(&self, name: &str) -> Option<Local> {
+        self.scope
+            .iter()
+            .rev()
+            .find(|(local_name, _)| local_name == name)
+            .map(|(_, local)| *local)
+    }
+}
+
+struct Compiler {
+    functions: Vec<Function>,
+    classes: Vec<ClassLayout>,
+    traits: Vec<TraitInfo>,
+    constants: Vec<Rc<Instance>>,
+    patterns: Vec<Pattern>,
+    call_sites: Vec<Span>,
+    trait_ids: HashMap<String, TraitId>,
+    let_ids: HashMap<*const RefCell<Let>, FunctionId>,
+    class_ids: HashMap<*const (), ClassId>,
+    /// Which function defines which trait on which class.
+    definitions: Vec<(ClassId, TraitId, FunctionId)>,
+    jobs: Vec<Job>,
+}
+
+impl Compiler {
+    fn new() -> Self {
+        Compiler {
+            functions: vec![],
+            classes: vec![],
+            traits: vec![],
+            constants: vec![],
+            patterns: vec![],
+            call_sites: vec![],
+            trait_ids: HashMap::new(),
+            let_ids: HashMap::new(),
+            class_ids: HashMap::new(),
+            definitions: vec![],
+            jobs: vec![],
+        }
+    }
+
+    /// Reserves a function, to be compiled later.
+    fn reserve_function(&mut self, name: &str, params: usize) -> FunctionId {
+        self.functions.push(Function {
+            name: name.to_string(),
+            params,
+            locals: params,
+            code: vec![],
+        });
+        self.functions.len() - 1
+    }
+
+    fn let_function(&mut self, lett: &Rc<RefCell<Let>>) -> FunctionId {
+        if let Some(id) = self.let_ids.get(&Rc::as_ptr(lett)) {
+            return *id;
+        }
+
+        let id = {
+            let lett = lett.borrow();
+            self.reserve_function(&lett.name, lett.inputs.len())
+        };
+
+        self.let_ids.insert(Rc::as_ptr(lett), id);
+        self.jobs.push(Job::Let(id, Rc::clone(lett)));
+
+        id
+    }
+
+    fn trait_id(&mut self, name: &str, inputs: Vec<String>) -> TraitId {
+        if let Some(id) = self.trait_ids.get(name) {
+            return *id;
+        }
+
+        self.traits.push(TraitInfo {
+            name: name.to_string(),
+            inputs,
+            raw_op: RawOp::from_trait_name(name),
+        });
+
+        let id = self.traits.len() - 1;
+        self.trait_ids.insert(name.to_string(), id);
+        id
+    }
+
+    fn trait_id_of(&mut self, trayt: &Rc<RefCell<Trait>>) -> TraitId {
+        let trayt = trayt.borrow();
+        let inputs = trayt.inputs.iter().map(|(name, _)| name.clone()).collect();
+
+        self.trait_id(&trayt.full_name, inputs)
+    }
+
+    /// Registers a class or struct and its definitions.
+    fn class_id(
+        &mut self,
+        key: *const (),
+        name: &str,
+        slots: Vec<String>,
+        fields: Option<Vec<RawType>>,
+        definitions: &[(Rc<RefCell<Trait>>, Evaluation)],
+    ) -> ClassId {
+        if let Some(id) = self.class_ids.get(&key) {
+            return *id;
+        }
+
+        let class = self.classes.len();
+        self.class_ids.insert(key, class);
+        self.classes.push(ClassLayout {
+            name: name.to_string(),
+            slots,
+            fields,
+            vtable: vec![],
+        });
+
+        for (trayt, evaluation) in definitions {
+            let trait_id = self.trait_id_of(trayt);
+            let inputs = self.traits[trait_id].inputs.clone();
+            let function = self.reserve_function(&self.traits[trait_id].name.clone(), inputs.len());
+
+            self.definitions.push((class, trait_id, function));
+            self.jobs.push(Job::Definition {
+                function,
+                class,
+                inputs,
+                evaluation: evaluation.clone(),
+            });
+        }
+
+        class
+    }
+
+    fn compile_job(&mut self, job: Job) {
+        let (function, builder, evaluation) = match job {
+            Job::Let(function, lett) => {
+                let lett = lett.borrow();
+                let params: Vec<_> = lett.inputs.iter().map(|(name, _)| name.clone()).collect();
+
+                (
+                    function,
+                    FunctionBuilder::new(&params, None),
+                    lett.evaluation.clone(),
+                )
+            }
+            Job::Definition {
+                function,
+                class,
+                inputs,
+                evaluation,
+            } => {
+                let slots = self.classes[class].slots.clone();
+
+                (
+                    function,
+                    FunctionBuilder::new(&inputs, Some((class, &slots))),
+                    evaluation,
+                )
+            }
+        };
+
+        let mut builder = builder;
+        self.compile_evaluation(&mut builder, &evaluation);
+        builder.emit(Instruction::Return);
+        builder.mark_tail_calls();
+
+        let function = &mut self.functions[function];
+        function.locals = builder.locals;
+        function.code = builder.code;
+    }
+
+    fn compile_evaluation(&mut self, builder: &mut FunctionBuilder, eval: &Evaluation) {
+        match eval {
+            Evaluation::Let(call) => {
+                let params: Vec<_> = call
+                    .lett
+                    .borrow()
+                    .inputs
+                    .iter()
+                    .map(|(name, _)| name.clone())
+                    .collect();
+
+                self.compile_inputs(builder, &params, &call.inputs);
+
+                let function = self.let_function(&call.lett);
+                let site = self.call_site(call.span);
+
+                builder.emit(Instruction::CallLet { function, site });
+            }
+            Evaluation::Trait(call) => {
+                self.compile_evaluation(builder, &call.subject);
+
+                let trayt = self.trait_id_of(&call.trayt);
+                let params = self.traits[trayt].inputs.clone();
+
+                self.compile_inputs(builder, &params, &call.inputs);
+
+                let site = self.call_site(call.span);
+
+                builder.emit(Instruction::CallTrait { trayt, site });
+            }
+            Evaluation::Literal(value) => {
+                self.constants.push(Rc::new(Instance::Raw(value.clone())));
+
+                builder.emit(Instruction::Constant(self.constants.len() - 1));
+            }
+            Evaluation::Local(name) => self.compile_local(builder, name),
+            Evaluation::FriendlyField(ff) => {
+                self.compile_local(builder, &ff.local_name);
+
+                let slot = builder.class.and_then(|class| {
+                    self.classes[class]
+                        .slots
+                        .iter()
+                        .position(|slot| slot == &ff.field_name)
+                        .map(|slot| (class, slot))
+                });
+
+                builder.emit(match slot {
+                    Some((class, slot)) => Instruction::FriendlyField { class, slot },
+                    None => Instruction::Fail(RuntimeErrorMessage::MissingField(format!(
+                        "{}.{}",
+                        ff.local_name, ff.field_name
+                    ))),
+                });
+            }
+            Evaluation::Match(call) => {
+                self.compile_evaluation(builder, &call.subject);
+
+                let slot = builder.locals;
+                builder.locals += 1;
+
+                let mut ends = vec![];
+                for (typ, branch) in &call.branches {
+                    let pattern = self.pattern(typ);
+                    self.patterns.push(pattern);
+
+                    let next = builder.emit(Instruction::JumpUnlessMatches {
+                        pattern: self.patterns.len() - 1,
+                        target: 0,
+                    });
+                    builder.emit(Instruction::StoreLocal(slot));
+
+                    builder
+                        .scope
+                        .push((call.local_name.clone(), Local::Frame(slot)));
+                    self.compile_evaluation(builder, branch);
+                    builder.scope.pop();
+
+                    ends.push(builder.emit(Instruction::Jump(0)));
+                    builder.patch(next);
+                }
+
+                builder.emit(Instruction::Fail(RuntimeErrorMessage::NoMatchingBranch(
+                    call.local_name.clone(),
+                )));
+
+                for end in ends {
+                    builder.patch(end);
+                }
+            }
+            Evaluation::IfElse(call) => {
+                self.compile_evaluation(builder, &call.condition);
+                let els = builder.emit(Instruction::JumpIfFalse(0));
+
+                self.compile_evaluation(builder, &call.iff);
+                let end = builder.emit(Instruction::Jump(0));
+
+                builder.patch(els);
+                self.compile_evaluation(builder, &call.els);
+
+                builder.patch(end);
+            }
+            Evaluation::Zelf => {
+                builder.emit(match builder.class {
+                    Some(_) => Instruction::LoadSelf,
+                    None => Instruction::Fail(RuntimeErrorMessage::NoSelf),
+                });
+            }
+            Evaluation::Void => {
+                builder.emit(Instruction::Void);
+            }
+            Evaluation::ClassConstructor(class) => {
+                let slots: Vec<_> = class
+                    .dependencies
+                    .iter()
+                    .map(|(name, _)| name.clone())
+                    .collect();
+
+                for slot in &slots {
+                    self.compile_local(builder, slot);
+                }
+
+                let class = self.class_id(
+                    Rc::as_ptr(class) as *const (),
+                    &class.name,
+                    slots,
+                    None,
+                    &class.definitions,
+                );
+
+                builder.emit(Instruction::New(class));
+            }
+            Evaluation::StructConstructor(strukt) => {
+                let slots: Vec<_> = strukt.fields.iter().map(|(name, _)| name.clone()).collect();
+                let fields = strukt.fields.iter().map(|(_, typ)| *typ).collect();
+
+                for slot in &slots {
+                    self.compile_local(builder, slot);
+                }
+
+                let class = self.class_id(
+                    Rc::as_ptr(strukt) as *const (),
+                    &strukt.name,
+                    slots,
+                    Some(fields),
+                    &strukt.definitions,
+                );
+
+                builder.emit(Instruction::New(class));
+            }
+        }
+    }
+
+    /// Compiles the given inputs in the order of the parameters.
+    fn compile_inputs(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        params: &[String],
+        inputs: &[(String, Evaluation)],
+    ) {
+        for param in params {
+            match inputs.iter().find(|(name, _)| name == param) {
+                Some((_, eval)) => self.compile_evaluation(builder, eval),
+                None => {
+                    builder.emit(Instruction::Fail(RuntimeErrorMessage::MissingInput(
+                        param.clone(),
+                    )));
+                }
+            }
+        }
+    }
+
+    fn compile_local(&mut self, builder: &mut FunctionBuilder, name: &str) {
+        builder.emit(match builder.local(name) {
+            Some(Local::Frame(slot)) => Instruction::LoadLocal(slot),
+            Some(Local::Zelf(slot)) => Instruction::LoadSlot(slot),
+            None => Instruction::Fail(RuntimeErrorMessage::MissingLocal(name.to_string())),
+        });
+    }
+
+    fn call_site(&mut self, span: Span) -> usize {
+        self.call_sites.push(span);
+        self.call_sites.len() - 1
+    }
+
+    fn pattern(&mut self, typ: &Type) -> Pattern {
+        match typ {
+            Type::Trait(trayt) => Pattern::Trait(self.trait_id_of(trayt)),
+            Type::Raw(raw_type) => Pattern::Raw(*raw_type),
+            Type::And(a, b) => Pattern::And(Box::new(self.pattern(a)), Box::new(self.pattern(b))),
+            Type::Or(a, b) => Pattern::Or(Box::new(self.pattern(a)), Box::new(self.pattern(b))),
+            Type::Zelf => Pattern::Zelf,
+            Type::Void => Pattern::Void,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ast::abstract_syntax_tree::AbstractSyntaxTree;
+    use crate::ast::parser::Parse;
+    use crate::lex::tokenizer::tokenize;
+    use crate::runtime::bytecode::Instruction;
+    use crate::runtime::compiler::compile;
+    use crate::sem::semantic_analyser::analyse_ast;
+    use crate::source::SourceMap;
+
+    #[test]
+    fn test_compile() {
+        let code = include_str!("../resources/lib/std.compost").to_string()
+            + r#"
+lets
+    Double: (x: Int) -> Int
+        x * 2
+
+    Main: Int
+        Double(x: 21)
+"#;
+
+        let mut tokens = tokenize(&SourceMap::single("test.compost", &code), 0).unwrap();
+        let ast = AbstractSyntaxTree::parse(&mut tokens).unwrap();
+        let context = analyse_ast(ast).unwrap();
+        let program = compile(&context.lets.resolve("Main", "").unwrap());
+
+        let main = &program.functions[program.main];
+        assert_eq!(main.name, "Main");
+        assert_eq!(main.params, 0);
+
+        let double = program
+            .functions
+            .iter()
+            .position(|function| function.name == "Double")
+            .unwrap();
+        assert_eq!(program.functions[double].params, 1);
+        assert_eq!(program.functions[double].code[0], Instruction::LoadLocal(0));
+
+        // Calls whose result is returned right away are tail calls.
+        assert!(matches!(
+            main.code[..],
+            [Instruction::Constant(_), Instruction::CallLet { .. }, Instruction::TailCallLet { function, .. }, Instruction::Return]
+                if function == double
+        ));
+        assert!(matches!(
+            program.functions[double].code[..],
+            [.., Instruction::TailCallTrait { .. }, Instruction::Return]
+        ));
+    }
+}
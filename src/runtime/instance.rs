@@ -1,171 +1,77 @@
 use crate::ast::raw_value::RawValue;
 use crate::ast::type_statement::RawType;
-use crate::error::CResult;
-use crate::runtime::class_instance::ClassInstance;
-use crate::runtime::evaluate::evaluate;
-use crate::runtime::raw_operation::raw_operation;
-use crate::runtime::struct_instance::StructInstance;
-use crate::sem::semantic_analyser::SemanticContext;
-use crate::sem::trayt::Trait;
-use crate::sem::typ::Type;
-use std::borrow::Borrow;
-use std::cell::RefCell;
-use std::collections::HashMap;
+use crate::runtime::bytecode::{ClassId, Pattern, Program};
 use std::rc::Rc;
 
 /// An instantiated class or struct, or a raw value.
 #[derive(Debug)]
 pub enum Instance {
-    Class(ClassInstance),
-    Struct(StructInstance),
+    Object(Object),
     Raw(RawValue),
     Void,
 }
 
-impl Instance {
-    pub fn call(
-        self: &Rc<Self>,
-        trayt: Rc<RefCell<Trait>>,
-        mut inputs: HashMap<String, Rc<Instance>>,
-    ) -> Rc<Instance> {
-        let definitions = match self.as_ref() {
-            Instance::Struct(instance) => &instance.strukt().definitions,
-            Instance::Class(instance) => &instance.class().definitions,
-            Instance::Raw(value) => {
-                let result = raw_operation(value, &trayt.as_ref().borrow().full_name, inputs);
-
-                return Rc::new(Instance::Raw(result));
-            }
-            Instance::Void => panic!("Can't call trait on void"),
-        };
-
-        let evaluation = &definitions
-            .iter()
-            .find(|(t, _)| t == &trayt)
-            .map(|(_, eval)| eval)
-            .unwrap_or_else(|| {
-                panic!(
-                    "Couldn't find trait {} on instance",
-                    trayt.as_ref().borrow().full_name
-                )
-            });
-
-        inputs.extend(self.locals());
-
-        evaluate(evaluation, &inputs, &Some(Rc::clone(self)))
-    }
+/// An instance of a class or struct. The slots hold the dependencies of a class, or the raw
+/// fields of a struct.
+#[derive(Debug)]
+pub struct Object {
+    pub class: ClassId,
+    pub slots: Vec<Rc<Instance>>,
+}
 
-    fn locals(&self) -> HashMap<String, Rc<Instance>> {
-        match self {
-            Instance::Struct(instance) => instance.fields(),
-            Instance::Class(instance) => instance.dependencies(),
-            _ => unreachable!(),
-        }
-    }
+impl Drop for Object {
+    /// Drops nested objects iteratively, so long chains of instances such as linked lists don't
+    /// overflow the native stack.
+    fn drop(&mut self) {
+        let mut pending = std::mem::take(&mut self.slots);
 
-    /// Whether the current instance satisfies the given type.
-    pub fn satisfies_type(&self, typ: &Type, is_self: bool) -> bool {
-        match typ {
-            Type::Trait(trayt) => {
-                let trayt_name = &trayt.as_ref().borrow().full_name;
-                match self {
-                    Instance::Class(class) => class
-                        .class()
-                        .definitions
-                        .iter()
-                        .any(|(trayt, _)| &trayt.as_ref().borrow().full_name == trayt_name),
-                    Instance::Struct(strukt) => strukt
-                        .strukt()
-                        .definitions
-                        .iter()
-                        .any(|(trayt, _)| &trayt.as_ref().borrow().full_name == trayt_name),
-                    _ => false,
-                }
+        while let Some(instance) = pending.pop() {
+            if let Ok(Instance::Object(mut object)) = Rc::try_unwrap(instance) {
+                pending.append(&mut object.slots);
             }
-            Type::Raw(raw_type) => {
-                if let Instance::Raw(val) = self {
-                    match raw_type {
-                        RawType::Int => matches!(val, RawValue::Int(_)),
-                        RawType::String => matches!(val, RawValue::String(_)),
-                        RawType::Bool => matches!(val, RawValue::Bool(_)),
-                    }
-                } else {
-                    false
-                }
-            }
-            Type::And(a, b) => self.satisfies_type(a, is_self) && self.satisfies_type(b, is_self),
-            Type::Or(a, b) => self.satisfies_type(a, is_self) || self.satisfies_type(b, is_self),
-            Type::Zelf => is_self,
-            Type::Void => true,
         }
     }
+}
 
-    /// Whether the current instance is of the same type as the given instance.
-    /// This is used to match the 'Self' type in match statements during runtime.
-    pub fn is_of_same_type(&self, other: &Self) -> bool {
-        match self {
-            Instance::Class(klass) => {
-                if let Instance::Class(other) = other {
-                    klass.class() == other.class()
-                } else {
-                    false
-                }
-            }
-            Instance::Struct(strukt) => {
-                if let Instance::Struct(other) = other {
-                    strukt.strukt() == other.strukt()
-                } else {
-                    false
-                }
+impl Instance {
+    /// Whether the current instance satisfies the given pattern.
+    pub fn satisfies(&self, pattern: &Pattern, is_self: bool, program: &Program) -> bool {
+        match pattern {
+            Pattern::Trait(trayt) => match self {
+                Instance::Object(object) => program.classes[object.class].vtable[*trayt].is_some(),
+                _ => false,
+            },
+            Pattern::Raw(raw_type) => match self {
+                Instance::Raw(value) => RawType::from(value) == *raw_type,
+                _ => false,
+            },
+            Pattern::And(a, b) => {
+                self.satisfies(a, is_self, program) && self.satisfies(b, is_self, program)
             }
-            Instance::Raw(raw_value) => {
-                if let Instance::Raw(other) = other {
-                    match raw_value {
-                        RawValue::Int(_) => matches!(other, RawValue::Int(_)),
-                        RawValue::String(_) => matches!(other, RawValue::String(_)),
-                        RawValue::Bool(_) => matches!(other, RawValue::Bool(_)),
-                    }
-                } else {
-                    false
-                }
+            Pattern::Or(a, b) => {
+                self.satisfies(a, is_self, program) || self.satisfies(b, is_self, program)
             }
-            Instance::Void => matches!(other, Instance::Void),
+            Pattern::Zelf => is_self,
+            Pattern::Void => true,
         }
     }
 
-    pub fn to_string(self: &Rc<Self>, context: &SemanticContext) -> CResult<String> {
-        if let Instance::Raw(raw_value) = self.borrow() {
-            let result = match raw_value {
-                RawValue::String(value) => value.clone(),
-                RawValue::Int(value) => value.to_string(),
-                RawValue::Bool(value) => if *value { "true" } else { "false" }.to_string(),
-            };
-
-            return Ok(result);
-        } else if let Instance::Struct(strukt) = self.borrow() {
-            if strukt.strukt().fields.get(0) == Some(&("value".to_string(), RawType::String)) {
-                if let RawValue::String(value) = strukt.field("value") {
-                    return Ok(value.clone());
-                } else {
-                    unreachable!()
-                }
-            }
+    /// The class or struct of the instance, if it is an object.
+    pub fn class(&self) -> Option<ClassId> {
+        match self {
+            Instance::Object(object) => Some(object.class),
+            _ => None,
         }
-
-        // Call the String trait on self recursively until hitting a String.
-        let string_trait = context.traits.resolve("String", "")?;
-
-        self.call(string_trait, [].into()).to_string(context)
     }
 
-    pub fn to_bool(self: &Rc<Self>) -> bool {
-        if let Instance::Raw(RawValue::Bool(value)) = self.borrow() {
-            return *value;
-        } else if let Instance::Struct(strukt) = self.borrow() {
-            if let RawValue::Bool(value) = strukt.field("value") {
-                return *value;
-            }
+    /// Whether the current instance is of the same type as the given instance.
+    /// This is used to match the 'Self' type in match statements during runtime.
+    pub fn is_of_same_type(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Instance::Object(a), Instance::Object(b)) => a.class == b.class,
+            (Instance::Raw(a), Instance::Raw(b)) => RawType::from(a) == RawType::from(b),
+            (Instance::Void, Instance::Void) => true,
+            _ => false,
         }
-        panic!("Can't resolve instance to bool")
     }
 }
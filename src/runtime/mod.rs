@@ -1,5 +1,6 @@
-pub(crate) mod class_instance;
-pub(crate) mod evaluate;
+pub(crate) mod bytecode;
+pub(crate) mod call_stack;
+pub(crate) mod compiler;
 pub(crate) mod instance;
 pub(crate) mod raw_operation;
-pub(crate) mod struct_instance;
+pub(crate) mod vm;
@@ -1,143 +1,268 @@
 use crate::ast::raw_value::RawValue;
+use crate::error::{runtime_error, RResult, RuntimeErrorMessage};
 use crate::runtime::instance::Instance;
-use std::borrow::Borrow;
-use std::collections::HashMap;
-use std::rc::Rc;
+
+/// An operation on raw values, corresponding to a trait.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RawOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Neg,
+    Eq,
+    Lt,
+    Gt,
+    And,
+    Or,
+    String,
+    Ne,
+    Lte,
+    Gte,
+    Not,
+}
+
+impl RawOp {
+    /// The raw operation of a trait, if it has one.
+    pub fn from_trait_name(trayt: &str) -> Option<Self> {
+        let op = match trayt {
+            "Op\\Add" => RawOp::Add,
+            "Op\\Sub" => RawOp::Sub,
+            "Op\\Mul" => RawOp::Mul,
+            "Op\\Div" => RawOp::Div,
+            "Op\\Neg" => RawOp::Neg,
+            "Op\\Eq" => RawOp::Eq,
+            "Op\\Lt" => RawOp::Lt,
+            "Op\\Gt" => RawOp::Gt,
+            "Op\\And" => RawOp::And,
+            "Op\\Or" => RawOp::Or,
+            "String" => RawOp::String,
+            "Op\\Ne" => RawOp::Ne,
+            "Op\\Lte" => RawOp::Lte,
+            "Op\\Gte" => RawOp::Gte,
+            "Op\\Not" => RawOp::Not,
+            _ => return None,
+        };
+        Some(op)
+    }
+
+    fn trait_name(&self) -> &'static str {
+        match self {
+            RawOp::Add => "Op\\Add",
+            RawOp::Sub => "Op\\Sub",
+            RawOp::Mul => "Op\\Mul",
+            RawOp::Div => "Op\\Div",
+            RawOp::Neg => "Op\\Neg",
+            RawOp::Eq => "Op\\Eq",
+            RawOp::Lt => "Op\\Lt",
+            RawOp::Gt => "Op\\Gt",
+            RawOp::And => "Op\\And",
+            RawOp::Or => "Op\\Or",
+            RawOp::String => "String",
+            RawOp::Ne => "Op\\Ne",
+            RawOp::Lte => "Op\\Lte",
+            RawOp::Gte => "Op\\Gte",
+            RawOp::Not => "Op\\Not",
+        }
+    }
+}
 
 /// Performs an operation on a raw value.
-pub fn raw_operation(
-    value: &RawValue,
-    trayt: &str,
-    inputs: HashMap<String, Rc<Instance>>,
-) -> RawValue {
-    match trayt {
-        "Op\\Add" => add(value, &rhs(inputs)),
-        "Op\\Sub" => sub(value, &rhs(inputs)),
-        "Op\\Mul" => mul(value, &rhs(inputs)),
-        "Op\\Div" => div(value, &rhs(inputs)),
-        "Op\\Neg" => neg(value),
-        "Op\\Eq" => eq(value, &rhs(inputs)),
-        "Op\\Lt" => lt(value, &rhs(inputs)),
-        "Op\\Gt" => gt(value, &rhs(inputs)),
-        "Op\\And" => and(value, &rhs(inputs)),
-        "Op\\Or" => or(value, &rhs(inputs)),
-        "String" => to_string(value),
-        _ => panic!("No such raw trait: {}", trayt),
-    }
-}
-
-fn rhs(inputs: HashMap<String, Rc<Instance>>) -> RawValue {
-    if let Instance::Raw(value) = inputs.get("rhs").expect("No RHS given").borrow() {
-        value.clone()
-    } else {
-        panic!("RHS is not a raw value")
+pub fn raw_operation(value: &RawValue, op: RawOp, rhs: Option<&Instance>) -> RResult<RawValue> {
+    match op {
+        RawOp::Add => add(value, rhs_value(op, rhs)?),
+        RawOp::Sub => sub(value, rhs_value(op, rhs)?),
+        RawOp::Mul => mul(value, rhs_value(op, rhs)?),
+        RawOp::Div => div(value, rhs_value(op, rhs)?),
+        RawOp::Neg => neg(value),
+        RawOp::Eq => eq(value, rhs_value(op, rhs)?),
+        RawOp::Lt => lt(value, rhs_value(op, rhs)?),
+        RawOp::Gt => gt(value, rhs_value(op, rhs)?),
+        RawOp::And => and(value, rhs_value(op, rhs)?),
+        RawOp::Or => or(value, rhs_value(op, rhs)?),
+        RawOp::String => Ok(to_string(value)),
+        RawOp::Ne => ne(value, rhs_value(op, rhs)?),
+        RawOp::Lte => lte(value, rhs_value(op, rhs)?),
+        RawOp::Gte => gte(value, rhs_value(op, rhs)?),
+        RawOp::Not => not(value),
+    }
+}
+
+fn rhs_value(op: RawOp, rhs: Option<&Instance>) -> RResult<&RawValue> {
+    match rhs {
+        Some(Instance::Raw(value)) => Ok(value),
+        Some(_) => runtime_error(RuntimeErrorMessage::UnsupportedOperation(
+            op.trait_name().to_string(),
+            "a non-raw right-hand side",
+        )),
+        None => runtime_error(RuntimeErrorMessage::MissingInput("rhs".into())),
     }
 }
 
-fn int(value: &RawValue) -> &i64 {
+pub fn type_name(value: &RawValue) -> &'static str {
+    match value {
+        RawValue::Int(_) => "int",
+        RawValue::String(_) => "string",
+        RawValue::Bool(_) => "bool",
+    }
+}
+
+fn unsupported<T>(trayt: &str, value: &RawValue) -> RResult<T> {
+    runtime_error(RuntimeErrorMessage::UnsupportedOperation(
+        trayt.to_string(),
+        type_name(value),
+    ))
+}
+
+fn int<'a>(trayt: &str, value: &'a RawValue) -> RResult<&'a i64> {
     if let RawValue::Int(value) = value {
-        value
+        Ok(value)
     } else {
-        panic!("Value is not an int")
+        unsupported(trayt, value)
     }
 }
 
-fn string(value: &RawValue) -> &str {
+fn string<'a>(trayt: &str, value: &'a RawValue) -> RResult<&'a str> {
     if let RawValue::String(value) = value {
-        value
+        Ok(value)
     } else {
-        panic!("Value is not a string")
+        unsupported(trayt, value)
     }
 }
 
-fn bool(value: &RawValue) -> &bool {
+fn bool<'a>(trayt: &str, value: &'a RawValue) -> RResult<&'a bool> {
     if let RawValue::Bool(value) = value {
-        value
+        Ok(value)
     } else {
-        panic!("Value is not a bool")
+        unsupported(trayt, value)
+    }
+}
+
+fn checked(result: Option<i64>) -> RResult<RawValue> {
+    match result {
+        Some(value) => Ok(RawValue::Int(value)),
+        None => runtime_error(RuntimeErrorMessage::IntegerOverflow),
     }
 }
 
-fn add(value: &RawValue, rhs: &RawValue) -> RawValue {
+fn add(value: &RawValue, rhs: &RawValue) -> RResult<RawValue> {
     match value {
-        RawValue::Int(value) => RawValue::Int(value + int(rhs)),
-        RawValue::String(value) => RawValue::String(value.to_string() + string(rhs)),
-        RawValue::Bool(_) => panic!("Addition not supported by bool"),
+        RawValue::Int(value) => checked(value.checked_add(*int("Op\\Add", rhs)?)),
+        RawValue::String(value) => Ok(RawValue::String(
+            value.to_string() + string("Op\\Add", rhs)?,
+        )),
+        RawValue::Bool(_) => unsupported("Op\\Add", value),
     }
 }
 
-fn sub(value: &RawValue, rhs: &RawValue) -> RawValue {
+fn sub(value: &RawValue, rhs: &RawValue) -> RResult<RawValue> {
     match value {
-        RawValue::Int(value) => RawValue::Int(value - int(rhs)),
-        RawValue::String(_) => panic!("Subtraction not supported by string"),
-        RawValue::Bool(_) => panic!("Subtraction not supported by bool"),
+        RawValue::Int(value) => checked(value.checked_sub(*int("Op\\Sub", rhs)?)),
+        _ => unsupported("Op\\Sub", value),
     }
 }
 
-fn mul(value: &RawValue, rhs: &RawValue) -> RawValue {
+fn mul(value: &RawValue, rhs: &RawValue) -> RResult<RawValue> {
     match value {
-        RawValue::Int(value) => RawValue::Int(value * int(rhs)),
-        RawValue::String(_) => panic!("Multiplication not supported by string"),
-        RawValue::Bool(_) => panic!("Multiplication not supported by bool"),
+        RawValue::Int(value) => checked(value.checked_mul(*int("Op\\Mul", rhs)?)),
+        _ => unsupported("Op\\Mul", value),
     }
 }
 
-fn div(value: &RawValue, rhs: &RawValue) -> RawValue {
+fn div(value: &RawValue, rhs: &RawValue) -> RResult<RawValue> {
     match value {
-        RawValue::Int(value) => RawValue::Int(value / int(rhs)),
-        RawValue::String(_) => panic!("Division not supported by string"),
-        RawValue::Bool(_) => panic!("Division not supported by bool"),
+        RawValue::Int(value) => {
+            let rhs = *int("Op\\Div", rhs)?;
+
+            if rhs == 0 {
+                return runtime_error(RuntimeErrorMessage::DivisionByZero);
+            }
+
+            checked(value.checked_div(rhs))
+        }
+        _ => unsupported("Op\\Div", value),
     }
 }
 
-fn neg(value: &RawValue) -> RawValue {
+fn neg(value: &RawValue) -> RResult<RawValue> {
     match value {
-        RawValue::Int(value) => RawValue::Int(-*value),
-        RawValue::String(_) => panic!("Negation not supported by string"),
-        RawValue::Bool(_) => panic!("Negation not supported by bool"),
+        RawValue::Int(value) => checked(value.checked_neg()),
+        _ => unsupported("Op\\Neg", value),
     }
 }
 
-fn eq(value: &RawValue, rhs: &RawValue) -> RawValue {
+fn eq(value: &RawValue, rhs: &RawValue) -> RResult<RawValue> {
     let bool = match value {
-        RawValue::Int(value) => value == int(rhs),
-        RawValue::String(value) => value == string(rhs),
-        RawValue::Bool(value) => value == bool(rhs),
+        RawValue::Int(value) => value == int("Op\\Eq", rhs)?,
+        RawValue::String(value) => value == string("Op\\Eq", rhs)?,
+        RawValue::Bool(value) => value == bool("Op\\Eq", rhs)?,
     };
-    RawValue::Bool(bool)
+    Ok(RawValue::Bool(bool))
 }
 
-fn lt(value: &RawValue, rhs: &RawValue) -> RawValue {
+fn lt(value: &RawValue, rhs: &RawValue) -> RResult<RawValue> {
     let bool = match value {
-        RawValue::Int(value) => value < int(rhs),
-        RawValue::String(value) => value.len() < string(rhs).len(),
-        RawValue::Bool(_) => panic!("Less than not supported by bool"),
+        RawValue::Int(value) => value < int("Op\\Lt", rhs)?,
+        RawValue::String(value) => value.len() < string("Op\\Lt", rhs)?.len(),
+        RawValue::Bool(_) => return unsupported("Op\\Lt", value),
     };
-    RawValue::Bool(bool)
+    Ok(RawValue::Bool(bool))
 }
 
-fn gt(value: &RawValue, rhs: &RawValue) -> RawValue {
+fn gt(value: &RawValue, rhs: &RawValue) -> RResult<RawValue> {
     let bool = match value {
-        RawValue::Int(value) => value > int(rhs),
-        RawValue::String(value) => value.len() > string(rhs).len(),
-        RawValue::Bool(_) => panic!("Greater than not supported by bool"),
+        RawValue::Int(value) => value > int("Op\\Gt", rhs)?,
+        RawValue::String(value) => value.len() > string("Op\\Gt", rhs)?.len(),
+        RawValue::Bool(_) => return unsupported("Op\\Gt", value),
     };
-    RawValue::Bool(bool)
+    Ok(RawValue::Bool(bool))
 }
 
-fn and(value: &RawValue, rhs: &RawValue) -> RawValue {
+fn ne(value: &RawValue, rhs: &RawValue) -> RResult<RawValue> {
+    let bool = match value {
+        RawValue::Int(value) => value != int("Op\\Ne", rhs)?,
+        RawValue::String(value) => value != string("Op\\Ne", rhs)?,
+        RawValue::Bool(value) => value != bool("Op\\Ne", rhs)?,
+    };
+    Ok(RawValue::Bool(bool))
+}
+
+fn lte(value: &RawValue, rhs: &RawValue) -> RResult<RawValue> {
+    let bool = match value {
+        RawValue::Int(value) => value <= int("Op\\Lte", rhs)?,
+        RawValue::String(value) => value.len() <= string("Op\\Lte", rhs)?.len(),
+        RawValue::Bool(_) => return unsupported("Op\\Lte", value),
+    };
+    Ok(RawValue::Bool(bool))
+}
+
+fn gte(value: &RawValue, rhs: &RawValue) -> RResult<RawValue> {
+    let bool = match value {
+        RawValue::Int(value) => value >= int("Op\\Gte", rhs)?,
+        RawValue::String(value) => value.len() >= string("Op\\Gte", rhs)?.len(),
+        RawValue::Bool(_) => return unsupported("Op\\Gte", value),
+    };
+    Ok(RawValue::Bool(bool))
+}
+
+fn not(value: &RawValue) -> RResult<RawValue> {
+    match value {
+        RawValue::Bool(value) => Ok(RawValue::Bool(!value)),
+        _ => unsupported("Op\\Not", value),
+    }
+}
+
+fn and(value: &RawValue, rhs: &RawValue) -> RResult<RawValue> {
     match value {
-        RawValue::Bool(value) => RawValue::Bool(*value && *bool(rhs)),
-        RawValue::Int(_) => panic!("And operation not supported by int"),
-        RawValue::String(_) => panic!("And operation not supported by string"),
+        RawValue::Bool(value) => Ok(RawValue::Bool(*value && *bool("Op\\And", rhs)?)),
+        _ => unsupported("Op\\And", value),
     }
 }
 
-fn or(value: &RawValue, rhs: &RawValue) -> RawValue {
+fn or(value: &RawValue, rhs: &RawValue) -> RResult<RawValue> {
     match value {
-        RawValue::Bool(value) => RawValue::Bool(*value || *bool(rhs)),
-        RawValue::Int(_) => panic!("And operation not supported by int"),
-        RawValue::String(_) => panic!("And operation not supported by string"),
+        RawValue::Bool(value) => Ok(RawValue::Bool(*value || *bool("Op\\Or", rhs)?)),
+        _ => unsupported("Op\\Or", value),
     }
 }
 
@@ -149,3 +274,85 @@ fn to_string(value: &RawValue) -> RawValue {
     };
     RawValue::String(string)
 }
+
+#[cfg(test)]
+mod test {
+    use crate::ast::raw_value::RawValue;
+    use crate::error::{RResult, RuntimeErrorMessage};
+    use crate::runtime::instance::Instance;
+    use crate::runtime::raw_operation::{raw_operation, RawOp};
+
+    #[test]
+    fn test_arithmetic() {
+        let rhs = Instance::Raw(RawValue::Int(2));
+
+        assert_eq!(
+            raw_operation(&RawValue::Int(7), RawOp::Div, Some(&rhs)),
+            Ok(RawValue::Int(3))
+        );
+        assert_eq!(
+            raw_operation(&RawValue::Int(1), RawOp::Sub, Some(&rhs)),
+            Ok(RawValue::Int(-1))
+        );
+    }
+
+    #[test]
+    fn test_comparisons() {
+        let rhs = Instance::Raw(RawValue::Int(2));
+
+        assert_eq!(
+            raw_operation(&RawValue::Int(2), RawOp::Lte, Some(&rhs)),
+            Ok(RawValue::Bool(true))
+        );
+        assert_eq!(
+            raw_operation(&RawValue::Int(1), RawOp::Gte, Some(&rhs)),
+            Ok(RawValue::Bool(false))
+        );
+        assert_eq!(
+            raw_operation(&RawValue::Int(1), RawOp::Ne, Some(&rhs)),
+            Ok(RawValue::Bool(true))
+        );
+        assert_eq!(
+            raw_operation(&RawValue::Bool(false), RawOp::Not, None),
+            Ok(RawValue::Bool(true))
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let message = |result: RResult<RawValue>| result.unwrap_err().message;
+
+        assert_eq!(
+            message(raw_operation(
+                &RawValue::Int(1),
+                RawOp::Div,
+                Some(&Instance::Raw(RawValue::Int(0)))
+            )),
+            RuntimeErrorMessage::DivisionByZero
+        );
+        assert_eq!(
+            message(raw_operation(
+                &RawValue::Int(i64::MAX),
+                RawOp::Add,
+                Some(&Instance::Raw(RawValue::Int(1)))
+            )),
+            RuntimeErrorMessage::IntegerOverflow
+        );
+        assert_eq!(
+            message(raw_operation(&RawValue::Int(1), RawOp::Add, None)),
+            RuntimeErrorMessage::MissingInput("rhs".into())
+        );
+        assert_eq!(
+            message(raw_operation(
+                &RawValue::Bool(true),
+                RawOp::Sub,
+                Some(&Instance::Raw(RawValue::Bool(false)))
+            )),
+            RuntimeErrorMessage::UnsupportedOperation("Op\\Sub".into(), "bool")
+        );
+        assert_eq!(
+            message(raw_operation(&RawValue::Int(1), RawOp::Not, None)),
+            RuntimeErrorMessage::UnsupportedOperation("Op\\Not".into(), "int")
+        );
+    }
+}
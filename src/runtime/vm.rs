@@ -0,0 +1,466 @@
+use crate::ast::raw_value::RawValue;
+use crate::error::{runtime_error, RResult, RuntimeError, RuntimeErrorMessage};
+use crate::runtime::bytecode::{ClassId, FunctionId, Instruction, Program, TraitId};
+use crate::runtime::call_stack::{Frame, Limits};
+use crate::runtime::instance::{Instance, Object};
+use crate::runtime::raw_operation::{raw_operation, type_name};
+use std::rc::Rc;
+
+/// The number of tail calls kept per frame for tracebacks. Later ones are only counted.
+const TAIL_TRACE_LIMIT: usize = 10;
+
+/// A stack machine that executes a compiled program.
+pub struct Vm<'a> {
+    program: &'a Program,
+    stack: Vec<Rc<Instance>>,
+    frames: Vec<CallFrame>,
+    limits: Limits,
+    steps: u64,
+}
+
+/// The state of a single let or trait call.
+struct CallFrame {
+    function: FunctionId,
+    ip: usize,
+    /// The position of the first local slot on the stack.
+    base: usize,
+    /// The stack is truncated to this length on return, which also removes the subject of a
+    /// trait call.
+    bottom: usize,
+    zelf: Option<Rc<Instance>>,
+    /// The call site, if called from Compost code.
+    site: Option<usize>,
+    /// The calls this frame replaced through tail calls, for tracebacks.
+    tail_calls: Vec<TailCall>,
+    /// The number of replaced calls that didn't fit in `tail_calls`.
+    omitted_tail_calls: usize,
+}
+
+/// A call that was replaced by a tail call.
+struct TailCall {
+    function: FunctionId,
+    receiver: Option<ClassId>,
+    site: usize,
+}
+
+impl<'a> Vm<'a> {
+    pub fn new(program: &'a Program, limits: Limits) -> Self {
+        Vm {
+            program,
+            stack: vec![],
+            frames: vec![],
+            limits,
+            steps: 0,
+        }
+    }
+
+    /// Evaluates the Main let.
+    pub fn run(&mut self) -> RResult<Rc<Instance>> {
+        self.push_frame(self.program.main, None, None, 0)?;
+        self.execute()
+    }
+
+    /// Calls the String trait until the instance is turned into an actual string.
+    pub fn stringify(&mut self, mut instance: Rc<Instance>) -> RResult<String> {
+        loop {
+            match instance.as_ref() {
+                Instance::Raw(value) => {
+                    let string = match value {
+                        RawValue::String(value) => value.clone(),
+                        RawValue::Int(value) => value.to_string(),
+                        RawValue::Bool(value) => value.to_string(),
+                    };
+                    return Ok(string);
+                }
+                Instance::Object(object) if self.program.classes[object.class].is_string() => {
+                    if let Instance::Raw(RawValue::String(value)) = object.slots[0].as_ref() {
+                        return Ok(value.clone());
+                    }
+                }
+                _ => {}
+            }
+
+            self.stack.push(instance);
+            instance = match self.call_trait(self.program.string_trait, None, false)? {
+                Some(result) => result,
+                None => self.execute()?,
+            };
+        }
+    }
+
+    /// Runs until the frame on top of the stack returns.
+    fn execute(&mut self) -> RResult<Rc<Instance>> {
+        let depth = self.frames.len();
+        let bottom = self.frames[depth - 1].bottom;
+
+        let result = self.execute_frames(depth);
+
+        if result.is_err() {
+            // Discard the state of the failed calls.
+            self.frames.truncate(depth - 1);
+            self.stack.truncate(bottom);
+        }
+
+        result
+    }
+
+    fn execute_frames(&mut self, depth: usize) -> RResult<Rc<Instance>> {
+        let program = self.program;
+
+        loop {
+            self.step()?;
+
+            let frame = self.frames.last_mut().unwrap();
+            let instruction = &program.functions[frame.function].code[frame.ip];
+            frame.ip += 1;
+
+            match instruction {
+                Instruction::Constant(index) => {
+                    self.stack.push(Rc::clone(&program.constants[*index]));
+                }
+                Instruction::Void => self.stack.push(Rc::new(Instance::Void)),
+                Instruction::LoadLocal(slot) => {
+                    let local = Rc::clone(&self.stack[frame.base + slot]);
+                    self.stack.push(local);
+                }
+                Instruction::StoreLocal(slot) => {
+                    let local = self.stack.pop().unwrap();
+                    self.stack[frame.base + slot] = local;
+                }
+                Instruction::LoadSelf => match &frame.zelf {
+                    Some(zelf) => {
+                        let zelf = Rc::clone(zelf);
+                        self.stack.push(zelf);
+                    }
+                    None => return Err(self.error(RuntimeErrorMessage::NoSelf, None)),
+                },
+                Instruction::LoadSlot(slot) => {
+                    let local = match frame.zelf.as_deref() {
+                        Some(Instance::Object(object)) => Rc::clone(&object.slots[*slot]),
+                        _ => return Err(self.error(RuntimeErrorMessage::NoSelf, None)),
+                    };
+                    self.stack.push(local);
+                }
+                Instruction::FriendlyField { class, slot } => {
+                    let instance = self.stack.pop().unwrap();
+
+                    let field = match instance.as_ref() {
+                        Instance::Object(object) if object.class == *class => {
+                            Rc::clone(&object.slots[*slot])
+                        }
+                        _ => {
+                            let name = program.classes[*class].slots[*slot].clone();
+                            return Err(self.error(RuntimeErrorMessage::MissingField(name), None));
+                        }
+                    };
+                    self.stack.push(field);
+                }
+                Instruction::CallLet { function, site } => {
+                    let base = self.stack.len() - program.functions[*function].params;
+                    self.push_frame(*function, None, Some(*site), base)?;
+                }
+                Instruction::CallTrait { trayt, site } => {
+                    if let Some(result) = self.call_trait(*trayt, Some(*site), false)? {
+                        self.stack.push(result);
+                    }
+                }
+                Instruction::TailCallLet { function, site } => {
+                    let start = self.stack.len() - program.functions[*function].params;
+                    self.replace_frame(*function, None, *site, start);
+                }
+                Instruction::TailCallTrait { trayt, site } => {
+                    if let Some(result) = self.call_trait(*trayt, Some(*site), true)? {
+                        if let Some(result) = self.finish_call(result, depth) {
+                            return Ok(result);
+                        }
+                    }
+                }
+                Instruction::New(class) => {
+                    let layout = &program.classes[*class];
+                    let slots = self.stack.split_off(self.stack.len() - layout.slots.len());
+
+                    let is_struct = layout.fields.is_some();
+                    if is_struct && slots.iter().any(|slot| !matches!(**slot, Instance::Raw(_))) {
+                        let message = RuntimeErrorMessage::UnsupportedOperation(
+                            layout.name.clone(),
+                            "a non-raw value",
+                        );
+                        return Err(self.error(message, None));
+                    }
+
+                    let object = Object {
+                        class: *class,
+                        slots,
+                    };
+                    self.stack.push(Rc::new(Instance::Object(object)));
+                }
+                Instruction::JumpIfFalse(target) => {
+                    let condition = self.stack.pop().unwrap();
+
+                    if !self.to_bool(&condition)? {
+                        self.frames.last_mut().unwrap().ip = *target;
+                    }
+                }
+                Instruction::Jump(target) => frame.ip = *target,
+                Instruction::JumpUnlessMatches { pattern, target } => {
+                    let subject = self.stack.last().unwrap();
+
+                    // Check if the subject is of the same type as 'self' in this scope
+                    let is_self = match &frame.zelf {
+                        Some(zelf) => zelf.is_of_same_type(subject),
+                        None => false,
+                    };
+
+                    if !subject.satisfies(&program.patterns[*pattern], is_self, program) {
+                        frame.ip = *target;
+                    }
+                }
+                Instruction::Fail(message) => return Err(self.error(message.clone(), None)),
+                Instruction::Return => {
+                    let result = self.stack.pop().unwrap();
+
+                    if let Some(result) = self.finish_call(result, depth) {
+                        return Ok(result);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Pops the current frame and passes on its result. Returns the result if the frame was the
+    /// one `execute` was running.
+    fn finish_call(&mut self, result: Rc<Instance>, depth: usize) -> Option<Rc<Instance>> {
+        let frame = self.frames.pop().unwrap();
+        self.stack.truncate(frame.bottom);
+
+        if self.frames.len() < depth {
+            return Some(result);
+        }
+
+        self.stack.push(result);
+        None
+    }
+
+    /// Calls a trait on the subject below its inputs on the stack.
+    /// Returns the result if the call was completed right away, or pushes a new frame otherwise.
+    /// A tail call replaces the current frame instead, or leaves it to the caller to return the
+    /// result.
+    fn call_trait(
+        &mut self,
+        trayt: TraitId,
+        site: Option<usize>,
+        tail: bool,
+    ) -> RResult<Option<Rc<Instance>>> {
+        let info = &self.program.traits[trayt];
+        let subject_index = self.stack.len() - info.inputs.len() - 1;
+        let subject = Rc::clone(&self.stack[subject_index]);
+
+        let pending_frame = |vm: &Self| {
+            site.map(|site| Frame {
+                name: info.name.clone(),
+                receiver: Some(vm.type_name(&subject)),
+                span: vm.program.call_sites[site],
+                omitted_tail_calls: 0,
+            })
+        };
+
+        match subject.as_ref() {
+            Instance::Object(object) => match self.program.classes[object.class].vtable[trayt] {
+                Some(function) => {
+                    let zelf = Some(Rc::clone(&subject));
+
+                    match (tail, site) {
+                        (true, Some(site)) => {
+                            self.replace_frame(function, zelf, site, subject_index)
+                        }
+                        _ => self.push_frame(function, zelf, site, subject_index + 1)?,
+                    }
+                    Ok(None)
+                }
+                None => {
+                    let message = RuntimeErrorMessage::UndefinedTrait(info.name.clone());
+                    Err(self.error(message, pending_frame(self)))
+                }
+            },
+            Instance::Raw(value) => {
+                let result = match info.raw_op {
+                    Some(op) => {
+                        let rhs = self.stack.get(subject_index + 1).map(|rhs| rhs.as_ref());
+                        raw_operation(value, op, rhs)
+                    }
+                    None => runtime_error(RuntimeErrorMessage::UnsupportedOperation(
+                        info.name.clone(),
+                        type_name(value),
+                    )),
+                };
+
+                match result {
+                    Ok(result) => {
+                        self.stack.truncate(subject_index);
+                        Ok(Some(Rc::new(Instance::Raw(result))))
+                    }
+                    Err(error) => Err(self.error(error.message, pending_frame(self))),
+                }
+            }
+            Instance::Void => {
+                let message = RuntimeErrorMessage::CallOnVoid(info.name.clone());
+                Err(self.error(message, pending_frame(self)))
+            }
+        }
+    }
+
+    /// Pushes a frame whose parameters are already on the stack, starting at the base.
+    fn push_frame(
+        &mut self,
+        function: FunctionId,
+        zelf: Option<Rc<Instance>>,
+        site: Option<usize>,
+        base: usize,
+    ) -> RResult<()> {
+        let bottom = if zelf.is_some() { base - 1 } else { base };
+
+        self.frames.push(CallFrame {
+            function,
+            ip: 0,
+            base,
+            bottom,
+            zelf,
+            site,
+            tail_calls: vec![],
+            omitted_tail_calls: 0,
+        });
+
+        if self.frames.len() > self.limits.max_depth {
+            let message = RuntimeErrorMessage::RecursionLimitExceeded(self.limits.max_depth);
+            return Err(self.error(message, None));
+        }
+
+        self.reserve_locals(function);
+
+        Ok(())
+    }
+
+    /// Replaces the current frame with a call to the given function. The subject, if any, and
+    /// the parameters are on top of the stack, starting at the given index.
+    fn replace_frame(
+        &mut self,
+        function: FunctionId,
+        zelf: Option<Rc<Instance>>,
+        site: usize,
+        start: usize,
+    ) {
+        let frame = self.frames.last_mut().unwrap();
+
+        // Keep track of the replaced call for tracebacks.
+        if let Some(site) = frame.site {
+            if frame.tail_calls.len() < TAIL_TRACE_LIMIT {
+                frame.tail_calls.push(TailCall {
+                    function: frame.function,
+                    receiver: frame.zelf.as_deref().and_then(Instance::class),
+                    site,
+                });
+            } else {
+                frame.omitted_tail_calls += 1;
+            }
+        }
+
+        // Move the subject and parameters into the place of the replaced frame.
+        self.stack.drain(frame.bottom..start);
+
+        frame.function = function;
+        frame.ip = 0;
+        frame.base = frame.bottom + usize::from(zelf.is_some());
+        frame.zelf = zelf;
+        frame.site = Some(site);
+
+        self.reserve_locals(function);
+    }
+
+    /// Reserves the slots of locals that aren't parameters.
+    fn reserve_locals(&mut self, function: FunctionId) {
+        let function = &self.program.functions[function];
+        for _ in function.params..function.locals {
+            self.stack.push(Rc::new(Instance::Void));
+        }
+    }
+
+    /// Consumes one evaluation step.
+    fn step(&mut self) -> RResult<()> {
+        self.steps += 1;
+
+        match self.limits.fuel {
+            Some(fuel) if self.steps > fuel => {
+                Err(self.error(RuntimeErrorMessage::StepBudgetExhausted(fuel), None))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn to_bool(&self, instance: &Instance) -> RResult<bool> {
+        let value = match instance {
+            Instance::Object(object) => self.program.classes[object.class]
+                .slots
+                .iter()
+                .position(|slot| slot == "value")
+                .map(|slot| object.slots[slot].as_ref()),
+            instance => Some(instance),
+        };
+
+        match value {
+            Some(Instance::Raw(RawValue::Bool(value))) => Ok(*value),
+            _ => Err(self.error(RuntimeErrorMessage::NotABool, None)),
+        }
+    }
+
+    /// The name of the class, struct or raw type of an instance.
+    fn type_name(&self, instance: &Instance) -> String {
+        match instance {
+            Instance::Object(object) => self.program.classes[object.class].name.clone(),
+            Instance::Raw(value) => type_name(value).to_string(),
+            Instance::Void => "?".to_string(),
+        }
+    }
+
+    /// Creates an error with a snapshot of the call stack, with an optional frame for a call
+    /// that failed before it could be entered.
+    fn error(&self, message: RuntimeErrorMessage, pending_frame: Option<Frame>) -> RuntimeError {
+        let mut trace = vec![];
+
+        for frame in &self.frames {
+            let Some(site) = frame.site else {
+                continue;
+            };
+
+            for call in &frame.tail_calls {
+                trace.push(Frame {
+                    name: self.program.functions[call.function].name.clone(),
+                    receiver: call
+                        .receiver
+                        .map(|class| self.program.classes[class].name.clone()),
+                    span: self.program.call_sites[call.site],
+                    omitted_tail_calls: 0,
+                });
+            }
+
+            trace.push(Frame {
+                name: self.program.functions[frame.function].name.clone(),
+                receiver: frame.zelf.as_ref().map(|zelf| self.type_name(zelf)),
+                span: self.program.call_sites[site],
+                omitted_tail_calls: frame.omitted_tail_calls,
+            });
+        }
+        trace.extend(pending_frame);
+
+        let (callee, context) = match trace.last() {
+            Some(frame) => (Some(frame.name.clone()), Some(frame.span)),
+            None => (None, None),
+        };
+
+        RuntimeError {
+            message,
+            callee,
+            context,
+            trace,
+        }
+    }
+}
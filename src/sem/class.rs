@@ -2,13 +2,15 @@ use std::cell::RefCell;
 use std::collections::HashMap;
 
 use crate::ast::module_statement::ModuleStatement;
-use crate::error::CResult;
+use crate::ast::Statement;
+use crate::error::{CResult, CompilationError};
 use crate::sem::evaluation::Evaluation;
 use crate::sem::lett::Let;
 use crate::sem::semantic_analyser::{SemanticContext, SemanticScope};
 use crate::sem::trayt::{interface_type, Trait};
 use crate::sem::typ::{combine_types, Type};
 use crate::sem::type_coercion::coerce_type;
+use crate::source::Span;
 use std::rc::Rc;
 use std::string::String;
 use std::sync::atomic::{AtomicUsize, Ordering};
@@ -18,6 +20,7 @@ static ID: AtomicUsize = AtomicUsize::new(0);
 // A class has a set of dependencies of certain types, and a set of trait definitions.
 #[derive(Debug)]
 pub struct Class {
+    pub name: String,
     pub dependencies: Vec<(String, Type)>,
     pub definitions: Vec<(Rc<RefCell<Trait>>, Evaluation)>,
     id: usize,
@@ -41,7 +44,23 @@ impl Class {
         Ok(inputs)
     }
 
-    pub fn analyse(module_statement: &ModuleStatement, context: &SemanticContext) -> CResult<Self> {
+    /// Where the types of the dependencies were declared.
+    pub fn constructor_input_contexts(module_statement: &ModuleStatement) -> Vec<(String, Span)> {
+        let dependencies = &module_statement.class.as_ref().unwrap().dependencies;
+
+        dependencies
+            .iter()
+            .map(|(name, type_statement)| (name.clone(), type_statement.span()))
+            .collect()
+    }
+
+    /// Analyses the definitions. Failing definitions are left out and their errors are added to
+    /// the given errors.
+    pub fn analyse(
+        module_statement: &ModuleStatement,
+        context: &SemanticContext,
+        errors: &mut Vec<CompilationError>,
+    ) -> CResult<Self> {
         let dependencies = Self::constructor_inputs(module_statement, context)?;
 
         let path = &module_statement.name;
@@ -59,7 +78,10 @@ impl Class {
 
         let mut definitions = vec![];
         for def_statement in module_statement.defs.iter() {
-            let trayt = context.traits.resolve(&def_statement.name, path)?;
+            // Defs of unknown traits have been reported during the analysis of the interface.
+            let Ok(trayt) = context.traits.resolve(&def_statement.name, path) else {
+                continue;
+            };
 
             used_interfaces.push(trayt.borrow().interface.clone());
 
@@ -68,10 +90,17 @@ impl Class {
                 .into_iter()
                 .collect();
 
-            let mut evaluation = Evaluation::analyse(def_statement.expr.clone(), &scope)?;
-            coerce_type(&trayt.borrow().output, &mut evaluation, &scope)?;
+            let evaluation = Evaluation::analyse(def_statement.expr.clone(), &scope).and_then(
+                |mut evaluation| {
+                    coerce_type(&trayt.borrow().output, &mut evaluation, &scope)?;
+                    Ok(evaluation)
+                },
+            );
 
-            definitions.push((trayt, evaluation));
+            match evaluation {
+                Ok(evaluation) => definitions.push((trayt, evaluation)),
+                Err(error) => errors.push(error),
+            }
         }
 
         // TODO: use interface instead of going through definitions and used interfaces
@@ -90,6 +119,7 @@ impl Class {
         }
 
         let class = Class {
+            name: module_statement.name.clone(),
             dependencies,
             definitions,
             id: ID.fetch_add(1, Ordering::SeqCst),
@@ -100,7 +130,9 @@ impl Class {
 
     pub fn constructor(self) -> Let {
         Let {
+            name: self.name.clone(),
             inputs: self.dependencies.clone(),
+            input_contexts: vec![],
             output: self.interface(),
             evaluation: Evaluation::ClassConstructor(Rc::new(self)),
         }
@@ -5,16 +5,19 @@ use crate::ast::raw_value::RawValue;
 use crate::ast::type_statement::RawType;
 use crate::ast::Statement;
 use crate::error::ErrorMessage::NoResolution;
-use crate::error::{error, CResult, ErrorMessage, CompilationError};
+use crate::error::{error, CResult, CompilationError, ErrorMessage};
 use crate::sem::class::Class;
 use crate::sem::lett::Let;
 use crate::sem::semantic_analyser::SemanticScope;
 use crate::sem::strukt::Struct;
+use crate::sem::suggestion::{closest, did_you_mean};
 use crate::sem::table::Table;
 use crate::sem::trayt::Trait;
 use crate::sem::typ::{combine_types, Type};
 use crate::sem::type_checking::check_types;
 use crate::sem::type_coercion::{coerce_type, coerce_types};
+use crate::source::Span;
+use std::collections::BTreeSet;
 use std::rc::Rc;
 
 /// A semantically analysed expression that can be evaluated.
@@ -38,6 +41,7 @@ pub enum Evaluation {
 pub struct LetEvaluation {
     pub lett: Rc<RefCell<Let>>,
     pub inputs: Vec<(String, Evaluation)>,
+    pub span: Span,
 }
 
 #[derive(Clone, Debug)]
@@ -45,6 +49,7 @@ pub struct TraitEvaluation {
     pub trayt: Rc<RefCell<Trait>>,
     pub subject: Box<Evaluation>,
     pub inputs: Vec<(String, Evaluation)>,
+    pub span: Span,
 }
 
 #[derive(Clone, Debug)]
@@ -62,9 +67,18 @@ pub struct IfElseEvaluation {
 }
 
 impl Evaluation {
+    /// Where the code this evaluation was analysed from is, if it is a call.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Evaluation::Let(call) => Some(call.span),
+            Evaluation::Trait(call) => Some(call.span),
+            _ => None,
+        }
+    }
+
     pub fn analyse(statement: ExpressionStatement, scope: &SemanticScope) -> CResult<Self> {
-        let error_context = statement.error_context();
-        let err_mapper = |e: CompilationError| e.context(error_context.clone());
+        let span = statement.span();
+        let err_mapper = |e: CompilationError| e.context(span);
 
         let eval = match statement.expression {
             Expression::Binary(call) => {
@@ -76,10 +90,16 @@ impl Evaluation {
                     BinaryOp::Eq => "Op\\Eq",
                     BinaryOp::Lt => "Op\\Lt",
                     BinaryOp::Gt => "Op\\Gt",
+                    BinaryOp::Ne => "Op\\Ne",
+                    BinaryOp::Lte => "Op\\Lte",
+                    BinaryOp::Gte => "Op\\Gte",
                     BinaryOp::And => "Op\\And",
                     BinaryOp::Or => "Op\\Or",
                 };
-                let trayt = scope.context.traits.resolve(trait_path, "")
+                let trayt = scope
+                    .context
+                    .traits
+                    .resolve(trait_path, "")
                     .map_err(err_mapper)?;
 
                 let mut lhs = Evaluation::analyse(*call.lhs, scope)?;
@@ -106,6 +126,7 @@ impl Evaluation {
                     trayt,
                     subject: Box::new(lhs),
                     inputs,
+                    span,
                 })
             }
             Expression::Unary(call) => {
@@ -113,7 +134,10 @@ impl Evaluation {
                     UnaryOp::Neg => "Op\\Neg",
                     UnaryOp::Not => "Op\\Not",
                 };
-                let trayt = scope.context.traits.resolve(trait_path, "")
+                let trayt = scope
+                    .context
+                    .traits
+                    .resolve(trait_path, "")
                     .map_err(err_mapper)?;
 
                 let subject = Evaluation::analyse(*call.subject, scope)?;
@@ -122,20 +146,32 @@ impl Evaluation {
                     trayt,
                     subject: Box::new(subject),
                     inputs: vec![],
+                    span,
                 })
             }
             Expression::Def(call) => {
                 let subject = Evaluation::analyse(*call.subject, scope)?;
 
                 // Make a temporary trait table using only traits defined on the subject.
+                let callable_traits = subject.typ(scope)?.callable_traits(scope);
                 let mut trait_name_table = Table::new("Trait");
-                for trait_name in subject.typ(scope)?.callable_traits(scope).into_iter() {
-                    trait_name_table.declare(&trait_name, trait_name.clone())?;
+                for trait_name in callable_traits.iter() {
+                    trait_name_table.declare(trait_name, trait_name.clone())?;
                 }
-                let trait_name = trait_name_table.resolve(&call.name, "")
+                let trait_name = trait_name_table
+                    .resolve(&call.name, "")
+                    .map_err(|error| list_callable_traits(error, &callable_traits))
                     .map_err(err_mapper)?;
 
-                let trayt = scope.context.traits.resolve(&trait_name, "")
+                let trayt = scope
+                    .context
+                    .traits
+                    .resolve(&trait_name, "")
+                    .map_err(err_mapper)?;
+                scope
+                    .context
+                    .traits
+                    .check_visible(&trayt, scope.path, span.file)
                     .map_err(err_mapper)?;
 
                 // Resolve all 'Self' types within input types with the current subject.
@@ -163,10 +199,19 @@ impl Evaluation {
                     trayt,
                     subject: Box::new(subject),
                     inputs,
+                    span,
                 })
             }
             Expression::Let(call) => {
-                let lett = scope.context.lets.resolve(&call.name, scope.path)
+                let lett = scope
+                    .context
+                    .lets
+                    .resolve(&call.name, scope.path)
+                    .map_err(err_mapper)?;
+                scope
+                    .context
+                    .lets
+                    .check_visible(&lett, scope.path, span.file)
                     .map_err(err_mapper)?;
 
                 let mut inputs = vec![];
@@ -177,14 +222,18 @@ impl Evaluation {
                 }
 
                 coerce_types(&lett.borrow().inputs, &mut inputs, scope).map_err(err_mapper)?;
-                check_types(&lett.borrow().inputs, &inputs, scope).map_err(err_mapper)?;
+                check_types(&lett.borrow().inputs, &inputs, scope)
+                    .map_err(|error| label_input_declaration(error, &lett.borrow()))
+                    .map_err(err_mapper)?;
 
-                Evaluation::Let(LetEvaluation { lett, inputs })
+                Evaluation::Let(LetEvaluation { lett, inputs, span })
             }
             Expression::Literal(value) => Evaluation::Literal(value),
             Expression::Local(ref name) => {
                 if !scope.locals.contains_key(name) {
-                    return statement.error(NoResolution("local variable", name.clone()));
+                    return statement
+                        .error(NoResolution("local variable", name.clone()))
+                        .map_err(|error| suggest_locals(error, name, scope));
                 }
 
                 Evaluation::Local(name.clone())
@@ -195,6 +244,7 @@ impl Evaluation {
                     None => {
                         return statement
                             .error(NoResolution("local variable", ff.local_name.clone()))
+                            .map_err(|error| suggest_locals(error, &ff.local_name, scope))
                     }
                 };
 
@@ -243,8 +293,8 @@ impl Evaluation {
                 {
                     return statement.error(ErrorMessage::TypeMismatch(
                         "if condition".to_string(),
-                        Type::Raw(RawType::Bool),
-                        condition_type,
+                        Box::new(Type::Raw(RawType::Bool)),
+                        Box::new(condition_type),
                     ));
                 }
 
@@ -286,12 +336,18 @@ impl Evaluation {
                 }
             }
             Evaluation::Literal(raw_value) => Type::Raw(raw_value.into()),
-            Evaluation::Local(name) => scope.locals.get(name).unwrap().clone(),
-            Evaluation::FriendlyField(ff) => scope
-                .locals
-                .get(&format!("{}.{}", ff.local_name, ff.field_name))
-                .unwrap()
-                .clone(),
+            Evaluation::Local(name) => match scope.locals.get(name) {
+                Some(typ) => typ.clone(),
+                None => return error(NoResolution("local variable", name.clone())),
+            },
+            Evaluation::FriendlyField(ff) => {
+                let name = format!("{}.{}", ff.local_name, ff.field_name);
+
+                match scope.locals.get(&name) {
+                    Some(typ) => typ.clone(),
+                    None => return error(NoResolution("field", name)),
+                }
+            }
             Evaluation::Match(call) => {
                 let mut types = vec![];
                 for (typ, branch) in &call.branches {
@@ -328,6 +384,55 @@ impl Evaluation {
 }
 
 /// Replace self types with specific type.
+/// Suggests locals in the scope with a name similar to the missing one.
+fn suggest_locals(error: CompilationError, name: &str, scope: &SemanticScope) -> CompilationError {
+    let suggestions = closest(name, scope.locals.keys().map(String::as_str));
+
+    match did_you_mean(&suggestions) {
+        Some(help) => error.help(&help),
+        None => error,
+    }
+}
+
+/// Lists the traits which can be called on the subject of a failed trait call.
+fn list_callable_traits(
+    error: CompilationError,
+    callable_traits: &BTreeSet<String>,
+) -> CompilationError {
+    if callable_traits.is_empty() {
+        return error.help("no traits can be called on this type");
+    }
+
+    let traits: Vec<String> = callable_traits
+        .iter()
+        .map(|trait_name| format!("'{}'", trait_name))
+        .collect();
+
+    error.help(&format!(
+        "the traits which can be called on this type are {}",
+        traits.join(", ")
+    ))
+}
+
+/// Points a type mismatch of an input at the place where the type of that input was declared.
+fn label_input_declaration(error: CompilationError, lett: &Let) -> CompilationError {
+    let ErrorMessage::TypeMismatch(name, _, _) = &error.message else {
+        return error;
+    };
+
+    match lett
+        .input_contexts
+        .iter()
+        .find(|(input_name, _)| input_name == name)
+    {
+        Some((_, context)) => {
+            let context = *context;
+            error.label(context, "expected type declared here")
+        }
+        None => error,
+    }
+}
+
 fn resolve_self_types(typ: Type, self_type: &Type) -> Type {
     match typ {
         Type::Zelf => self_type.clone(),
@@ -346,7 +451,8 @@ fn resolve_self_types(typ: Type, self_type: &Type) -> Type {
 pub fn raw_operation_output_type(input: &RawType, trayt: &str) -> CResult<RawType> {
     let typ = match trayt {
         "Op\\Add" | "Op\\Sub" | "Op\\Mul" | "Op\\Div" | "Op\\Neg" => *input,
-        "Op\\Eq" | "Op\\Lt" | "Op\\Gt" | "Op\\And" | "Op\\Or" => RawType::Bool,
+        "Op\\Eq" | "Op\\Lt" | "Op\\Gt" | "Op\\Ne" | "Op\\Lte" | "Op\\Gte" => RawType::Bool,
+        "Op\\Not" | "Op\\And" | "Op\\Or" => RawType::Bool,
         "String" => RawType::String,
         _ => return error(ErrorMessage::UndefinedTrait(trayt.to_string())),
     };
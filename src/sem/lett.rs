@@ -1,16 +1,22 @@
 use crate::sem::typ::Type;
 
 use crate::ast::let_statement::LetStatement;
+use crate::ast::Statement;
 use crate::error::CResult;
 use crate::sem::evaluation::Evaluation;
 use crate::sem::semantic_analyser::{SemanticContext, SemanticScope};
+use crate::sem::table::parent;
 
 use crate::sem::type_coercion::coerce_type;
+use crate::source::Span;
 
 // A 'let' defines a constant instance or a function.
 #[derive(Debug)]
 pub struct Let {
+    pub name: String,
     pub inputs: Vec<(String, Type)>,
+    /// Where the types of the inputs were declared, if they were declared in code.
+    pub input_contexts: Vec<(String, Span)>,
     pub output: Type,
     pub evaluation: Evaluation,
 }
@@ -23,16 +29,27 @@ impl Let {
         path: &str,
     ) -> CResult<Self> {
         let mut inputs = vec![];
+        let mut input_contexts = vec![];
         for (param_name, type_statement) in statement.parameters.iter() {
             let typ = Type::analyse(type_statement, context, path)?;
 
             inputs.push((param_name.clone(), typ));
+            input_contexts.push((param_name.clone(), type_statement.span()));
         }
 
         let output = Type::analyse(&statement.output, context, path)?;
 
+        // Global lets in a namespace are named in full already, and analysed within the namespace.
+        let name = if path.is_empty() || parent(&statement.name) == path {
+            statement.name.clone()
+        } else {
+            format!("{}\\{}", path, statement.name)
+        };
+
         let lett = Let {
+            name,
             inputs,
+            input_contexts,
             output,
             evaluation: Evaluation::Zelf,
         };
@@ -60,7 +77,9 @@ impl Let {
         // check_type_fits(&evaluation.typ(&scope)?, &lett.output, &statement.name)?;
 
         let lett = Let {
+            name: lett.name,
             inputs: lett.inputs,
+            input_contexts: lett.input_contexts,
             output: lett.output,
             evaluation,
         };
@@ -3,6 +3,7 @@ pub(crate) mod evaluation;
 pub(crate) mod lett;
 pub(crate) mod semantic_analyser;
 pub(crate) mod strukt;
+pub(crate) mod suggestion;
 pub(crate) mod table;
 pub(crate) mod trayt;
 pub(crate) mod typ;
@@ -1,14 +1,15 @@
 use crate::ast::abstract_syntax_tree::AbstractSyntaxTree;
-use crate::error::CResult;
+use crate::ast::Statement;
+use crate::error::{CResult, CompilationError};
 use crate::sem::class::Class;
 use crate::sem::evaluation::Evaluation;
 use crate::sem::lett::Let;
 use crate::sem::strukt::Struct;
-use crate::sem::table::Table;
+use crate::sem::table::{parent, Table};
 use crate::sem::trayt::{interface_type, Interface, Trait};
 use crate::sem::typ::Type;
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 
 /// All available symbols in a program
@@ -38,18 +39,50 @@ pub struct SemanticScope<'a> {
 }
 
 /// Analyses the semantics of a complete AST, and returns the global semantic context.
-pub fn analyse_ast(ast: AbstractSyntaxTree) -> CResult<SemanticContext> {
+/// Analysis continues past failing lets and defs, to return all of their errors at once.
+pub fn analyse_ast(ast: AbstractSyntaxTree) -> Result<SemanticContext, Vec<CompilationError>> {
+    let mut errors = vec![];
+
+    match analyse_program(&ast, &mut errors) {
+        Ok(context) if errors.is_empty() => Ok(context),
+        Ok(_) => Err(errors),
+        Err(error) => {
+            errors.push(error);
+            Err(errors)
+        }
+    }
+}
+
+/// Returns errors that can't be recovered from, and adds the others to the given errors.
+fn analyse_program(
+    ast: &AbstractSyntaxTree,
+    errors: &mut Vec<CompilationError>,
+) -> CResult<SemanticContext> {
     let mut context = SemanticContext::new();
+    // Lets whose types couldn't be analysed, which are left out of further analysis.
+    let mut failed_lets = HashSet::new();
 
     // ==========================================================================================
     // STEP 1: Populate trait and interface identifiers.
     // ==========================================================================================
 
     // Populate trait and interface identifiers.
-    for module in ast.mods.iter() {
+    for module in &ast.mods {
         let dummy_interface = context
             .interfaces
-            .declare(&module.name, RefCell::new(vec![]))?;
+            .declare(&module.name, RefCell::new(vec![]))
+            .map_err(|error| {
+                // The first module with the name is the one that was declared before.
+                let first = ast
+                    .mods
+                    .iter()
+                    .find(|other| other.name == module.name)
+                    .unwrap_or(module);
+
+                error
+                    .context(module.span())
+                    .label(first.span(), "first declared here")
+            })?;
 
         for trait_statement in module.traits.iter() {
             let name = format!("{}\\{}", module.name, trait_statement.name);
@@ -57,6 +90,10 @@ pub fn analyse_ast(ast: AbstractSyntaxTree) -> CResult<SemanticContext> {
             context
                 .traits
                 .declare(&name, RefCell::new(Trait::dummy(&name, &dummy_interface)))?;
+
+            if trait_statement.private {
+                context.traits.make_private(&name, trait_statement.span());
+            }
         }
 
         // Each module has an eponymous trait, which has the module interface as output type.
@@ -64,6 +101,13 @@ pub fn analyse_ast(ast: AbstractSyntaxTree) -> CResult<SemanticContext> {
             &module.name,
             RefCell::new(Trait::dummy(&module.name, &dummy_interface)),
         )?;
+
+        // A private module hides its type and its constructor.
+        if module.private {
+            context.interfaces.make_private(&module.name, module.span());
+            context.traits.make_private(&module.name, module.span());
+            context.lets.make_private(&module.name, module.span());
+        }
     }
 
     // Fill module interfaces, made up of the module's own traits and def traits from other modules.
@@ -82,9 +126,21 @@ pub fn analyse_ast(ast: AbstractSyntaxTree) -> CResult<SemanticContext> {
 
         // Traits added on from other modules through defs.
         for def in &module.defs {
-            let trayt = context.traits.resolve(&def.name, &module.name)?;
-
-            interface.push(trayt);
+            let resolved = context
+                .traits
+                .resolve(&def.name, &module.name)
+                .and_then(|trayt| {
+                    context
+                        .traits
+                        .check_visible(&trayt, &module.name, def.span().file)?;
+                    Ok(trayt)
+                });
+
+            match resolved {
+                Ok(trayt) => interface.push(trayt),
+                // Defs of unknown traits are left out from here on.
+                Err(error) => errors.push(error.context(def.span())),
+            }
         }
 
         // Traits added on through using statements.
@@ -129,7 +185,9 @@ pub fn analyse_ast(ast: AbstractSyntaxTree) -> CResult<SemanticContext> {
             let mut related_interfaces = vec![];
 
             for def in &module.defs {
-                let trayt = context.traits.resolve(&def.name, &module.name)?;
+                let Ok(trayt) = context.traits.resolve(&def.name, &module.name) else {
+                    continue;
+                };
 
                 related_interfaces.push(Rc::clone(&trayt.borrow().interface));
             }
@@ -163,11 +221,22 @@ pub fn analyse_ast(ast: AbstractSyntaxTree) -> CResult<SemanticContext> {
 
     // Populate global let identifiers and types.
     for let_statement in &ast.lets {
-        let lett = Let::analyse_just_types(let_statement, &context, "")?;
-
-        context
-            .lets
-            .declare(&let_statement.name, RefCell::new(lett))?;
+        let scope = parent(&let_statement.name);
+        let declared = Let::analyse_just_types(let_statement, &context, scope).and_then(|lett| {
+            context
+                .lets
+                .declare(&let_statement.name, RefCell::new(lett))
+                .map_err(|error| error.context(let_statement.span()))
+        });
+
+        if let Err(error) = declared {
+            errors.push(error);
+            failed_lets.insert(let_statement.name.clone());
+        } else if let_statement.private {
+            context
+                .lets
+                .make_private(&let_statement.name, let_statement.span());
+        }
     }
 
     for module in &ast.mods {
@@ -185,16 +254,29 @@ pub fn analyse_ast(ast: AbstractSyntaxTree) -> CResult<SemanticContext> {
         for let_statement in &module.lets {
             let name = format!("{}\\{}", module.name, let_statement.name);
 
-            let lett = Let::analyse_just_types(let_statement, &context, &module.name)?;
-
-            context.lets.declare(&name, RefCell::new(lett))?;
+            let declared =
+                Let::analyse_just_types(let_statement, &context, &module.name).and_then(|lett| {
+                    context
+                        .lets
+                        .declare(&name, RefCell::new(lett))
+                        .map_err(|error| error.context(let_statement.span()))
+                });
+
+            if let Err(error) = declared {
+                errors.push(error);
+                failed_lets.insert(name);
+            } else if let_statement.private {
+                context.lets.make_private(&name, let_statement.span());
+            }
         }
 
         // Populate struct and class constructor and def identifiers.
         if let Some(struct_statement) = &module.strukt {
             // Just the inputs and output of the constructor.
             let constructor = Let {
+                name: module.name.clone(),
                 inputs: Struct::constructor_inputs(struct_statement),
+                input_contexts: vec![],
                 output: interface_type(
                     context
                         .interfaces
@@ -211,7 +293,9 @@ pub fn analyse_ast(ast: AbstractSyntaxTree) -> CResult<SemanticContext> {
         } else if module.class.is_some() {
             // Just the inputs and output of the constructor.
             let constructor = Let {
+                name: module.name.clone(),
                 inputs: Class::constructor_inputs(module, &context)?,
+                input_contexts: Class::constructor_input_contexts(module),
                 output: interface_type(
                     context
                         .interfaces
@@ -236,47 +320,67 @@ pub fn analyse_ast(ast: AbstractSyntaxTree) -> CResult<SemanticContext> {
 
     // Analyse global let expressions.
     for let_statement in &ast.lets {
-        let lett = Let::analyse(let_statement, &context, "")?;
+        if failed_lets.contains(&let_statement.name) {
+            continue;
+        }
 
-        context.lets.resolve(&let_statement.name, "")?.replace(lett);
+        match Let::analyse(let_statement, &context, parent(&let_statement.name)) {
+            Ok(lett) => {
+                context.lets.resolve(&let_statement.name, "")?.replace(lett);
+            }
+            Err(error) => errors.push(error),
+        }
     }
 
     for module in &ast.mods {
         // Analyse module let expressions.
         for let_statement in &module.lets {
-            let lett = Let::analyse(let_statement, &context, &module.name)?;
+            if failed_lets.contains(&format!("{}\\{}", module.name, let_statement.name)) {
+                continue;
+            }
 
-            context
-                .lets
-                .resolve(&let_statement.name, &module.name)?
-                .replace(lett);
+            match Let::analyse(let_statement, &context, &module.name) {
+                Ok(lett) => {
+                    context
+                        .lets
+                        .resolve(&let_statement.name, &module.name)?
+                        .replace(lett);
+                }
+                Err(error) => errors.push(error),
+            }
         }
 
         // Re-analyse traits with default definitions.
         for trait_statement in &module.traits {
-            let trayt = Trait::analyse(trait_statement, module, &context, true)?;
-
-            context
-                .traits
-                .resolve(&trait_statement.name, &module.name)?
-                .replace(trayt);
+            match Trait::analyse(trait_statement, module, &context, true) {
+                Ok(trayt) => {
+                    context
+                        .traits
+                        .resolve(&trait_statement.name, &module.name)?
+                        .replace(trayt);
+                }
+                Err(error) => errors.push(error),
+            }
         }
 
         // Analyse struct and class constructor and def expressions.
-        if module.strukt.is_some() {
-            let strukt = Struct::analyse(module, &context)?;
-
-            context
-                .lets
-                .resolve(&module.name, "")?
-                .replace(Rc::new(strukt).constructor());
+        let constructor = if module.strukt.is_some() {
+            Some(Rc::new(Struct::analyse(module, &context, errors)?).constructor())
         } else if module.class.is_some() {
-            let class = Class::analyse(module, &context)?;
+            Some(Class::analyse(module, &context, errors)?.constructor())
+        } else {
+            None
+        };
 
-            context
-                .lets
-                .resolve(&module.name, "")?
-                .replace(class.constructor());
+        if let Some(mut constructor) = constructor {
+            let lett = context.lets.resolve(&module.name, "")?;
+
+            // Keep the module interface as output type, which is what the expressions analysed
+            // so far have been checked against.
+            constructor.output = lett.borrow().output.clone();
+            constructor.input_contexts = lett.borrow().input_contexts.clone();
+
+            lett.replace(constructor);
         }
     }
 
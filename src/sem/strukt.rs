@@ -1,7 +1,7 @@
 use crate::ast::module_statement::ModuleStatement;
 use crate::ast::struct_statement::StructStatement;
 use crate::ast::type_statement::RawType;
-use crate::error::CResult;
+use crate::error::{CResult, CompilationError};
 use crate::sem::evaluation::Evaluation;
 use crate::sem::lett::Let;
 use crate::sem::semantic_analyser::{SemanticContext, SemanticScope};
@@ -18,6 +18,7 @@ static ID: AtomicUsize = AtomicUsize::new(0);
 /// A struct has a set of fields which are of raw types, and a set of trait definitions.
 #[derive(Debug)]
 pub struct Struct {
+    pub name: String,
     pub fields: Vec<(String, RawType)>,
     pub definitions: Vec<(Rc<RefCell<Trait>>, Evaluation)>,
     id: usize,
@@ -32,7 +33,13 @@ impl Struct {
             .collect()
     }
 
-    pub fn analyse(module_statement: &ModuleStatement, context: &SemanticContext) -> CResult<Self> {
+    /// Analyses the definitions. Failing definitions are left out and their errors are added to
+    /// the given errors.
+    pub fn analyse(
+        module_statement: &ModuleStatement,
+        context: &SemanticContext,
+        errors: &mut Vec<CompilationError>,
+    ) -> CResult<Self> {
         let struct_statement = module_statement.strukt.as_ref().unwrap();
 
         let constructor_inputs = Self::constructor_inputs(struct_statement);
@@ -52,7 +59,10 @@ impl Struct {
 
         let mut definitions = vec![];
         for def_statement in module_statement.defs.iter() {
-            let trayt = context.traits.resolve(&def_statement.name, path)?;
+            // Defs of unknown traits have been reported during the analysis of the interface.
+            let Ok(trayt) = context.traits.resolve(&def_statement.name, path) else {
+                continue;
+            };
 
             used_interfaces.push(trayt.as_ref().borrow().interface.clone());
 
@@ -76,9 +86,10 @@ impl Struct {
                 scope.locals.insert(friendly_local.0, friendly_local.1);
             }
 
-            let evaluation = Evaluation::analyse(def_statement.expr.clone(), &scope)?;
-
-            definitions.push((trayt, evaluation));
+            match Evaluation::analyse(def_statement.expr.clone(), &scope) {
+                Ok(evaluation) => definitions.push((trayt, evaluation)),
+                Err(error) => errors.push(error),
+            }
         }
 
         // Add automatic definitions from other modules.
@@ -96,6 +107,7 @@ impl Struct {
         }
 
         let strukt = Struct {
+            name: module_statement.name.clone(),
             fields: struct_statement.fields.clone(),
             definitions,
             id: ID.fetch_add(1, Ordering::SeqCst),
@@ -112,7 +124,9 @@ impl Struct {
             .collect();
 
         Let {
+            name: self.name.clone(),
             inputs,
+            input_contexts: vec![],
             output: self.interface(),
             evaluation: Evaluation::StructConstructor(Rc::clone(self)),
         }
@@ -0,0 +1,113 @@
+const MAX_SUGGESTIONS: usize = 3;
+
+/// The number of single character insertions, deletions, substitutions and swaps of adjacent
+/// characters needed to turn one string into the other.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    // distances[i][j] is the distance between the first i characters of a and j characters of b.
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    distances[0] = (0..=b.len()).collect();
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let substitution = distances[i - 1][j - 1] + usize::from(a[i - 1] != b[j - 1]);
+            let insertion = distances[i][j - 1] + 1;
+            let deletion = distances[i - 1][j] + 1;
+
+            let mut distance = substitution.min(insertion).min(deletion);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+
+            distances[i][j] = distance;
+        }
+    }
+
+    distances[a.len()][b.len()]
+}
+
+/// The edit distance between the names, ignoring case.
+pub fn name_distance(a: &str, b: &str) -> usize {
+    edit_distance(&a.to_lowercase(), &b.to_lowercase())
+}
+
+/// Whether the candidate is close enough to the name to be a likely typo of it.
+pub fn is_similar(name: &str, candidate: &str) -> bool {
+    let max_distance = (name.chars().count() / 3).max(1);
+
+    name_distance(name, candidate) <= max_distance
+}
+
+/// The names most similar to the given name, closest first.
+pub fn closest<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut similar: Vec<_> = candidates
+        .into_iter()
+        .filter(|candidate| *candidate != name && is_similar(name, candidate))
+        .map(|candidate| (name_distance(name, candidate), candidate.to_string()))
+        .collect();
+
+    similar.sort();
+    similar
+        .into_iter()
+        .map(|(_, candidate)| candidate)
+        .collect()
+}
+
+/// A help message which suggests the given names, if there are any.
+pub fn did_you_mean(names: &[String]) -> Option<String> {
+    let names: Vec<String> = names
+        .iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|name| format!("'{}'", name))
+        .collect();
+
+    match names.as_slice() {
+        [] => None,
+        [name] => Some(format!("did you mean {}?", name)),
+        [names @ .., last] => Some(format!("did you mean {} or {}?", names.join(", "), last)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::sem::suggestion::{closest, did_you_mean, edit_distance};
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("Point", "Point"), 0);
+        assert_eq!(edit_distance("Pont", "Point"), 1);
+        assert_eq!(edit_distance("Point", "Paint"), 1);
+        assert_eq!(edit_distance("Sum", "Some"), 2);
+        assert_eq!(edit_distance("Itn", "Int"), 1);
+        assert_eq!(edit_distance("", "Sum"), 3);
+    }
+
+    #[test]
+    fn test_closest() {
+        let candidates = ["Paint", "Point", "Pointer", "Length"];
+
+        assert_eq!(closest("Pont", candidates), vec!["Point"]);
+        assert_eq!(closest("Pint", candidates), vec!["Paint", "Point"]);
+        assert_eq!(closest("length", candidates), vec!["Length"]);
+        assert_eq!(closest("sum", ["Sam", "SUM"]), vec!["SUM", "Sam"]);
+        assert!(closest("Width", candidates).is_empty());
+    }
+
+    #[test]
+    fn test_did_you_mean() {
+        assert_eq!(did_you_mean(&[]), None);
+        assert_eq!(
+            did_you_mean(&["Point".to_string()]),
+            Some("did you mean 'Point'?".to_string())
+        );
+        assert_eq!(
+            did_you_mean(&["A".to_string(), "B".to_string(), "C".to_string()]),
+            Some("did you mean 'A', 'B' or 'C'?".to_string())
+        );
+    }
+}
@@ -1,5 +1,8 @@
 use crate::error::{error, CResult, ErrorMessage};
+use crate::sem::suggestion::{did_you_mean, is_similar, name_distance};
+use crate::source::{FileId, Span};
 use std::cmp::max;
+use std::collections::HashMap;
 use std::rc::Rc;
 
 /// A table of references to items of a kind.
@@ -7,6 +10,8 @@ pub struct Table<T> {
     name: &'static str,
     items: Vec<(Vec<String>, Rc<T>)>,
     longest_path: usize,
+    /// The paths of the private items, with where they are declared.
+    private: HashMap<Vec<String>, Span>,
 }
 
 impl<T> Table<T> {
@@ -15,6 +20,7 @@ impl<T> Table<T> {
             name,
             items: vec![],
             longest_path: 0,
+            private: HashMap::new(),
         }
     }
 
@@ -22,8 +28,25 @@ impl<T> Table<T> {
     /// When "Add" and "Op\Add" are available, "Add" should resolve to the former.
     /// In the "Op" scope, "Add" should resolve to the latter.
     /// When only "Op\Add" is available, "Add" should resolve to that.
-    /// Only when the name is not available inside the scope, global search should be tried.
+    /// Only when the name is not available inside the scope, the enclosing scopes should be tried,
+    /// up to a global search.
     pub fn resolve(&self, name: &str, scope: &str) -> CResult<Rc<T>> {
+        match self.find(name, scope) {
+            Some(item) => Ok(item),
+            None => {
+                let suggestions = self.suggest(name, scope);
+
+                error(ErrorMessage::NoResolution(self.name, name.into())).map_err(|error| {
+                    match did_you_mean(&suggestions) {
+                        Some(help) => error.help(&help),
+                        None => error,
+                    }
+                })
+            }
+        }
+    }
+
+    fn find(&self, name: &str, scope: &str) -> Option<Rc<T>> {
         let path = [Self::path(scope), Self::path(name)].concat();
 
         // Check shortest match first, then longer ones.
@@ -34,20 +57,99 @@ impl<T> Table<T> {
                     let shortened_item_path = &item_path[start..];
 
                     if shortened_item_path == path {
-                        return Ok(Rc::clone(item));
+                        return Some(Rc::clone(item));
                     }
                 }
             }
         }
 
         if !scope.is_empty() {
-            // Retry without a scope.
-            self.resolve(name, "")
+            // Retry in the enclosing scope.
+            self.find(name, parent(scope))
+        } else {
+            None
+        }
+    }
+
+    /// The names of the items which are similar to the given name, closest first, as they can be
+    /// resolved from within the scope. Of equally close items, those within the scope come first.
+    pub fn suggest(&self, name: &str, scope: &str) -> Vec<String> {
+        let path = Self::path(name);
+        let scope_path = Self::path(scope);
+
+        let mut suggestions = vec![];
+        for (item_path, item) in &self.items {
+            // Compare the name with as many segments of the item path.
+            let start = item_path.len().saturating_sub(path.len());
+            let shortened_name = item_path[start..].join("\\");
+
+            if shortened_name == name || !is_similar(name, &shortened_name) {
+                continue;
+            }
+
+            // Use the full name when the shortened one resolves to another item, and leave out
+            // items which can't be named from within the scope.
+            let resolves_to_item = |name: &str| {
+                self.find(name, scope)
+                    .is_some_and(|resolved| Rc::ptr_eq(&resolved, item))
+            };
+            let full_name = item_path.join("\\");
+            let suggestion = if resolves_to_item(&shortened_name) {
+                shortened_name.clone()
+            } else if resolves_to_item(&full_name) {
+                full_name
+            } else {
+                continue;
+            };
+
+            let outside_scope = scope_path.is_empty() || !item_path.starts_with(&scope_path);
+
+            suggestions.push((
+                name_distance(name, &shortened_name),
+                outside_scope,
+                suggestion,
+            ));
+        }
+
+        suggestions.sort_by_key(|(distance, outside_scope, _)| (*distance, *outside_scope));
+
+        suggestions
+            .into_iter()
+            .map(|(_, _, suggestion)| suggestion)
+            .collect()
+    }
+
+    /// Fails when the item is private and the scope is outside of where it is declared. A private
+    /// item in a module or namespace is visible within that module or namespace, and a private
+    /// global item within the file that declares it.
+    pub fn check_visible(&self, item: &Rc<T>, scope: &str, file: FileId) -> CResult<()> {
+        let Some((path, _)) = self.items.iter().find(|(_, other)| Rc::ptr_eq(other, item)) else {
+            return Ok(());
+        };
+        let Some(declaration) = self.private.get(path) else {
+            return Ok(());
+        };
+
+        let enclosing = &path[..path.len() - 1];
+        let visible = if enclosing.is_empty() {
+            declaration.file == file
+        } else {
+            Self::path(scope).starts_with(enclosing)
+        };
+
+        if visible {
+            Ok(())
         } else {
-            error(ErrorMessage::NoResolution(self.name, name.into()))
+            error(ErrorMessage::Private(self.name, path.join("\\")))
+                .map_err(|error| error.label(*declaration, "declared private here"))
         }
     }
 
+    /// Makes the declared item private, see `check_visible`.
+    pub fn make_private(&mut self, name: &str, declaration: Span) {
+        self.private.insert(Self::path(name), declaration);
+    }
+
     /// Resolves all matches within a given path.
     pub fn resolve_wildcard(&self, name: &str) -> CResult<Vec<Rc<T>>> {
         let path = Self::path(name);
@@ -78,6 +180,13 @@ impl<T> Table<T> {
         Ok(rc)
     }
 
+    /// All items with their full names, in order of declaration.
+    pub fn iter(&self) -> impl Iterator<Item = (String, &Rc<T>)> {
+        self.items
+            .iter()
+            .map(|(path, item)| (path.join("\\"), item))
+    }
+
     fn path(string: &str) -> Vec<String> {
         string
             .split('\\')
@@ -87,9 +196,16 @@ impl<T> Table<T> {
     }
 }
 
+/// The path of the scope which the named item is in, which is empty for a global item.
+pub fn parent(name: &str) -> &str {
+    name.rsplit_once('\\').map_or("", |(parent, _)| parent)
+}
+
 #[cfg(test)]
 mod test {
-    use crate::sem::table::Table;
+    use crate::error::Annotation;
+    use crate::sem::table::{parent, Table};
+    use crate::source::Span;
 
     #[test]
     fn test() {
@@ -101,4 +217,68 @@ mod test {
         assert_eq!(table.resolve("Thing", "").unwrap().as_ref(), &1);
         assert_eq!(table.resolve("Mod", "").unwrap().as_ref(), &2);
     }
+
+    #[test]
+    fn test_enclosing_scopes() {
+        let mut table = Table::new("Integer");
+
+        table.declare("Shapes\\Point", 1).unwrap();
+        table.declare("App\\Point", 2).unwrap();
+        table.declare("App\\Line", 3).unwrap();
+
+        assert_eq!(table.resolve("Point", "App\\Line").unwrap().as_ref(), &2);
+        assert_eq!(table.resolve("Point", "Shapes").unwrap().as_ref(), &1);
+        assert_eq!(table.resolve("Line", "Shapes\\Point").unwrap().as_ref(), &3);
+
+        assert_eq!(parent("App\\Line"), "App");
+        assert_eq!(parent("Line"), "");
+    }
+
+    #[test]
+    fn test_private() {
+        let mut table = Table::new("Integer");
+        let declaration = Span {
+            file: 1,
+            start: 0,
+            end: 1,
+        };
+
+        let global = table.declare("Global", 1).unwrap();
+        let nested = table.declare("Shapes\\Point\\Nested", 2).unwrap();
+        table.make_private("Global", declaration);
+        table.make_private("Shapes\\Point\\Nested", declaration);
+
+        assert!(table.check_visible(&global, "Other", 1).is_ok());
+        assert!(table.check_visible(&global, "", 2).is_err());
+        assert!(table.check_visible(&nested, "Shapes\\Point", 2).is_ok());
+        assert!(table
+            .check_visible(&nested, "Shapes\\Point\\Line", 2)
+            .is_ok());
+        assert!(table.check_visible(&nested, "Shapes", 1).is_err());
+    }
+
+    #[test]
+    fn test_suggest() {
+        let mut table = Table::new("Integer");
+
+        table.declare("Geometry\\Point", 1).unwrap();
+        table.declare("Paint", 2).unwrap();
+        table.declare("Colour\\Paint", 3).unwrap();
+        table.declare("Length", 4).unwrap();
+
+        assert_eq!(table.suggest("Pont", ""), vec!["Point"]);
+        assert_eq!(
+            table.suggest("Pint", ""),
+            vec!["Point", "Paint", "Colour\\Paint"]
+        );
+        assert_eq!(table.suggest("Pint", "Colour"), vec!["Paint", "Point"]);
+        assert_eq!(table.suggest("Geometry\\Pont", ""), vec!["Geometry\\Point"]);
+        assert!(table.suggest("Width", "").is_empty());
+
+        let error = table.resolve("Pont", "").err().unwrap();
+        assert_eq!(
+            error.annotations,
+            vec![Annotation::Help("did you mean 'Point'?".to_string())]
+        );
+    }
 }
@@ -1,7 +1,8 @@
 use crate::ast::type_statement::{RawType, TypeStatement, TypeStatementType};
 use crate::ast::Statement;
-use crate::error::{CResult, ErrorMessage};
+use crate::error::{CResult, CompilationError, ErrorMessage};
 use crate::sem::semantic_analyser::{SemanticContext, SemanticScope};
+use crate::sem::suggestion::did_you_mean;
 use crate::sem::trayt::{interface_type, Trait};
 use std::cell::RefCell;
 use std::collections::BTreeSet;
@@ -20,6 +21,20 @@ pub enum Type {
     Void,
 }
 
+/// Suggests traits, which include the eponymous traits of modules, with a name similar to the
+/// missing one.
+fn suggest_traits(
+    error: CompilationError,
+    name: &str,
+    context: &SemanticContext,
+    path: &str,
+) -> CompilationError {
+    match did_you_mean(&context.traits.suggest(name, path)) {
+        Some(help) => error.help(&help),
+        None => error,
+    }
+}
+
 pub fn combine_types(types: Vec<Type>) -> Type {
     let mut combined = None;
 
@@ -42,21 +57,40 @@ impl Type {
         context: &SemanticContext,
         path: &str,
     ) -> CResult<Self> {
+        let file = statement.span().file;
+        let visible = |error: CompilationError| error.context(statement.span());
+
         let typ = match &statement.typ {
             TypeStatementType::Name(name) => {
                 if let Ok(interface) = context.interfaces.resolve(name, path) {
+                    context
+                        .interfaces
+                        .check_visible(&interface, path, file)
+                        .map_err(visible)?;
                     interface_type(interface.borrow().as_ref())
                 } else if let Ok(trayt) = context.traits.resolve(name, path) {
+                    context
+                        .traits
+                        .check_visible(&trayt, path, file)
+                        .map_err(visible)?;
                     Type::Trait(trayt)
                 } else {
-                    return statement.error(ErrorMessage::NoModuleOrTrait(name.clone()));
+                    return statement
+                        .error(ErrorMessage::NoModuleOrTrait(name.clone()))
+                        .map_err(|error| suggest_traits(error, name, context, path));
                 }
             }
             TypeStatementType::AtName(name) => {
                 if let Ok(trayt) = context.traits.resolve(name, path) {
+                    context
+                        .traits
+                        .check_visible(&trayt, path, file)
+                        .map_err(visible)?;
                     Type::Trait(trayt)
                 } else {
-                    return statement.error(ErrorMessage::NoTrait(name.clone()));
+                    return statement
+                        .error(ErrorMessage::NoTrait(name.clone()))
+                        .map_err(|error| suggest_traits(error, name, context, path));
                 }
             }
             TypeStatementType::And(a, b) => Type::And(
@@ -82,7 +116,7 @@ impl Type {
             Type::And(a, b) => a
                 .callable_traits(scope)
                 .into_iter()
-                .chain(b.callable_traits(scope).into_iter())
+                .chain(b.callable_traits(scope))
                 .collect(),
             Type::Zelf => match &scope.zelf {
                 None => [].into(),
@@ -16,8 +16,8 @@ pub fn check_types(
             if check_type_fits(&input.typ(scope)?, typ).is_err() {
                 return error(ErrorMessage::TypeMismatch(
                     name.clone(),
-                    typ.clone(),
-                    input.typ(scope)?,
+                    Box::new(typ.clone()),
+                    Box::new(input.typ(scope)?),
                 ));
             }
         } else {
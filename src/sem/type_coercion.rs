@@ -53,5 +53,8 @@ fn coerce_raw_to_struct(
 
     let inputs = [("value".to_string(), eval.clone())].into();
 
-    Ok(Evaluation::Let(LetEvaluation { lett, inputs }))
+    // The coerced call has no source of its own, so it borrows the span of what it wraps.
+    let span = eval.span().unwrap_or_default();
+
+    Ok(Evaluation::Let(LetEvaluation { lett, inputs, span }))
 }
@@ -0,0 +1,187 @@
+use std::ops::Range;
+
+/// The index of a file in a source map.
+pub type FileId = usize;
+
+/// A range of bytes within a file.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct Span {
+    pub file: FileId,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A line and column within a named file, both counted from 1.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Location {
+    pub file: String,
+    pub line: usize,
+    pub col: usize,
+}
+
+/// The files that make up a program. They are joined into one piece of code, which is tokenized as
+/// a whole, and positions in that code can be traced back to their file.
+#[derive(Debug, Default)]
+pub struct SourceMap {
+    code: String,
+    files: Vec<SourceFile>,
+}
+
+#[derive(Debug)]
+struct SourceFile {
+    name: String,
+    /// The namespace of what the file declares, as in "Geometry".
+    namespace: String,
+    /// The position of the start of the file in the joined code.
+    start: usize,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A source map of a single file.
+    pub fn single(name: &str, code: &str) -> Self {
+        let mut sources = Self::new();
+        sources.add(name, code);
+        sources
+    }
+
+    /// Adds a file after the ones added before.
+    pub fn add(&mut self, name: &str, code: &str) -> FileId {
+        self.add_namespaced(name, code, "")
+    }
+
+    /// Adds a file of which the declarations are in the given namespace.
+    pub fn add_namespaced(&mut self, name: &str, code: &str, namespace: &str) -> FileId {
+        // Files always start on a new line.
+        if !self.code.is_empty() && !self.code.ends_with('\n') {
+            self.code.push('\n');
+        }
+
+        self.files.push(SourceFile {
+            name: name.to_string(),
+            namespace: namespace.to_string(),
+            start: self.code.len(),
+        });
+        self.code.push_str(code);
+
+        self.files.len() - 1
+    }
+
+    /// The code of all files joined together.
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    pub fn name(&self, file: FileId) -> &str {
+        &self.files[file].name
+    }
+
+    pub fn namespace(&self, file: FileId) -> &str {
+        &self.files[file].namespace
+    }
+
+    /// The number of files.
+    pub fn file_count(&self) -> usize {
+        self.files.len()
+    }
+
+    /// The code of a single file.
+    pub fn file_code(&self, file: FileId) -> &str {
+        &self.code[self.range(file)]
+    }
+
+    /// The range of the joined code which a file takes up.
+    pub fn range(&self, file: FileId) -> Range<usize> {
+        let end = self
+            .files
+            .get(file + 1)
+            .map_or(self.code.len(), |next| next.start);
+
+        self.files[file].start..end
+    }
+
+    /// The file which contains the given position of the joined code.
+    pub fn file_at(&self, position: usize) -> FileId {
+        self.files
+            .iter()
+            .rposition(|file| file.start <= position)
+            .unwrap_or(0)
+    }
+
+    /// The span within a file of a range of the joined code. A range running into the next file is
+    /// cut off at the end of the file it starts in.
+    pub fn span(&self, range: Range<usize>) -> Span {
+        let file = self.file_at(range.start);
+        let start = self.files[file].start;
+        let len = self.file_code(file).len();
+
+        Span {
+            file,
+            start: (range.start - start).min(len),
+            end: range
+                .end
+                .saturating_sub(start)
+                .clamp(range.start - start, len),
+        }
+    }
+
+    /// The file, line and column of the start of the span.
+    pub fn location(&self, span: &Span) -> Location {
+        let before = &self.file_code(span.file)[..span.start];
+        let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+
+        Location {
+            file: self.name(span.file).to_string(),
+            line: before.matches('\n').count() + 1,
+            col: before[line_start..].chars().count() + 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::source::{Location, SourceMap, Span};
+
+    #[test]
+    fn test_files() {
+        let mut sources = SourceMap::new();
+        let std = sources.add("std.compost", "lets\n    One: 1\n");
+        let user = sources.add("user.compost", "lets\n    Two: 2");
+        let input = sources.add("input", "Two");
+
+        assert_eq!(sources.code(), "lets\n    One: 1\nlets\n    Two: 2\nTwo");
+        assert_eq!(sources.file_code(user), "lets\n    Two: 2\n");
+        assert_eq!(sources.file_at(0), std);
+        assert_eq!(sources.file_at(16), user);
+        assert_eq!(sources.file_at(32), input);
+    }
+
+    #[test]
+    fn test_location() {
+        let mut sources = SourceMap::new();
+        sources.add("std.compost", "lets\n    One: 1\n");
+        sources.add("user.compost", "lets\n    Two: 2\n");
+
+        let span = sources.span(25..28);
+
+        assert_eq!(
+            span,
+            Span {
+                file: 1,
+                start: 9,
+                end: 12
+            }
+        );
+        assert_eq!(
+            sources.location(&span),
+            Location {
+                file: "user.compost".to_string(),
+                line: 2,
+                col: 5
+            }
+        );
+    }
+}
@@ -0,0 +1,163 @@
+use std::process::{Command, Output};
+
+fn compost(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_compost"))
+        .args(args)
+        .output()
+        .expect("Unable to run compost")
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8(output.stderr.clone()).unwrap()
+}
+
+#[test]
+fn test_run() {
+    let output = compost(&["run", "examples/if.compost"]);
+
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "Yes\n");
+
+    // Running is the default.
+    let output = compost(&["examples/if.compost"]);
+
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "Yes\n");
+
+    // A directory is run as a package.
+    let output = compost(&["run", "examples/packages/app"]);
+
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "App: 1, 2, 3. Shapes: 1, 2.\n");
+}
+
+#[test]
+fn test_check() {
+    let output = compost(&["check", "examples/binary_tree.compost"]);
+
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "");
+
+    let output = compost(&["check", "tests/programs/undefined_local.compost"]);
+
+    assert_eq!(output.status.code(), Some(5));
+    assert_eq!(stdout(&output), "");
+    assert_eq!(
+        stderr(&output),
+        "error[C0004]: No resolution for local variable 'x'\n \
+          --> tests/programs/undefined_local.compost:3:13\n  \
+         |\n\
+         3 |         1 + x\n  \
+         |             ^\n\n\
+         For more information about an error, try `compost explain C0004`.\n"
+    );
+}
+
+#[test]
+fn test_labels() {
+    let output = compost(&["check", "tests/programs/type_mismatch.compost"]);
+
+    assert_eq!(output.status.code(), Some(5));
+    assert!(stderr(&output).starts_with(
+        "error[C0014]: Type mismatch for 'n'.\n \
+          --> tests/programs/type_mismatch.compost:6:9\n  \
+         |\n\
+         6 |         Double(n: 'two')\n  \
+         |         ^^^^^^^^^^^^^^^^\n  \
+         |\n\
+         2 |     Double(n: Int): Int\n  \
+         |               --- expected type declared here\n  \
+         |\n  \
+         = note: Expected: "
+    ));
+}
+
+#[test]
+fn test_exit_codes() {
+    let lexing = compost(&["run", "tests/programs/unexpected_char.compost"]);
+    let parsing = compost(&["run", "tests/programs/missing_type.compost"]);
+    let semantic = compost(&["run", "tests/programs/undefined_local.compost"]);
+    let runtime = compost(&["run", "tests/programs/division_by_zero.compost"]);
+
+    assert_eq!(lexing.status.code(), Some(3));
+    assert_eq!(parsing.status.code(), Some(4));
+    assert_eq!(semantic.status.code(), Some(5));
+    assert_eq!(runtime.status.code(), Some(6));
+
+    assert!(stderr(&runtime).starts_with("error: Division by zero in 'Op\\Div'"));
+    assert_eq!(stdout(&runtime), "");
+}
+
+#[test]
+fn test_explain() {
+    let output = compost(&["explain", "C0014"]);
+
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stdout(&output).starts_with("A value was given which doesn't have the type"));
+    assert!(stdout(&output).contains("Double(n: 'two')"));
+
+    let output = compost(&["explain", "C1234"]);
+
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(stderr(&output), "Unknown error code 'C1234'\n");
+}
+
+#[test]
+fn test_tokens() {
+    let output = compost(&["tokens", "examples/if.compost"]);
+
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stdout(&output).starts_with("1:1\t0\tKw(Lets)\n2:5\t4\tGlobal(\"Main\")\n"));
+    assert!(stdout(&output).ends_with("6:1\t0\tEof\n"));
+}
+
+#[test]
+fn test_ast() {
+    let output = compost(&["ast", "examples/if.compost"]);
+
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stdout(&output).starts_with("AbstractSyntaxTree {\n    imports: [],\n    mods: [],\n"));
+    assert!(stdout(&output).contains("name: \"Main\""));
+}
+
+#[test]
+fn test_types() {
+    let output = compost(&["types", "examples/functions_and_constants.compost"]);
+
+    assert_eq!(output.status.code(), Some(0));
+    let lines: Vec<String> = stdout(&output).lines().map(String::from).collect();
+
+    // Only what's declared in the file, not the standard library.
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with("let MyConstant: Op\\Add & "));
+    assert!(lines[1].starts_with("let MyFunction(a: Op\\Add & "));
+    assert!(lines[2].starts_with("let Main: "));
+}
+
+#[test]
+fn test_fmt() {
+    let unformatted = compost(&["fmt", "--check", "tests/programs/unformatted.compost"]);
+    let formatted = compost(&["fmt", "--check", "examples/classes.compost"]);
+
+    assert_eq!(unformatted.status.code(), Some(1));
+    assert_eq!(
+        stderr(&unformatted),
+        "'tests/programs/unformatted.compost' is not formatted\n"
+    );
+    assert_eq!(formatted.status.code(), Some(0));
+
+    let parsing = compost(&["fmt", "--check", "tests/programs/missing_type.compost"]);
+    assert_eq!(parsing.status.code(), Some(4));
+}
+
+#[test]
+fn test_usage() {
+    assert_eq!(compost(&[]).status.code(), Some(2));
+    assert_eq!(compost(&["run"]).status.code(), Some(2));
+    assert_eq!(compost(&["--unknown", "file"]).status.code(), Some(2));
+    assert_eq!(compost(&["run", "missing.compost"]).status.code(), Some(2));
+}
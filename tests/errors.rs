@@ -0,0 +1,478 @@
+use compost::run::{
+    check_code, check_package, compile_file_to_c, compile_file_to_wat, run_code,
+    run_code_with_limits, run_file, run_package, DiagnosticKind, Limits, Source,
+};
+
+#[test]
+fn test_division_by_zero() {
+    let code = r#"
+lets
+    Main: Int
+        10 / 0
+"#;
+
+    let diagnostic = run_code(code).unwrap_err().remove(0);
+
+    assert_eq!(diagnostic.kind, DiagnosticKind::Runtime);
+    assert!(diagnostic
+        .message
+        .starts_with("Division by zero in 'Op\\Div'"))
+}
+
+#[test]
+fn test_void_call() {
+    let code = r#"
+mod Thing
+    traits
+        Value: Int
+
+lets
+    Nothing: Thing
+        ?
+
+    Main: Int
+        Nothing.Value
+"#;
+
+    assert!(run_code(code).unwrap_err()[0]
+        .message
+        .starts_with("Can't call trait 'Thing\\Value' on void"))
+}
+
+#[test]
+fn test_traceback() {
+    let code = r#"
+mod Counter
+    class
+        count: Int
+    traits
+        Ratio: (divisor: Int) -> Int
+    defs
+        Ratio: count / divisor
+
+lets
+    Compute: (counter: Counter) -> Int
+        counter.Ratio(divisor: 0)
+
+    Main: Int
+        Compute(counter: Counter(count: 4))
+"#;
+
+    let trace = run_code(code).unwrap_err().remove(0).trace;
+    let traceback = trace.iter().take(4).collect::<Vec<_>>();
+
+    assert_eq!(
+        traceback,
+        [
+            "Traceback (most recent call last):",
+            "  Compute at user.compost:15:9",
+            "  Counter\\Ratio on Counter at user.compost:12:9",
+            "  Op\\Div on Int at user.compost:8:16",
+        ]
+    )
+}
+
+#[test]
+fn test_recursion_limit() {
+    let code = r#"
+mod Rectangle
+    traits
+        Top: Int
+        Bottom: Int
+        Height: Int
+    defs
+        Top: .Bottom - .Height
+        Bottom: .Top + .Height
+
+mod Square
+    class
+        size: Int
+    defs
+        Rectangle\Height: size
+
+lets
+    Main: Int
+        Square(size: 3).Bottom
+"#;
+
+    let limits = Limits {
+        max_depth: 100,
+        fuel: None,
+    };
+
+    assert!(run_code_with_limits(code, limits).unwrap_err()[0]
+        .message
+        .starts_with("Recursion limit exceeded (max depth: 100)"))
+}
+
+#[test]
+fn test_step_budget() {
+    let code = r#"
+lets
+    Count: (n: Int) -> Int
+        if n = 0 then Int(value: 0) else Count(n: n - 1) + 1
+
+    Main: Int
+        Count(n: 100)
+"#;
+
+    let limits = Limits {
+        max_depth: 1000,
+        fuel: Some(50),
+    };
+
+    assert!(run_code_with_limits(code, limits).unwrap_err()[0]
+        .message
+        .starts_with("Step budget exhausted (fuel: 50)"));
+    assert_eq!(run_code(code).unwrap(), "100");
+}
+
+#[test]
+fn test_tail_calls() {
+    let code = r#"
+mod Node
+    class
+        value: Int
+        prev: Self | ?
+    traits
+        Sum: (total: Int) -> Int
+    defs
+        Sum
+            match prev: prev
+                Self: prev.Sum(total: total + value)
+                ?: total + value
+
+lets
+    Build: (n: Int, list: Node) -> Node
+        if n = 0
+            then list
+            else Build(n: n - 1, list: Node(value: n, prev: list))
+
+    Main: Int
+        Build(n: 99999, list: Node(value: 100000, prev: ?)).Sum(total: 0)
+"#;
+
+    let limits = Limits {
+        max_depth: 100,
+        fuel: None,
+    };
+
+    assert_eq!(run_code_with_limits(code, limits).unwrap(), "5000050000")
+}
+
+#[test]
+fn test_multiple_parse_errors() {
+    let code = r#"
+lets
+    First: Int
+        1 +
+    Second: Int
+        2
+    Third: Int
+        )
+
+lets
+    Main: Int
+        Second
+"#;
+
+    let diagnostics = run_code(code).unwrap_err();
+    let messages: Vec<_> = diagnostics
+        .iter()
+        .map(|d| {
+            let location = d.location.as_ref().unwrap();
+            (d.message.as_str(), location.line, location.col)
+        })
+        .collect();
+
+    assert_eq!(
+        messages,
+        vec![
+            (
+                "Unexpected token Global(\"Second\"), expecting an expression",
+                5,
+                5
+            ),
+            ("Unexpected token Kw(Lets), expecting an expression", 10, 1),
+        ]
+    );
+    assert!(diagnostics
+        .iter()
+        .all(|d| d.kind == DiagnosticKind::Parsing));
+}
+
+#[test]
+fn test_multiple_semantic_errors() {
+    let code = r#"
+mod Point
+    class
+        x: Int
+    traits
+        Sum: Int
+    defs
+        Sum: x + z
+        Other: x
+
+lets
+    Main: Int
+        Missing
+    Typed: Nope
+        1
+"#;
+
+    let diagnostics = run_code(code).unwrap_err();
+    let messages: Vec<_> = diagnostics
+        .iter()
+        .map(|d| {
+            let location = d.location.as_ref().unwrap();
+            (d.message.as_str(), location.line, location.col)
+        })
+        .collect();
+
+    assert_eq!(
+        messages,
+        vec![
+            ("No resolution for local variable 'z'", 8, 18),
+            ("No resolution for Trait 'Other'", 9, 9),
+            ("No resolution for Let 'Missing'", 13, 9),
+            ("'Nope' is not a module or a trait", 14, 12),
+        ]
+    );
+    assert!(diagnostics
+        .iter()
+        .all(|d| d.kind == DiagnosticKind::Semantic));
+}
+
+#[test]
+fn test_suggestions() {
+    let code = r#"
+mod Point
+    class
+        x: Int
+        y: Int
+    traits
+        Sum: Int
+    defs
+        Sum: x + yy
+
+lets
+    Origin: Point
+        Point(x: 0, y: 0)
+    Main: Int
+        Origin.Summ
+    Other: Int
+        Orign.Sum
+    Typed: Pointt
+        Origin
+"#;
+
+    let diagnostics = run_code(code).unwrap_err();
+    let help: Vec<_> = diagnostics.iter().map(|d| d.help.clone()).collect();
+
+    assert_eq!(
+        help,
+        vec![
+            vec!["did you mean 'y'?".to_string()],
+            vec![
+                "did you mean 'Sum'?".to_string(),
+                "the traits which can be called on this type are 'Point\\Sum'".to_string()
+            ],
+            vec!["did you mean 'Origin'?".to_string()],
+            vec!["did you mean 'Point'?".to_string()],
+        ]
+    );
+}
+
+#[test]
+fn test_missing_file() {
+    let path = "tests/programs/missing.compost";
+
+    for result in [
+        run_file(path),
+        compile_file_to_c(path),
+        compile_file_to_wat(path),
+    ] {
+        let diagnostic = result.unwrap_err().remove(0);
+
+        assert_eq!(diagnostic.kind, DiagnosticKind::Package);
+        assert!(diagnostic
+            .message
+            .starts_with("Unable to read file 'tests/programs/missing.compost'"));
+    }
+}
+
+#[test]
+fn test_file_locations() {
+    let code = r#"
+mod Int
+    struct
+        value: int
+
+lets
+    Main: Int
+        1
+"#;
+
+    let diagnostic = run_code(Source::new("numbers.compost", code))
+        .unwrap_err()
+        .remove(0);
+    let location = diagnostic.location.as_ref().unwrap();
+    let first = &diagnostic.labels[0].snippet;
+
+    assert_eq!(
+        (location.file.as_str(), location.line),
+        ("numbers.compost", 2)
+    );
+    assert_eq!(first.file, "std.compost");
+    assert_eq!(first.source, "mod Int");
+    assert!(diagnostic.to_string().contains("::: std.compost:"));
+}
+
+#[test]
+fn test_imports() {
+    let cycle = run_file("tests/programs/imports/First.compost").unwrap_err();
+
+    assert_eq!(cycle[0].kind, DiagnosticKind::Parsing);
+    assert_eq!(cycle[0].code, Some("C0016"));
+    assert_eq!(
+        cycle[0].location.as_ref().unwrap().file,
+        "tests/programs/imports/Second.compost"
+    );
+
+    let missing = run_file("tests/programs/imports/missing.compost").unwrap_err();
+
+    assert_eq!(missing[0].code, Some("C0015"));
+    assert!(missing[0]
+        .message
+        .contains("'tests/programs/imports/Nowhere/Missing.compost'"));
+}
+
+#[test]
+fn test_packages() {
+    let cycle = check_package("tests/programs/packages/first").unwrap_err();
+
+    assert_eq!(cycle[0].kind, DiagnosticKind::Package);
+    assert_eq!(
+        cycle[0].message,
+        "Dependency cycle: First -> Second -> First"
+    );
+    assert_eq!(
+        cycle[0].location.as_ref().unwrap().file,
+        "tests/programs/packages/second/compost.toml"
+    );
+
+    let misnamed = check_package("tests/programs/packages/misnamed").unwrap_err();
+
+    assert_eq!(
+        misnamed[0].message,
+        "The package at 'examples/packages/shapes' is named 'Shapes', not 'Geometry'"
+    );
+    assert_eq!(misnamed[0].location.as_ref().unwrap().line, 6);
+
+    let invalid = check_package("tests/programs/packages/invalid").unwrap_err();
+
+    assert_eq!(invalid[0].location.as_ref().unwrap().line, 2);
+    assert!(invalid[0].message.contains("'invalid'"));
+
+    let missing = check_package("tests/programs/packages/nothing").unwrap_err();
+
+    assert_eq!(
+        missing[0].message,
+        "Unable to read the manifest 'tests/programs/packages/nothing/compost.toml'"
+    );
+    assert_eq!(missing[0].kind.exit_code(), 2);
+
+    // Lets are named by their namespace once.
+    let runtime = run_package("tests/programs/packages/caller").unwrap_err();
+
+    assert_eq!(
+        runtime[0].trace[1],
+        "  Faulty\\Broken at tests/programs/packages/caller/main.compost:3:9"
+    );
+}
+
+#[test]
+fn test_private() {
+    // A private global is only visible within its file.
+    let diagnostics = run_file("tests/programs/imports/private.compost").unwrap_err();
+
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!(diagnostics[0].code, Some("C0017"));
+    assert_eq!(diagnostics[0].message, "Let 'Code' is private");
+    assert_eq!(
+        diagnostics[0].labels[0].snippet.file,
+        "tests/programs/imports/Secrets.compost"
+    );
+    assert_eq!(diagnostics[1].message, "Interface 'Secret' is private");
+
+    // A private let of a package is only visible within the package.
+    let diagnostics = check_package("tests/programs/packages/intruder").unwrap_err();
+
+    assert_eq!(diagnostics[0].message, "Let 'Shapes\\Zero' is private");
+
+    // A private trait is only visible within its module.
+    let code = "mod Point
+    class
+        x: Int
+    traits
+        private Secret: Int
+        X: Int
+    defs
+        Secret: x
+        X: .Secret
+
+lets
+    Main: Int
+        Point(x: 1).X + Point(x: 2).Secret
+";
+    let diagnostics = run_code(code).unwrap_err();
+
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].message, "Trait 'Point\\Secret' is private");
+    assert_eq!(diagnostics[0].location.as_ref().unwrap().col, 25);
+}
+
+#[test]
+fn test_integer_too_large() {
+    let code = r#"
+lets
+    Main: Int
+        18446744073709551615
+"#;
+
+    let diagnostic = run_code(code).unwrap_err().remove(0);
+    let location = diagnostic.location.as_ref().unwrap();
+
+    assert_eq!(diagnostic.kind, DiagnosticKind::Lexing);
+    assert_eq!(diagnostic.code, Some("C0020"));
+    assert_eq!((location.line, location.col), (4, 9));
+
+    assert_eq!(
+        run_code(&code.replace("18446744073709551615", "9223372036854775807")).unwrap(),
+        "9223372036854775807"
+    );
+}
+
+#[test]
+fn test_cut_off_code() {
+    let class = "mod Point\n    class(x: Int, y";
+    let def = "mod Point
+    class
+        x: Int
+    traits
+        Moved: (dx: Int) -> Point
+    defs
+        Moved: Point(x: x + ";
+    let matching = "lets
+    Value: (item: Int | ?) -> Int
+        match known: item
+            Int
+";
+
+    // Recovering from the error at the end of the code doesn't step beyond it.
+    for code in [class, def, matching] {
+        let diagnostics = check_code(code).unwrap_err();
+
+        assert_eq!(diagnostics[0].kind, DiagnosticKind::Parsing, "{}", code);
+    }
+}
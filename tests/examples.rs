@@ -1,69 +1,198 @@
-use compost::run::run_file;
+use compost::run::{
+    compile_code_to_c, compile_file_to_c, format_code, run_code, run_file, run_package, Source,
+};
+use std::path::Path;
+use std::process::Command;
+use std::{env, fs, process};
+
+/// Checks the output of an example, both from the interpreter and from a binary compiled from
+/// the generated C code.
+fn assert_output(file_path: &str, expected: &str) {
+    assert_eq!(
+        run_file(file_path).unwrap(),
+        expected,
+        "interpreted {}",
+        file_path
+    );
+
+    assert_eq!(
+        run_c(file_path),
+        Ok(expected.to_string()),
+        "compiled {}",
+        file_path
+    );
+}
+
+/// Compiles the file to C, builds it with the system C compiler and returns what it prints, as an
+/// error if it fails.
+fn run_c(file_path: &str) -> Result<String, String> {
+    let name = Path::new(file_path).file_stem().unwrap().to_str().unwrap();
+    let dir = env::temp_dir().join(format!("compost-{}-{}", process::id(), name));
+    fs::create_dir_all(&dir).unwrap();
+
+    let source = dir.join("main.c");
+    let binary = dir.join("main");
+    fs::write(&source, compile_file_to_c(file_path).unwrap()).unwrap();
+
+    let status = Command::new("cc")
+        .arg("-o")
+        .arg(&binary)
+        .arg(&source)
+        .status()
+        .expect("Unable to run cc");
+    assert!(status.success(), "cc failed on {}", source.display());
+
+    let output = Command::new(&binary).output().unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    let printed = String::from_utf8(output.stdout)
+        .unwrap()
+        .trim_end_matches('\n')
+        .to_string();
+
+    if output.status.success() {
+        Ok(printed)
+    } else {
+        Err(printed)
+    }
+}
 
 #[test]
 fn test_automatic_definitions() {
-    assert_eq!(
-        run_file("examples/automatic_definitions.compost"),
+    assert_output(
+        "examples/automatic_definitions.compost",
         "BottomRight of A: 30, 15. Width and Height of B: 5, 10",
     )
 }
 
 #[test]
 fn test_class_inheritance() {
-    assert_eq!(run_file("examples/class_inheritance.compost"), "100")
+    assert_output("examples/class_inheritance.compost", "100")
 }
 
 #[test]
 fn test_classes() {
-    assert_eq!(
-        run_file("examples/classes.compost"),
+    assert_output(
+        "examples/classes.compost",
         "There is no way to output this point",
     )
 }
 
 #[test]
 fn test_functions_and_constants() {
-    assert_eq!(run_file("examples/functions_and_constants.compost"), "52")
+    assert_output("examples/functions_and_constants.compost", "52")
 }
 
 #[test]
 fn test_traits_and_definitions() {
-    assert_eq!(
-        run_file("examples/traits_and_definitions.compost"),
-        "-1, -2",
-    )
+    assert_output("examples/traits_and_definitions.compost", "-1, -2")
 }
 
 #[test]
 fn test_types() {
-    assert_eq!(
-        run_file("examples/types.compost"),
+    assert_output(
+        "examples/types.compost",
         "Hello, Bob. Hello, Fifi. Bob (20). Fifi (3)",
     )
 }
 
 #[test]
 fn test_linked_list() {
-    assert_eq!(
-        run_file("examples/linked_list.compost"),
-        "1, 2, 3, 4 (total: 10). Reversed: 4, 3, 2, 1 (total: 10). Together: 1, 2, 3, 4, 4, 3, 2, 1 (total: 20)",
-    )
+    assert_output("examples/linked_list.compost", "1, 2, 3, 4 (total: 10). Reversed: 4, 3, 2, 1 (total: 10). Together: 1, 2, 3, 4, 4, 3, 2, 1 (total: 20)")
 }
 
 #[test]
 fn test_multiple_inheritance() {
-    assert_eq!(
-        run_file("examples/multiple_inheritance.compost"),
+    assert_output(
+        "examples/multiple_inheritance.compost",
         "Child of Perry (species: Platypus)",
     )
 }
 
 #[test]
 fn test_if() {
-    assert_eq!(run_file("examples/if.compost"), "Yes")
+    assert_output("examples/if.compost", "Yes")
 }
 
 #[test]
 fn test_binary_tree() {
-    assert_eq!(run_file("examples/binary_tree.compost"), "3 -1 2")
+    assert_output("examples/binary_tree.compost", "3 -1 2")
+}
+
+#[test]
+fn test_imports() {
+    assert_output("examples/imports.compost", "6, 8")
+}
+
+#[test]
+fn test_long_list() {
+    assert_output("examples/long_list.compost", "20000100000")
+}
+
+#[test]
+fn test_recursion_limit() {
+    let file_path = "tests/programs/mutual_recursion.compost";
+    let message = "Recursion limit exceeded (max depth: 100000)";
+
+    let diagnostics = run_file(file_path).unwrap_err();
+    assert!(diagnostics[0].message.starts_with(message));
+    assert_eq!(run_c(file_path), Err(message.to_string()));
+}
+
+#[test]
+fn test_escapes() {
+    assert_output("tests/programs/escapes.compost", "It's 🌱, a\\b\tc\u{1}d");
+
+    // C strings end at a NUL character, so neither backend accepts one.
+    let code = "lets\n    Main: String\n        'a\\u{0}b'\n";
+    for result in [run_code(code), compile_code_to_c(code)] {
+        let diagnostic = result.unwrap_err().remove(0);
+        let location = diagnostic.location.unwrap();
+
+        assert_eq!(diagnostic.message, "Invalid escape '\\u{0}' in string");
+        assert_eq!((location.line, location.col, location.len), (3, 11, 5));
+    }
+}
+
+#[test]
+fn test_packages() {
+    assert_eq!(
+        run_package("examples/packages/app").unwrap(),
+        "App: 1, 2, 3. Shapes: 1, 2."
+    );
+    assert_eq!(
+        run_package("examples/packages/app/compost.toml").unwrap(),
+        "App: 1, 2, 3. Shapes: 1, 2."
+    );
+}
+
+#[test]
+fn test_formatted_examples() {
+    for entry in fs::read_dir("examples").unwrap() {
+        let path = entry.unwrap().path();
+        if path
+            .extension()
+            .is_none_or(|extension| extension != "compost")
+        {
+            continue;
+        }
+
+        let file_path = path.to_str().unwrap();
+        let code = fs::read_to_string(&path).unwrap();
+        let formatted = format_code(Source::new(file_path, &code)).unwrap();
+
+        // Some examples only declare modules, without a Main let to run.
+        assert_eq!(
+            run_code(Source::new(file_path, &formatted)).ok(),
+            run_file(file_path).ok(),
+            "formatted {}",
+            file_path
+        );
+        assert_eq!(
+            format_code(Source::new(file_path, &formatted)).unwrap(),
+            formatted,
+            "formatted twice {}",
+            file_path
+        );
+    }
 }
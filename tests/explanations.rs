@@ -0,0 +1,41 @@
+use compost::run::{check_code, error_codes, explain};
+
+/// The code blocks of an explanation, which are the erroneous example and its fix.
+fn examples(explanation: &str) -> Vec<String> {
+    explanation
+        .split("```compost\n")
+        .skip(1)
+        .map(|block| block.split("```").next().unwrap().to_string())
+        .collect()
+}
+
+/// The errors whose examples span several files, which can't be checked as the code of one.
+const ACROSS_FILES: [&str; 2] = ["C0015", "C0016"];
+
+#[test]
+fn test_examples() {
+    for code in error_codes() {
+        let explanation = explain(code).unwrap();
+
+        let examples = examples(explanation);
+        let [erroneous, fixed] = examples.as_slice() else {
+            panic!("{} should have an erroneous example and a fix", code);
+        };
+
+        if ACROSS_FILES.contains(&code) {
+            continue;
+        }
+
+        let diagnostics = check_code(erroneous).unwrap_err();
+        assert_eq!(diagnostics[0].code, Some(code), "{}", erroneous);
+
+        assert_eq!(check_code(fixed), Ok(()), "{}", fixed);
+    }
+}
+
+#[test]
+fn test_unknown_code() {
+    assert_eq!(explain("C0000"), None);
+    assert_eq!(explain("C9999"), None);
+    assert_eq!(explain("c0004"), explain("C0004"));
+}
@@ -0,0 +1,367 @@
+use std::env;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, ExitStatus, Stdio};
+
+const URI: &str = "file:///tmp/points.compost";
+
+const CODE: &str = "mod Point
+    class
+        x: Int
+        y: Int
+    traits
+        X: Int
+        Moved: (dx: Int) -> Point
+    defs
+        X: x
+        Moved: Point(x: x + dx, y: y)
+
+lets
+    Origin: Point
+        Point(x: 0, y: 0)
+
+    Shifted(by: Int): Point
+        Origin.Moved(dx: by)
+
+    Main: Int
+        Shifted(by: 2).X
+";
+
+/// A language client which talks to `compost lsp` through its stdin and stdout.
+struct Client {
+    server: Child,
+    input: ChildStdin,
+    output: BufReader<ChildStdout>,
+    next_id: usize,
+}
+
+impl Client {
+    /// Starts the server and initializes it.
+    fn start() -> Self {
+        let mut server = Command::new(env!("CARGO_BIN_EXE_compost"))
+            .arg("lsp")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("Unable to run compost lsp");
+
+        let mut client = Client {
+            input: server.stdin.take().unwrap(),
+            output: BufReader::new(server.stdout.take().unwrap()),
+            server,
+            next_id: 1,
+        };
+
+        let response = client.request("initialize", r#"{"capabilities":{}}"#);
+        assert!(response.contains(r#""hoverProvider":true"#), "{}", response);
+        client.notify("initialized", "{}");
+
+        client
+    }
+
+    fn send(&mut self, message: &str) {
+        write!(
+            self.input,
+            "Content-Length: {}\r\n\r\n{}",
+            message.len(),
+            message
+        )
+        .unwrap();
+        self.input.flush().unwrap();
+    }
+
+    fn notify(&mut self, method: &str, params: &str) {
+        self.send(&format!(
+            r#"{{"jsonrpc":"2.0","method":"{}","params":{}}}"#,
+            method, params
+        ));
+    }
+
+    /// Sends a request and returns its response, skipping notifications sent before it.
+    fn request(&mut self, method: &str, params: &str) -> String {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        self.send(&format!(
+            r#"{{"jsonrpc":"2.0","id":{},"method":"{}","params":{}}}"#,
+            id, method, params
+        ));
+
+        loop {
+            let message = self.read();
+            if message.contains(&format!(r#""id":{},"#, id)) {
+                return message;
+            }
+        }
+    }
+
+    /// Reads the next message from the server.
+    fn read(&mut self) -> String {
+        let mut length = 0;
+
+        loop {
+            let mut header = String::new();
+            self.output.read_line(&mut header).unwrap();
+
+            match header.trim_end().split_once(": ") {
+                Some(("Content-Length", value)) => length = value.parse().unwrap(),
+                _ if header.trim_end().is_empty() => break,
+                _ => {}
+            }
+        }
+
+        let mut content = vec![0; length];
+        self.output.read_exact(&mut content).unwrap();
+
+        String::from_utf8(content).unwrap()
+    }
+
+    fn open(&mut self, uri: &str, code: &str) -> String {
+        self.notify(
+            "textDocument/didOpen",
+            &format!(
+                r#"{{"textDocument":{{"uri":"{}","languageId":"compost","version":1,"text":{}}}}}"#,
+                uri,
+                json_string(code)
+            ),
+        );
+
+        self.read()
+    }
+
+    fn change(&mut self, uri: &str, code: &str) -> String {
+        self.notify(
+            "textDocument/didChange",
+            &format!(
+                r#"{{"textDocument":{{"uri":"{}","version":2}},"contentChanges":[{{"text":{}}}]}}"#,
+                uri,
+                json_string(code)
+            ),
+        );
+
+        self.read()
+    }
+
+    /// Sends a request about the position in the document.
+    fn at(&mut self, method: &str, uri: &str, line: usize, character: usize) -> String {
+        self.request(
+            &format!("textDocument/{}", method),
+            &format!(
+                r#"{{"textDocument":{{"uri":"{}"}},"position":{{"line":{},"character":{}}}}}"#,
+                uri, line, character
+            ),
+        )
+    }
+
+    /// Shuts the server down and waits for it to exit.
+    fn exit(mut self) -> ExitStatus {
+        let response = self.request("shutdown", "null");
+        assert!(response.contains(r#""result":null"#), "{}", response);
+
+        self.notify("exit", "null");
+        self.server.wait().unwrap()
+    }
+}
+
+/// The code as a JSON string.
+fn json_string(code: &str) -> String {
+    format!(
+        "\"{}\"",
+        code.replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+    )
+}
+
+#[test]
+fn test_lifecycle() {
+    let mut client = Client::start();
+
+    let response = client.request("workspace/unknown", "{}");
+    assert!(response.contains(r#""code":-32601"#), "{}", response);
+
+    assert!(client.exit().success());
+}
+
+#[test]
+fn test_diagnostics() {
+    let mut client = Client::start();
+
+    let diagnostics = client.open(URI, &CODE.replace("Shifted(by: 2)", "Shifted(by: z)"));
+    assert!(
+        diagnostics.contains(r#""method":"textDocument/publishDiagnostics""#),
+        "{}",
+        diagnostics
+    );
+    assert!(
+        diagnostics.contains(
+            r#""range":{"start":{"line":19,"character":20},"end":{"line":19,"character":21}}"#
+        ),
+        "{}",
+        diagnostics
+    );
+    assert!(
+        diagnostics.contains(r#""message":"No resolution for local variable 'z'"#),
+        "{}",
+        diagnostics
+    );
+    assert!(diagnostics.contains(r#""code":"C0004""#), "{}", diagnostics);
+
+    let diagnostics = client.change(URI, CODE);
+    assert!(
+        diagnostics.contains(r#""diagnostics":[]"#),
+        "{}",
+        diagnostics
+    );
+
+    assert!(client.exit().success());
+}
+
+#[test]
+fn test_incomplete_documents() {
+    let mut client = Client::start();
+    client.open(URI, CODE);
+
+    let mut documents = vec![
+        "mod Name\n    class(v".to_string(),
+        CODE.replace("Origin.Moved(dx: by)", "Origin.Moved(dx: by).Z"),
+        CODE.replace("Shifted(by: 2)", "Shifted(by: 18446744073709551615)"),
+        // A field of a matched local which doesn't exist.
+        std::fs::read_to_string("examples/binary_tree.compost")
+            .unwrap()
+            .replace("node.Insert", "node.nsert"),
+    ];
+    // The code as it is being typed, cut off at the end of each line.
+    documents.extend(
+        CODE.match_indices('\n')
+            .map(|(end, _)| CODE[..end].to_string()),
+    );
+    // And in the middle of each token.
+    documents.extend(
+        CODE.match_indices(|c: char| !c.is_alphanumeric())
+            .map(|(end, _)| CODE[..end.saturating_sub(1)].to_string()),
+    );
+
+    for document in documents {
+        let diagnostics = client.change(URI, &document);
+        assert!(
+            diagnostics.contains(r#""method":"textDocument/publishDiagnostics""#),
+            "{}\n{}",
+            document,
+            diagnostics
+        );
+
+        let hover = client.at("hover", URI, 0, 0);
+        assert!(hover.contains(r#""result""#), "{}\n{}", document, hover);
+    }
+
+    assert!(client.exit().success());
+}
+
+#[test]
+fn test_hover() {
+    let mut client = Client::start();
+    client.open(URI, CODE);
+
+    // A let.
+    let hover = client.at("hover", URI, 19, 9);
+    assert!(hover.contains(r#"```compost\nShifted(by: "#), "{}", hover);
+
+    // A local.
+    let hover = client.at("hover", URI, 16, 25);
+    assert!(hover.contains(r#"```compost\nby: "#), "{}", hover);
+
+    // A trait call.
+    let hover = client.at("hover", URI, 16, 17);
+    assert!(
+        hover.contains(r#"```compost\nPoint\\Moved(dx: "#),
+        "{}",
+        hover
+    );
+
+    // Nothing.
+    let hover = client.at("hover", URI, 10, 0);
+    assert!(hover.contains(r#""result":null"#), "{}", hover);
+
+    assert!(client.exit().success());
+}
+
+#[test]
+fn test_definition() {
+    let mut client = Client::start();
+    client.open(URI, CODE);
+
+    // A let.
+    let definition = client.at("definition", URI, 19, 9);
+    assert!(
+        definition.contains(
+            r#""range":{"start":{"line":15,"character":4},"end":{"line":15,"character":27}}"#
+        ),
+        "{}",
+        definition
+    );
+
+    // A trait call.
+    let definition = client.at("definition", URI, 19, 24);
+    assert!(
+        definition.contains(r#""start":{"line":5,"character":8}"#),
+        "{}",
+        definition
+    );
+
+    // A module, through its constructor.
+    let definition = client.at("definition", URI, 13, 9);
+    assert!(
+        definition.contains(r#""start":{"line":0,"character":0}"#),
+        "{}",
+        definition
+    );
+
+    // A module in an imported file.
+    let path = env::current_dir().unwrap().join("examples/imports.compost");
+    let uri = format!("file://{}", path.display());
+    client.open(&uri, &std::fs::read_to_string(&path).unwrap());
+
+    let definition = client.at("definition", &uri, 6, 11);
+    assert!(
+        definition.contains("examples/Geometry/Vector.compost"),
+        "{}",
+        definition
+    );
+    assert!(
+        definition.contains(r#""start":{"line":0,"character":0}"#),
+        "{}",
+        definition
+    );
+
+    assert!(client.exit().success());
+}
+
+#[test]
+fn test_completion() {
+    let mut client = Client::start();
+    client.open(URI, CODE);
+
+    // After the dot of a let call, with the name of the trait partly typed.
+    let completion = client.at("completion", URI, 16, 16);
+    assert!(
+        completion.contains(r#"{"label":"Moved","kind":2,"detail":"Point\\Moved(dx: "#),
+        "{}",
+        completion
+    );
+    assert!(completion.contains(r#""label":"X""#), "{}", completion);
+
+    // After the dot of a local, as it is being typed.
+    client.change(URI, &CODE.replace("Origin.Moved(dx: by)", "by."));
+    let completion = client.at("completion", URI, 16, 11);
+    assert!(
+        completion.contains(r#""label":"Add","kind":2,"detail":"Op\\Add(rhs: "#),
+        "{}",
+        completion
+    );
+
+    // Not after a dot.
+    let completion = client.at("completion", URI, 19, 9);
+    assert!(completion.contains(r#""result":[]"#), "{}", completion);
+
+    assert!(client.exit().success());
+}
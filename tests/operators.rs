@@ -0,0 +1,93 @@
+use compost::run::run_code;
+
+/// Runs the expression as the body of Main, which is a number or a string.
+fn evaluate(expression: &str) -> String {
+    let code = format!("lets\n    Main: Int | String\n        {}\n", expression);
+
+    run_code(&code).unwrap()
+}
+
+#[test]
+fn test_arithmetic() {
+    assert_eq!(evaluate("10 - 2 - 3"), "5");
+    assert_eq!(evaluate("100 / 10 / 5"), "2");
+    assert_eq!(evaluate("1 + 2 * 3 - 8 / 4"), "5");
+    assert_eq!(evaluate("2 * 3 + 4 * 5"), "26");
+    assert_eq!(evaluate("10 - (2 - 3)"), "11");
+    assert_eq!(evaluate("2 * (3 + 4) * 5"), "70");
+    assert_eq!(evaluate("-2 + 3"), "1");
+    assert_eq!(evaluate("1 - -(2 + 3) * 2"), "11");
+}
+
+#[test]
+fn test_comparisons_and_logic() {
+    assert_eq!(evaluate("if 1 + 2 * 3 = 7 then 'yes' else 'no'"), "yes");
+    assert_eq!(evaluate("if 2 * 3 > 1 + 4 then 'yes' else 'no'"), "yes");
+    assert_eq!(
+        evaluate("if 1 < 2 & 3 > 4 | 2 = 2 then 'yes' else 'no'"),
+        "yes"
+    );
+    assert_eq!(
+        evaluate("if 1 > 2 | 3 > 4 & 2 = 2 then 'yes' else 'no'"),
+        "no"
+    );
+    assert_eq!(evaluate("if 1 = 1 = true then 'yes' else 'no'"), "yes");
+}
+
+#[test]
+fn test_negation_and_compound_comparisons() {
+    assert_eq!(evaluate("if 1 != 2 then 'yes' else 'no'"), "yes");
+    assert_eq!(evaluate("if 2 <= 2 & 3 >= 4 then 'yes' else 'no'"), "no");
+    assert_eq!(evaluate("if !(1 > 2) then 'yes' else 'no'"), "yes");
+    assert_eq!(evaluate("if not true | false then 'yes' else 'no'"), "no");
+    assert_eq!(evaluate("if 'a' != 'b' then 'yes' else 'no'"), "yes");
+}
+
+#[test]
+fn test_default_comparisons() {
+    let code = "\
+mod Version
+    class
+        number: Int
+    traits
+        Number: Int
+    defs
+        Number: number
+        Op\\Eq: number = rhs.Number
+        Op\\Lt: number < rhs.Number
+
+mod Pair
+    class
+        x: Int
+        y: Int
+    traits
+        X: Int
+        Y: Int
+    defs
+        X: x
+        Y: y
+        Op\\Eq: x = rhs.X & y = rhs.Y
+        Op\\Lt: x < rhs.X & y < rhs.Y
+
+lets
+    Check: (condition: Bool) -> String
+        if condition then 'y' else 'n'
+    Main: String
+        Check(condition: Version(number: 2) <= Version(number: 2))
+        + Check(condition: Version(number: 3) != Version(number: 2))
+        + Check(condition: Version(number: 3) >= Version(number: 2))
+        + Check(condition: Version(number: 1) >= Version(number: 2))
+        + Check(condition: Pair(x: 2, y: 2) >= Pair(x: 1, y: 1))
+        + Check(condition: Pair(x: 1, y: 2) >= Pair(x: 2, y: 1))
+        + Check(condition: Pair(x: 1, y: 2) <= Pair(x: 2, y: 1))
+";
+
+    // Pairs which aren't ordered are neither greater than nor less than each other.
+    assert_eq!(run_code(code).unwrap(), "yyynynn");
+}
+
+#[test]
+fn test_lines() {
+    assert_eq!(evaluate("1\n        - 2\n        - 3"), "-4");
+    assert_eq!(evaluate("'a' + 'b'\n        + 'c'"), "abc");
+}
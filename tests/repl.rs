@@ -0,0 +1,112 @@
+use compost::repl::Repl;
+
+#[test]
+fn test_expressions() {
+    let mut repl = Repl::new();
+
+    assert_eq!(repl.input("1 + 2"), "3");
+    assert_eq!(repl.input("'a' + 'b'"), "ab");
+    assert_eq!(repl.input("if 1 > 0 then 'Yes' else 'No'"), "Yes");
+    assert_eq!(repl.input("'''\nIt's\n  here\n'''"), "It's\n  here");
+}
+
+#[test]
+fn test_declarations() {
+    let mut repl = Repl::new();
+
+    let declaration = r#"mod Point
+    class
+        x: Int
+        y: Int
+    traits
+        Sum: Int
+    defs
+        Sum: x + y"#;
+
+    assert_eq!(repl.input(declaration), "");
+    assert_eq!(
+        repl.input("lets\n    Origin: Point\n        Point(x: 0, y: 0)"),
+        ""
+    );
+    assert_eq!(repl.input("Point(x: 1, y: 2).Sum"), "3");
+    assert_eq!(repl.input("Origin.Sum"), "0");
+
+    // A failed declaration is not kept.
+    assert!(repl
+        .input("lets\n    Origin: Point\n        Point(x: 1, y: 1)")
+        .starts_with("error[C0005]: Let 'Origin' was declared twice"));
+    assert_eq!(repl.input("lets\n    Two: Int\n        1 + 1"), "");
+    assert_eq!(repl.input("Two + Origin.Sum"), "2");
+
+    // A private module spans several lines as well.
+    assert_eq!(
+        repl.input("private mod Size\n    class\n        value: Int"),
+        ""
+    );
+    assert!(repl
+        .input("Size(value: 1)")
+        .starts_with("error[C0017]: Let 'Size' is private"));
+}
+
+#[test]
+fn test_imports() {
+    let mut repl = Repl::new();
+
+    // Imports are relative to the working directory.
+    assert!(repl
+        .input("import Missing")
+        .starts_with("error[C0015]: Unable to import 'Missing'"));
+    assert_eq!(repl.input("1"), "1");
+}
+
+#[test]
+fn test_types() {
+    let mut repl = Repl::new();
+
+    assert_eq!(repl.input(":type 1 + 2"), "int");
+    assert_eq!(repl.input(":type 'a' = 'b'"), "bool");
+}
+
+#[test]
+fn test_errors() {
+    let mut repl = Repl::new();
+
+    assert!(repl.input("1 / 0").starts_with("error: Division by zero"));
+    assert!(repl
+        .input("Nothing")
+        .starts_with("error[C0004]: No resolution for Let 'Nothing'"));
+    assert!(repl.input("1 2").starts_with("error[C0002]: Unexpected"));
+    assert!(repl.input(":what").starts_with("Unknown command"));
+}
+
+#[test]
+fn test_needs_more() {
+    assert!(Repl::needs_more("lets"));
+    assert!(Repl::needs_more("lets\n    Two: 1 + 1"));
+    assert!(Repl::needs_more("private mod Point"));
+    assert!(Repl::needs_more(
+        "private mod Point\n    class\n        x: Int"
+    ));
+    assert!(!Repl::needs_more("import Geometry"));
+    assert!(Repl::needs_more("Point(x: 1"));
+    assert!(!Repl::needs_more("Point(x: 1, y: 2)"));
+    assert!(!Repl::needs_more("1 + 2"));
+    assert!(!Repl::needs_more(":type 1 + 2"));
+    assert!(Repl::needs_more(":type Point(x: 1"));
+    assert!(Repl::needs_more("'''\nHello"));
+}
+
+#[test]
+fn test_incomplete_entries() {
+    let mut repl = Repl::new();
+
+    // An entry which is cut off doesn't end the session.
+    assert!(repl
+        .input("mod Name\n    class(v")
+        .starts_with("error[C0002]: Unexpected"));
+    assert!(repl.input("class(").starts_with("error"));
+    assert!(repl
+        .input("lets\n    Big: Int\n        18446744073709551615")
+        .starts_with("error[C0020]"));
+    assert_eq!(repl.input("1 + 2"), "3");
+}
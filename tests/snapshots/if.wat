@@ -0,0 +1,586 @@
+(module
+  (type $arity_0 (func (param i32) (result i32)))
+  (type $arity_1 (func (param i32 i32) (result i32)))
+
+  (global $num_traits i32 (i32.const 2))
+  (global $string_trait i32 (i32.const 0))
+  (global $void i32 (i32.const 16))
+  (global $trait_names i32 (i32.const 808))
+  (global $raw_ops i32 (i32.const 816))
+  (global $class_names i32 (i32.const 824))
+  (global $class_sizes i32 (i32.const 824))
+  (global $class_is_string i32 (i32.const 824))
+  (global $class_value_slots i32 (i32.const 824))
+  (global $vtable_defined i32 (i32.const 824))
+  (global $str_int i32 (i32.const 160))
+  (global $str_string i32 (i32.const 184))
+  (global $str_bool i32 (i32.const 208))
+  (global $str_unknown i32 (i32.const 232))
+  (global $str_true i32 (i32.const 256))
+  (global $str_false i32 (i32.const 280))
+  (global $str_overflow i32 (i32.const 312))
+  (global $str_division_by_zero i32 (i32.const 344))
+  (global $str_operation i32 (i32.const 376))
+  (global $str_not_supported_by i32 (i32.const 416))
+  (global $str_non_raw_rhs i32 (i32.const 464))
+  (global $str_non_raw_value i32 (i32.const 496))
+  (global $str_call_trait i32 (i32.const 536))
+  (global $str_on_void i32 (i32.const 568))
+  (global $str_trait i32 (i32.const 592))
+  (global $str_not_defined i32 (i32.const 648))
+  (global $str_field i32 (i32.const 672))
+  (global $str_does_not_exist i32 (i32.const 704))
+  (global $str_not_a_bool i32 (i32.const 760))
+  (global $str_out_of_memory i32 (i32.const 792))
+  (global $heap (mut i32) (i32.const 824))
+
+  (table 1 funcref)
+
+  ;; The runtime of a compiled Compost program.
+  ;;
+  ;; Every value is a record in linear memory, starting with its kind:
+  ;; void (0), int (1, i64 at +8), string (2, bytes at +4, length at +8), bool (3, i32 at +4)
+  ;; or object (4, class at +4, slots at +8). Memory is never freed.
+
+  (global $error (mut i32) (i32.const 0))
+
+  (func $alloc (param $size i32) (result i32)
+    (local $address i32)
+    (local $pages i32)
+    (local.set $address (global.get $heap))
+    (global.set $heap
+      (i32.and
+        (i32.add (i32.add (local.get $address) (local.get $size)) (i32.const 7))
+        (i32.const -8)))
+    (if (i32.gt_u (global.get $heap) (i32.shl (memory.size) (i32.const 16)))
+      (then
+        (local.set $pages
+          (i32.add
+            (i32.shr_u
+              (i32.sub (global.get $heap) (i32.shl (memory.size) (i32.const 16)))
+              (i32.const 16))
+            (i32.const 1)))
+        (if (i32.eq (memory.grow (local.get $pages)) (i32.const -1))
+          (then (call $fail (global.get $str_out_of_memory))))))
+    (local.get $address))
+
+  ;; Stops the program. The message can be read through the exported 'error' function.
+  (func $fail (param $message i32)
+    (global.set $error (local.get $message))
+    (unreachable))
+
+  (func $kind (param $value i32) (result i32)
+    (i32.load (local.get $value)))
+
+  (func $table (param $table i32) (param $index i32) (result i32)
+    (i32.load (i32.add (local.get $table) (i32.shl (local.get $index) (i32.const 2)))))
+
+  (func $make_int (param $integer i64) (result i32)
+    (local $value i32)
+    (local.set $value (call $alloc (i32.const 16)))
+    (i32.store (local.get $value) (i32.const 1))
+    (i64.store offset=8 (local.get $value) (local.get $integer))
+    (local.get $value))
+
+  (func $make_string (param $bytes i32) (param $length i32) (result i32)
+    (local $value i32)
+    (local.set $value (call $alloc (i32.const 16)))
+    (i32.store (local.get $value) (i32.const 2))
+    (i32.store offset=4 (local.get $value) (local.get $bytes))
+    (i32.store offset=8 (local.get $value) (local.get $length))
+    (local.get $value))
+
+  (func $make_bool (param $boolean i32) (result i32)
+    (local $value i32)
+    (local.set $value (call $alloc (i32.const 16)))
+    (i32.store (local.get $value) (i32.const 3))
+    (i32.store offset=4 (local.get $value) (local.get $boolean))
+    (local.get $value))
+
+  (func $concat (param $a i32) (param $b i32) (result i32)
+    (local $a_length i32)
+    (local $b_length i32)
+    (local $bytes i32)
+    (local.set $a_length (i32.load offset=8 (local.get $a)))
+    (local.set $b_length (i32.load offset=8 (local.get $b)))
+    (local.set $bytes (call $alloc (i32.add (local.get $a_length) (local.get $b_length))))
+    (memory.copy (local.get $bytes) (i32.load offset=4 (local.get $a)) (local.get $a_length))
+    (memory.copy
+      (i32.add (local.get $bytes) (local.get $a_length))
+      (i32.load offset=4 (local.get $b))
+      (local.get $b_length))
+    (call $make_string (local.get $bytes) (i32.add (local.get $a_length) (local.get $b_length))))
+
+  (func $string_eq (param $a i32) (param $b i32) (result i32)
+    (local $length i32)
+    (local $index i32)
+    (local.set $length (i32.load offset=8 (local.get $a)))
+    (if (i32.ne (local.get $length) (i32.load offset=8 (local.get $b)))
+      (then (return (i32.const 0))))
+    (block $done
+      (loop $compare
+        (br_if $done (i32.ge_u (local.get $index) (local.get $length)))
+        (if (i32.ne
+              (i32.load8_u (i32.add (i32.load offset=4 (local.get $a)) (local.get $index)))
+              (i32.load8_u (i32.add (i32.load offset=4 (local.get $b)) (local.get $index))))
+          (then (return (i32.const 0))))
+        (local.set $index (i32.add (local.get $index) (i32.const 1)))
+        (br $compare)))
+    (i32.const 1))
+
+  (func $int_to_string (param $integer i64) (result i32)
+    (local $bytes i32)
+    (local $position i32)
+    (local $magnitude i64)
+    (local.set $bytes (call $alloc (i32.const 20)))
+    (local.set $position (i32.const 20))
+    (local.set $magnitude
+      (select
+        (i64.sub (i64.const 0) (local.get $integer))
+        (local.get $integer)
+        (i64.lt_s (local.get $integer) (i64.const 0))))
+    (loop $digits
+      (local.set $position (i32.sub (local.get $position) (i32.const 1)))
+      (i32.store8
+        (i32.add (local.get $bytes) (local.get $position))
+        (i32.add
+          (i32.const 48)
+          (i32.wrap_i64 (i64.rem_u (local.get $magnitude) (i64.const 10)))))
+      (local.set $magnitude (i64.div_u (local.get $magnitude) (i64.const 10)))
+      (br_if $digits (i64.ne (local.get $magnitude) (i64.const 0))))
+    (if (i64.lt_s (local.get $integer) (i64.const 0))
+      (then
+        (local.set $position (i32.sub (local.get $position) (i32.const 1)))
+        (i32.store8 (i32.add (local.get $bytes) (local.get $position)) (i32.const 45))))
+    (call $make_string
+      (i32.add (local.get $bytes) (local.get $position))
+      (i32.sub (i32.const 20) (local.get $position))))
+
+  (func $raw_to_string (param $value i32) (result i32)
+    (block $unknown
+      (block $bool
+        (block $string
+          (block $int
+            (br_table $unknown $int $string $bool $unknown (call $kind (local.get $value))))
+          (return (call $int_to_string (i64.load offset=8 (local.get $value)))))
+        (return (local.get $value)))
+      (return
+        (select
+          (global.get $str_true)
+          (global.get $str_false)
+          (i32.load offset=4 (local.get $value)))))
+    (global.get $str_unknown))
+
+  (func $type_name (param $value i32) (result i32)
+    (block $unknown
+      (block $object
+        (block $bool
+          (block $string
+            (block $int
+              (br_table $unknown $int $string $bool $object (call $kind (local.get $value))))
+            (return (global.get $str_int)))
+          (return (global.get $str_string)))
+        (return (global.get $str_bool)))
+      (return (call $table (global.get $class_names) (i32.load offset=4 (local.get $value)))))
+    (global.get $str_unknown))
+
+  (func $fail_unsupported_by (param $trait i32) (param $type_name i32)
+    (call $fail
+      (call $concat
+        (call $concat
+          (call $concat
+            (global.get $str_operation)
+            (call $table (global.get $trait_names) (local.get $trait)))
+          (global.get $str_not_supported_by))
+        (local.get $type_name))))
+
+  ;; The right-hand side of a raw operation, which must have the same raw type as the subject.
+  (func $rhs_value (param $trait i32) (param $rhs i32) (param $kind i32) (result i32)
+    (if (i32.or
+          (i32.eq (call $kind (local.get $rhs)) (i32.const 0))
+          (i32.eq (call $kind (local.get $rhs)) (i32.const 4)))
+      (then (call $fail_unsupported_by (local.get $trait) (global.get $str_non_raw_rhs))))
+    (if (i32.ne (call $kind (local.get $rhs)) (local.get $kind))
+      (then
+        (call $fail_unsupported_by (local.get $trait) (call $type_name (local.get $rhs)))))
+    (local.get $rhs))
+
+  (func $checked (param $result i64) (param $overflow i32) (result i32)
+    (if (local.get $overflow)
+      (then (call $fail (global.get $str_overflow))))
+    (call $make_int (local.get $result)))
+
+  (func $raw_operation (param $trait i32) (param $value i32) (param $rhs i32) (result i32)
+    (local $op i32)
+    (local $a i64)
+    (local $b i64)
+    (local $result i64)
+    (local.set $op (call $table (global.get $raw_ops) (local.get $trait)))
+
+    ;; String
+    (if (i32.eq (local.get $op) (i32.const 11))
+      (then (return (call $raw_to_string (local.get $value)))))
+
+    (block $unsupported
+      (block $bool
+        (block $string
+          (block $int
+            (br_table $unsupported $int $string $bool $unsupported (call $kind (local.get $value))))
+          (local.set $a (i64.load offset=8 (local.get $value)))
+
+          ;; Neg
+          (if (i32.eq (local.get $op) (i32.const 5))
+            (then
+              (return
+                (call $checked
+                  (i64.sub (i64.const 0) (local.get $a))
+                  (i64.eq (local.get $a) (i64.const 0x8000000000000000))))))
+
+          (br_if $unsupported
+            (i32.or
+              (i32.or
+                (i32.eqz (local.get $op))
+                (i32.eq (local.get $op) (i32.const 15)))
+              (i32.and
+                (i32.ge_u (local.get $op) (i32.const 9))
+                (i32.le_u (local.get $op) (i32.const 11)))))
+          (local.set $b
+            (i64.load offset=8 (call $rhs_value (local.get $trait) (local.get $rhs) (i32.const 1))))
+
+          ;; Add
+          (if (i32.eq (local.get $op) (i32.const 1))
+            (then
+              (local.set $result (i64.add (local.get $a) (local.get $b)))
+              (return
+                (call $checked
+                  (local.get $result)
+                  (i64.lt_s
+                    (i64.and
+                      (i64.xor (local.get $a) (local.get $result))
+                      (i64.xor (local.get $b) (local.get $result)))
+                    (i64.const 0))))))
+          ;; Sub
+          (if (i32.eq (local.get $op) (i32.const 2))
+            (then
+              (local.set $result (i64.sub (local.get $a) (local.get $b)))
+              (return
+                (call $checked
+                  (local.get $result)
+                  (i64.lt_s
+                    (i64.and
+                      (i64.xor (local.get $a) (local.get $b))
+                      (i64.xor (local.get $a) (local.get $result)))
+                    (i64.const 0))))))
+          ;; Mul
+          (if (i32.eq (local.get $op) (i32.const 3))
+            (then
+              (if (i64.eqz (local.get $a))
+                (then (return (call $make_int (i64.const 0)))))
+              (if (i32.and
+                    (i64.eq (local.get $a) (i64.const -1))
+                    (i64.eq (local.get $b) (i64.const 0x8000000000000000)))
+                (then (call $fail (global.get $str_overflow))))
+              (local.set $result (i64.mul (local.get $a) (local.get $b)))
+              (return
+                (call $checked
+                  (local.get $result)
+                  (i64.ne (i64.div_s (local.get $result) (local.get $a)) (local.get $b))))))
+          ;; Div
+          (if (i32.eq (local.get $op) (i32.const 4))
+            (then
+              (if (i64.eqz (local.get $b))
+                (then (call $fail (global.get $str_division_by_zero))))
+              (if (i32.and
+                    (i64.eq (local.get $a) (i64.const 0x8000000000000000))
+                    (i64.eq (local.get $b) (i64.const -1)))
+                (then (call $fail (global.get $str_overflow))))
+              (return (call $make_int (i64.div_s (local.get $a) (local.get $b))))))
+          ;; Eq
+          (if (i32.eq (local.get $op) (i32.const 6))
+            (then (return (call $make_bool (i64.eq (local.get $a) (local.get $b))))))
+          ;; Lt
+          (if (i32.eq (local.get $op) (i32.const 7))
+            (then (return (call $make_bool (i64.lt_s (local.get $a) (local.get $b))))))
+          ;; Ne
+          (if (i32.eq (local.get $op) (i32.const 12))
+            (then (return (call $make_bool (i64.ne (local.get $a) (local.get $b))))))
+          ;; Lte
+          (if (i32.eq (local.get $op) (i32.const 13))
+            (then (return (call $make_bool (i64.le_s (local.get $a) (local.get $b))))))
+          ;; Gte
+          (if (i32.eq (local.get $op) (i32.const 14))
+            (then (return (call $make_bool (i64.ge_s (local.get $a) (local.get $b))))))
+          ;; Gt
+          (return (call $make_bool (i64.gt_s (local.get $a) (local.get $b)))))
+
+        ;; Add
+        (if (i32.eq (local.get $op) (i32.const 1))
+          (then
+            (return
+              (call $concat
+                (local.get $value)
+                (call $rhs_value (local.get $trait) (local.get $rhs) (i32.const 2))))))
+        ;; Eq
+        (if (i32.eq (local.get $op) (i32.const 6))
+          (then
+            (return
+              (call $make_bool
+                (call $string_eq
+                  (local.get $value)
+                  (call $rhs_value (local.get $trait) (local.get $rhs) (i32.const 2)))))))
+        ;; Lt
+        (if (i32.eq (local.get $op) (i32.const 7))
+          (then
+            (return
+              (call $make_bool
+                (i32.lt_u
+                  (i32.load offset=8 (local.get $value))
+                  (i32.load offset=8
+                    (call $rhs_value (local.get $trait) (local.get $rhs) (i32.const 2))))))))
+        ;; Gt
+        (if (i32.eq (local.get $op) (i32.const 8))
+          (then
+            (return
+              (call $make_bool
+                (i32.gt_u
+                  (i32.load offset=8 (local.get $value))
+                  (i32.load offset=8
+                    (call $rhs_value (local.get $trait) (local.get $rhs) (i32.const 2))))))))
+        ;; Ne
+        (if (i32.eq (local.get $op) (i32.const 12))
+          (then
+            (return
+              (call $make_bool
+                (i32.eqz
+                  (call $string_eq
+                    (local.get $value)
+                    (call $rhs_value (local.get $trait) (local.get $rhs) (i32.const 2))))))))
+        ;; Lte
+        (if (i32.eq (local.get $op) (i32.const 13))
+          (then
+            (return
+              (call $make_bool
+                (i32.le_u
+                  (i32.load offset=8 (local.get $value))
+                  (i32.load offset=8
+                    (call $rhs_value (local.get $trait) (local.get $rhs) (i32.const 2))))))))
+        ;; Gte
+        (if (i32.eq (local.get $op) (i32.const 14))
+          (then
+            (return
+              (call $make_bool
+                (i32.ge_u
+                  (i32.load offset=8 (local.get $value))
+                  (i32.load offset=8
+                    (call $rhs_value (local.get $trait) (local.get $rhs) (i32.const 2))))))))
+        (br $unsupported))
+
+      ;; Eq
+      (if (i32.eq (local.get $op) (i32.const 6))
+        (then
+          (return
+            (call $make_bool
+              (i32.eq
+                (i32.load offset=4 (local.get $value))
+                (i32.load offset=4
+                  (call $rhs_value (local.get $trait) (local.get $rhs) (i32.const 3))))))))
+      ;; Ne
+      (if (i32.eq (local.get $op) (i32.const 12))
+        (then
+          (return
+            (call $make_bool
+              (i32.ne
+                (i32.load offset=4 (local.get $value))
+                (i32.load offset=4
+                  (call $rhs_value (local.get $trait) (local.get $rhs) (i32.const 3))))))))
+      ;; Not
+      (if (i32.eq (local.get $op) (i32.const 15))
+        (then (return (call $make_bool (i32.eqz (i32.load offset=4 (local.get $value)))))))
+      ;; And
+      (if (i32.eq (local.get $op) (i32.const 9))
+        (then
+          (return
+            (call $make_bool
+              (i32.and
+                (i32.load offset=4 (local.get $value))
+                (i32.load offset=4
+                  (call $rhs_value (local.get $trait) (local.get $rhs) (i32.const 3))))))))
+      ;; Or
+      (if (i32.eq (local.get $op) (i32.const 10))
+        (then
+          (return
+            (call $make_bool
+              (i32.or
+                (i32.load offset=4 (local.get $value))
+                (i32.load offset=4
+                  (call $rhs_value (local.get $trait) (local.get $rhs) (i32.const 3)))))))))
+
+    (call $fail_unsupported_by (local.get $trait) (call $type_name (local.get $value)))
+    (unreachable))
+
+  ;; Looks up the index of a trait definition in the function table.
+  (func $vtable_index (param $trait i32) (param $subject i32) (result i32)
+    (local $index i32)
+    (if (i32.eqz (call $kind (local.get $subject)))
+      (then
+        (call $fail
+          (call $concat
+            (call $concat
+              (global.get $str_call_trait)
+              (call $table (global.get $trait_names) (local.get $trait)))
+            (global.get $str_on_void)))))
+    (local.set $index
+      (i32.add
+        (i32.mul (i32.load offset=4 (local.get $subject)) (global.get $num_traits))
+        (local.get $trait)))
+    (if (i32.eqz (call $table (global.get $vtable_defined) (local.get $index)))
+      (then
+        (call $fail
+          (call $concat
+            (call $concat
+              (global.get $str_trait)
+              (call $table (global.get $trait_names) (local.get $trait)))
+            (global.get $str_not_defined)))))
+    (local.get $index))
+
+  (func $new_object (param $class i32) (result i32)
+    (local $value i32)
+    (local.set $value
+      (call $alloc
+        (i32.add
+          (i32.const 8)
+          (i32.shl (call $table (global.get $class_sizes) (local.get $class)) (i32.const 2)))))
+    (i32.store (local.get $value) (i32.const 4))
+    (i32.store offset=4 (local.get $value) (local.get $class))
+    (local.get $value))
+
+  ;; Checks that a field of a struct is a raw value.
+  (func $check_raw (param $class i32) (param $value i32) (result i32)
+    (if (i32.or
+          (i32.eqz (call $kind (local.get $value)))
+          (i32.eq (call $kind (local.get $value)) (i32.const 4)))
+      (then
+        (call $fail
+          (call $concat
+            (call $concat
+              (call $concat
+                (global.get $str_operation)
+                (call $table (global.get $class_names) (local.get $class)))
+              (global.get $str_not_supported_by))
+            (global.get $str_non_raw_value)))))
+    (local.get $value))
+
+  (func $friendly_field (param $value i32) (param $class i32) (param $slot i32) (param $name i32)
+    (result i32)
+    (if (i32.or
+          (i32.ne (call $kind (local.get $value)) (i32.const 4))
+          (i32.ne (i32.load offset=4 (local.get $value)) (local.get $class)))
+      (then
+        (call $fail
+          (call $concat
+            (call $concat (global.get $str_field) (local.get $name))
+            (global.get $str_does_not_exist)))))
+    (i32.load offset=8
+      (i32.add (local.get $value) (i32.shl (local.get $slot) (i32.const 2)))))
+
+  (func $to_bool (param $value i32) (result i32)
+    (local $slot i32)
+    (if (i32.eq (call $kind (local.get $value)) (i32.const 4))
+      (then
+        (local.set $slot
+          (call $table (global.get $class_value_slots) (i32.load offset=4 (local.get $value))))
+        (if (i32.ge_s (local.get $slot) (i32.const 0))
+          (then
+            (local.set $value
+              (i32.load offset=8
+                (i32.add (local.get $value) (i32.shl (local.get $slot) (i32.const 2)))))))))
+    (if (i32.ne (call $kind (local.get $value)) (i32.const 3))
+      (then (call $fail (global.get $str_not_a_bool))))
+    (i32.load offset=4 (local.get $value)))
+
+  (func $has_trait (param $value i32) (param $trait i32) (result i32)
+    (if (i32.ne (call $kind (local.get $value)) (i32.const 4))
+      (then (return (i32.const 0))))
+    (call $table
+      (global.get $vtable_defined)
+      (i32.add
+        (i32.mul (i32.load offset=4 (local.get $value)) (global.get $num_traits))
+        (local.get $trait))))
+
+  (func $same_type (param $a i32) (param $b i32) (result i32)
+    (if (i32.ne (call $kind (local.get $a)) (call $kind (local.get $b)))
+      (then (return (i32.const 0))))
+    (if (i32.ne (call $kind (local.get $a)) (i32.const 4))
+      (then (return (i32.const 1))))
+    (i32.eq (i32.load offset=4 (local.get $a)) (i32.load offset=4 (local.get $b))))
+
+  ;; Calls the String trait until the value is turned into an actual string.
+  (func $to_string (param $value i32) (result i32)
+    (local $first i32)
+    (loop $resolve
+      (if (i32.and
+            (i32.ne (call $kind (local.get $value)) (i32.const 0))
+            (i32.ne (call $kind (local.get $value)) (i32.const 4)))
+        (then (return (call $raw_to_string (local.get $value)))))
+      (if (i32.eq (call $kind (local.get $value)) (i32.const 4))
+        (then
+          (if (call $table (global.get $class_is_string) (i32.load offset=4 (local.get $value)))
+            (then
+              (local.set $first (i32.load offset=8 (local.get $value)))
+              (if (i32.eq (call $kind (local.get $first)) (i32.const 2))
+                (then (return (local.get $first))))))))
+      (local.set $value (call $call_trait_0 (global.get $string_trait) (local.get $value)))
+      (br $resolve))
+    (unreachable))
+
+  (func $call_trait_0 (param $trait i32) (param $subject i32) (result i32)
+    (if (i32.and
+          (i32.ne (call $kind (local.get $subject)) (i32.const 0))
+          (i32.ne (call $kind (local.get $subject)) (i32.const 4)))
+      (then
+        (return (call $raw_operation (local.get $trait) (local.get $subject) (local.get $subject)))))
+    (call_indirect (type $arity_0) (local.get $subject)
+      (call $vtable_index (local.get $trait) (local.get $subject))))
+
+  (func $call_trait_1 (param $trait i32) (param $subject i32) (param $i0 i32) (result i32)
+    (if (i32.and
+          (i32.ne (call $kind (local.get $subject)) (i32.const 0))
+          (i32.ne (call $kind (local.get $subject)) (i32.const 4)))
+      (then
+        (return (call $raw_operation (local.get $trait) (local.get $subject) (local.get $i0)))))
+    (call_indirect (type $arity_1) (local.get $subject) (local.get $i0)
+      (call $vtable_index (local.get $trait) (local.get $subject))))
+
+  ;; Main
+  (func $f0 (param $self i32) (result i32)
+    (local $s0 i32)
+    (local $s1 i32)
+    (local $s2 i32)
+    (local $object i32)
+    (block $L7
+    (block $L6
+    (local.set $s0 (i32.const 24))
+    (local.set $s1 (i32.const 40))
+    (local.set $s0 (call $call_trait_1 (i32.const 1) (local.get $s0) (local.get $s1)))
+    (br_if $L6 (i32.eqz (call $to_bool (local.get $s0))))
+    (local.set $s0 (i32.const 64))
+    (br $L7)
+    ) ;; $L6
+    (local.set $s0 (i32.const 88))
+    ) ;; $L7
+    (return (local.get $s0))
+    (unreachable))
+
+  ;; Returns a pointer to an (address, length) pair of the string result.
+  (func (export "main") (result i32)
+    (i32.add (call $to_string (call $f0 (i32.const 0))) (i32.const 4)))
+
+  ;; Returns a pointer to an (address, length) pair of the error message, or 0.
+  (func (export "error") (result i32)
+    (if (i32.eqz (global.get $error))
+      (then (return (i32.const 0))))
+    (i32.add (global.get $error) (i32.const 4)))
+
+  (memory (export "memory") 2)
+  (data (i32.const 16) "\00\00\00\00\00\00\00\00\01\00\00\00\00\00\00\00\01\00\00\00\00\00\00\00\01\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00Yes\00\00\00\00\00\02\00\00\008\00\00\00\03\00\00\00\00\00\00\00No\00\00\00\00\00\00\02\00\00\00P\00\00\00\02\00\00\00\00\00\00\00String\00\00\02\00\00\00h\00\00\00\06\00\00\00\00\00\00\00Op\5cGt\00\00\00\02\00\00\00\80\00\00\00\05\00\00\00\00\00\00\00int\00\00\00\00\00\02\00\00\00\98\00\00\00\03\00\00\00\00\00\00\00string\00\00\02\00\00\00\b0\00\00\00\06\00\00\00\00\00\00\00bool\00\00\00\00\02\00\00\00\c8\00\00\00\04\00\00\00\00\00\00\00?\00\00\00\00\00\00\00\02\00\00\00\e0\00\00\00\01\00\00\00\00\00\00\00true\00\00\00\00\02\00\00\00\f8\00\00\00\04\00\00\00\00\00\00\00false\00\00\00\02\00\00\00\10\01\00\00\05\00\00\00\00\00\00\00Integer overflow\02\00\00\00(\01\00\00\10\00\00\00\00\00\00\00Division by zero\02\00\00\00H\01\00\00\10\00\00\00\00\00\00\00Operation '\00\00\00\00\00\02\00\00\00h\01\00\00\0b\00\00\00\00\00\00\00' is not supported by \00\00\02\00\00\00\88\01\00\00\16\00\00\00\00\00\00\00a non-raw right-hand side\00\00\00\00\00\00\00\02\00\00\00\b0\01\00\00\19\00\00\00\00\00\00\00a non-raw value\00\02\00\00\00\e0\01\00\00\0f\00\00\00\00\00\00\00Can't call trait '\00\00\00\00\00\00\02\00\00\00\00\02\00\00\12\00\00\00\00\00\00\00' on void\00\00\00\00\00\00\00\02\00\00\00(\02\00\00\09\00\00\00\00\00\00\00Trait '\00\02\00\00\00H\02\00\00\07\00\00\00\00\00\00\00' is not defined on this instance\00\00\00\00\00\00\00\02\00\00\00`\02\00\00!\00\00\00\00\00\00\00Field '\00\02\00\00\00\98\02\00\00\07\00\00\00\00\00\00\00' does not exist\02\00\00\00\b0\02\00\00\10\00\00\00\00\00\00\00Value can't be resolved to a bool\00\00\00\00\00\00\00\02\00\00\00\d0\02\00\00!\00\00\00\00\00\00\00Out of memory\00\00\00\02\00\00\00\08\03\00\00\0d\00\00\00\00\00\00\00p\00\00\00\88\00\00\00\0b\00\00\00\08\00\00\00")
+)
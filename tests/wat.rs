@@ -0,0 +1,52 @@
+use compost::run::{compile_code_to_wat, compile_file_to_wat};
+use std::{env, fs};
+
+/// Compares the output with a snapshot file in `tests/snapshots`.
+/// Run with `UPDATE_SNAPSHOTS=1` to update the snapshots after an intended change.
+fn assert_snapshot(name: &str, actual: &str) {
+    let path = format!("tests/snapshots/{}.wat", name);
+
+    if env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::write(&path, actual).unwrap();
+    }
+
+    let expected = fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("Missing snapshot {}, run with UPDATE_SNAPSHOTS=1", path));
+
+    assert!(actual == expected, "{} does not match the snapshot", name);
+}
+
+#[test]
+fn test_if() {
+    assert_snapshot("if", &compile_file_to_wat("examples/if.compost").unwrap())
+}
+
+#[test]
+fn test_functions_and_constants() {
+    assert_snapshot(
+        "functions_and_constants",
+        &compile_file_to_wat("examples/functions_and_constants.compost").unwrap(),
+    )
+}
+
+#[test]
+fn test_exports() {
+    let wat = compile_file_to_wat("examples/classes.compost").unwrap();
+
+    assert!(wat.contains("(func (export \"main\") (result i32)"));
+    assert!(wat.contains("(func (export \"error\") (result i32)"));
+    assert!(wat.contains("(memory (export \"memory\")"));
+}
+
+#[test]
+fn test_missing_main() {
+    let code = r#"
+lets
+    Other: Int
+        1
+"#;
+
+    assert!(compile_code_to_wat(code).unwrap_err()[0]
+        .message
+        .starts_with("No resolution for Let 'Main'"))
+}